@@ -1,12 +1,13 @@
 #version 460
-layout(location=0) in vec2 pos;
-layout(location=1) in vec2 tex;
+layout(location=0) in vec2 corner;
+layout(location=1) in vec4 rect;
+layout(location=2) in vec4 uv;
 
 out vec2 tex_coords;
 
 layout(location=0) uniform mat4 projection;
 
 void main() {
-	tex_coords = tex;
-	gl_Position = projection * vec4(pos, 0.0, 1.0);
+	tex_coords = mix(uv.xy, uv.zw, corner);
+	gl_Position = projection * vec4(mix(rect.xy, rect.zw, corner), 0.0, 1.0);
 }
@@ -0,0 +1,354 @@
+use {
+    crate::{
+        gfx::texture::{Format, Texture, TEX_2D},
+        mem::{collections::BTreeMap, vec::Vec},
+    },
+    core::cell::Cell,
+};
+
+/// Skyline rectangle packer. Rectangles are placed as low as possible on
+/// the outline of those already placed, so space can't be freed one
+/// rectangle at a time, only by packing again.
+#[derive(Debug, Clone)]
+pub struct Packer {
+    size: [i32; 2],
+    padding: i32,
+    /// `[x, y, width]` segments of the outline, left to right
+    skyline: Vec<[i32; 3]>,
+}
+
+impl Packer {
+    /// Packer for a `size` area leaving `padding` empty pixels around every
+    /// rectangle, so filtering doesn't bleed between neighbours
+    pub fn new(size: [i32; 2], padding: i32) -> Self {
+        Self {
+            size,
+            padding,
+            skyline: crate::mem::vec![[0, 0, size[0]]],
+        }
+    }
+
+    pub fn size(&self) -> [i32; 2] {
+        self.size
+    }
+
+    /// Bottom left corner of a free `size` area, `None` if it doesn't fit
+    pub fn insert(&mut self, [w, h]: [i32; 2]) -> Option<[i32; 2]> {
+        let [w, h] = [w + 2 * self.padding, h + 2 * self.padding];
+
+        // Lowest top edge, then narrowest segment to waste the least
+        let (idx, y) = (0..self.skyline.len())
+            .filter_map(|idx| Some((idx, self.fit(idx, [w, h])?)))
+            .min_by_key(|&(idx, y)| (y + h, self.skyline[idx][2]))?;
+
+        let x = self.skyline[idx][0];
+        self.skyline.insert(idx, [x, y + h, w]);
+
+        // Shrink or drop the segments now under the new one
+        let right = x + w;
+        let next = idx + 1;
+        while next < self.skyline.len() && self.skyline[next][0] < right {
+            let [sx, _, sw] = &mut self.skyline[next];
+            if *sx + *sw <= right {
+                self.skyline.remove(next);
+            } else {
+                *sw -= right - *sx;
+                *sx = right;
+                break;
+            }
+        }
+        self.merge();
+
+        Some([x + self.padding, y + self.padding])
+    }
+
+    /// Enlarge the area, keeping everything packed so far in place
+    pub fn grow(&mut self, [w, h]: [i32; 2]) {
+        if w > self.size[0] {
+            self.skyline.push([self.size[0], 0, w - self.size[0]]);
+            self.merge();
+        }
+        self.size = [w.max(self.size[0]), h.max(self.size[1])];
+    }
+
+    pub fn clear(&mut self) {
+        self.skyline = crate::mem::vec![[0, 0, self.size[0]]];
+    }
+
+    /// Height a `[w, h]` rectangle would sit at if placed at segment `idx`
+    fn fit(&self, idx: usize, [w, h]: [i32; 2]) -> Option<i32> {
+        let x = self.skyline[idx][0];
+        if x + w > self.size[0] {
+            return None;
+        }
+
+        let y = self.skyline[idx..]
+            .iter()
+            .take_while(|&&[sx, _, _]| sx < x + w)
+            .map(|&[_, sy, _]| sy)
+            .max()?;
+
+        (y + h <= self.size[1]).then_some(y)
+    }
+
+    fn merge(&mut self) {
+        self.skyline.dedup_by(|next, prev| {
+            let merge = next[1] == prev[1];
+            if merge {
+                prev[2] += next[2];
+            }
+
+            merge
+        });
+    }
+}
+
+/// Area of an `Atlas` texture, in pixels and texture coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    /// `[x, y, width, height]` from the bottom left
+    pub rect: [i32; 4],
+    /// Bottom left and top right texture coordinates
+    pub uv: [[f32; 2]; 2],
+}
+
+/// Images of any size packed into a single texture, looked up by key.
+/// The texture grows up to a maximum size as needed, after which images
+/// unused since the last `next_frame` are evicted to make room.
+///
+/// Growing or evicting moves images, so regions should be looked up again
+/// after every insertion rather than kept.
+#[derive(Debug)]
+pub struct Atlas<K: Ord + Clone, F: Format> {
+    tex: Texture<F>,
+    packer: Packer,
+    max_size: [i32; 2],
+    entries: BTreeMap<K, Entry>,
+    frame: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    rect: [i32; 4],
+    used: Cell<u64>,
+}
+
+impl<K: Ord + Clone, F: Format> Atlas<K, F> {
+    pub fn new(size: [i32; 2], max_size: [i32; 2], padding: i32) -> Self {
+        assert!(
+            (0..2).all(|i| size[i] > 0 && size[i] <= max_size[i]),
+            "atlas size {:?} must be positive and at most {:?}",
+            size,
+            max_size
+        );
+        let tex = Texture::new(TEX_2D, size);
+        tex.clear(0);
+
+        Self {
+            tex,
+            packer: Packer::new(size, padding),
+            max_size,
+            entries: BTreeMap::new(),
+            frame: 0,
+        }
+    }
+
+    /// Reserve a `size` area for `key` to be drawn or uploaded into, or the
+    /// existing area if already present. `None` if it can't be made to fit.
+    pub fn allocate(&mut self, key: K, size: [i32; 2]) -> Option<Region> {
+        if let Some(region) = self.get(&key) {
+            return Some(region);
+        }
+
+        let pos = self.packer.insert(size).or_else(|| {
+            // Double the shorter side until it fits or can't grow further
+            while (0..2).any(|i| self.tex.size()[i] < self.max_size[i]) {
+                let [w, h] = self.tex.size();
+                let grown = if w <= h && w < self.max_size[0] || h >= self.max_size[1] {
+                    [(w * 2).min(self.max_size[0]), h]
+                } else {
+                    [w, (h * 2).min(self.max_size[1])]
+                };
+
+                self.grow(grown);
+                if let Some(pos) = self.packer.insert(size) {
+                    return Some(pos);
+                }
+            }
+
+            let frame = self.frame;
+            self.evict(|entry| entry.used.get() < frame);
+            self.packer.insert(size)
+        })?;
+
+        let [x, y] = pos;
+        self.entries.insert(
+            key.clone(),
+            Entry {
+                rect: [x, y, size[0], size[1]],
+                used: Cell::new(self.frame),
+            },
+        );
+
+        self.get(&key)
+    }
+
+    /// Allocate space for `key` and upload its pixels, bottom row first
+    pub fn insert(&mut self, key: K, size: [i32; 2], pixels: &[F::Pixel]) -> Option<Region> {
+        let region = self.allocate(key, size)?;
+        let [x, y, ..] = region.rect;
+        self.tex.update([x, y], size, pixels);
+
+        Some(region)
+    }
+
+    /// Region of `key`, marking it used this frame
+    pub fn get(&self, key: &K) -> Option<Region> {
+        let entry = self.entries.get(key)?;
+        entry.used.set(self.frame);
+
+        let [x, y, w, h] = entry.rect;
+        let [tw, th] = self.tex.size().map(|n| n as f32);
+
+        Some(Region {
+            rect: entry.rect,
+            uv: [
+                [x as f32 / tw, y as f32 / th],
+                [(x + w) as f32 / tw, (y + h) as f32 / th],
+            ],
+        })
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Forget `key`, its space being reclaimed the next time the atlas is
+    /// packed again
+    pub fn remove(&mut self, key: &K) {
+        self.entries.remove(key);
+    }
+
+    /// Start a new frame, making images not looked up since candidates for
+    /// eviction
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn texture(&self) -> &Texture<F> {
+        &self.tex
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Move to a larger texture, keeping everything in place
+    fn grow(&mut self, size: [i32; 2]) {
+        log::debug!("growing atlas {:?} to {:?}", self.tex.size(), size);
+
+        let tex = Texture::new(TEX_2D, size);
+        tex.clear(0);
+        self.tex.copy_to(&tex, [0, 0], [0, 0], self.tex.size());
+
+        self.tex = tex;
+        self.packer.grow(size);
+    }
+
+    /// Drop the entries matching `evict` and pack the rest again, tallest
+    /// first
+    fn evict(&mut self, evict: impl Fn(&Entry) -> bool) {
+        log::debug!("evicting from atlas of {} images", self.entries.len());
+
+        let size = self.tex.size();
+        let tex = Texture::new(TEX_2D, size);
+        tex.clear(0);
+        let mut packer = Packer::new(size, self.packer.padding);
+
+        let mut entries: Vec<_> = core::mem::take(&mut self.entries)
+            .into_iter()
+            .filter(|(_, entry)| !evict(entry))
+            .collect();
+        entries.sort_by_key(|(_, entry)| -entry.rect[3]);
+
+        for (key, mut entry) in entries {
+            let [x, y, w, h] = entry.rect;
+            if let Some([nx, ny]) = packer.insert([w, h]) {
+                self.tex.copy_to(&tex, [x, y], [nx, ny], [w, h]);
+                entry.rect = [nx, ny, w, h];
+                self.entries.insert(key, entry);
+            }
+        }
+
+        self.tex = tex;
+        self.packer = packer;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills() {
+        let mut packer = Packer::new([8, 8], 0);
+        let corners = [[4, 4]; 4].map(|size| packer.insert(size));
+        assert_eq!(
+            corners,
+            [Some([0, 0]), Some([4, 0]), Some([0, 4]), Some([4, 4])]
+        );
+        assert_eq!(packer.insert([1, 1]), None);
+    }
+
+    #[test]
+    fn padding() {
+        let mut packer = Packer::new([10, 10], 1);
+        assert_eq!(packer.insert([3, 3]), Some([1, 1]));
+        assert_eq!(packer.insert([3, 3]), Some([6, 1]));
+        assert_eq!(packer.insert([8, 3]), Some([1, 6]));
+        assert_eq!(packer.insert([1, 1]), None);
+    }
+
+    #[test]
+    fn no_overlaps() {
+        let mut packer = Packer::new([256, 256], 1);
+        let mut rects = Vec::<[i32; 4]>::new();
+        let mut seed = 1u32;
+        for _ in 0..200 {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            let size = [(seed >> 8) as i32 % 24 + 1, (seed >> 20) as i32 % 24 + 1];
+            let Some([x, y]) = packer.insert(size) else {
+                continue;
+            };
+
+            let rect = [x, y, size[0], size[1]];
+            assert!(x >= 1 && y >= 1 && x + size[0] < 256 && y + size[1] < 256);
+            for &[ox, oy, ow, oh] in &rects {
+                let apart = x + size[0] < ox || ox + ow < x || y + size[1] < oy || oy + oh < y;
+                assert!(apart, "{:?} overlaps {:?}", rect, [ox, oy, ow, oh]);
+            }
+            rects.push(rect);
+        }
+
+        assert!(rects.len() > 100);
+    }
+
+    #[test]
+    fn grow_and_clear() {
+        let mut packer = Packer::new([4, 4], 0);
+        assert_eq!(packer.insert([4, 4]), Some([0, 0]));
+        assert_eq!(packer.insert([2, 2]), None);
+
+        packer.grow([8, 4]);
+        assert_eq!(packer.size(), [8, 4]);
+        assert_eq!(packer.insert([2, 2]), Some([4, 0]));
+        assert_eq!(packer.insert([2, 2]), Some([6, 0]));
+
+        packer.clear();
+        assert_eq!(packer.insert([8, 4]), Some([0, 0]));
+    }
+}
@@ -1,13 +1,36 @@
-use {crate::gfx::*, core::mem::size_of, Resource};
+mod ring;
+
+pub use ring::RingBuffer;
+use {
+    crate::gfx::*,
+    core::{
+        marker::PhantomData,
+        mem::{size_of, size_of_val},
+        ops::{Deref, DerefMut, Range},
+    },
+    Resource,
+};
+
+pub const ARRAY: u32 = GL_ARRAY_BUFFER;
+pub const ELEMENT_ARRAY: u32 = GL_ELEMENT_ARRAY_BUFFER;
+pub const UNIFORM: u32 = GL_UNIFORM_BUFFER;
+pub const SHADER_STORAGE: u32 = GL_SHADER_STORAGE_BUFFER;
+pub const ATOMIC_COUNTER: u32 = GL_ATOMIC_COUNTER_BUFFER;
+pub const DISPATCH_INDIRECT: u32 = GL_DISPATCH_INDIRECT_BUFFER;
+pub const DRAW_INDIRECT: u32 = GL_DRAW_INDIRECT_BUFFER;
+pub const PIXEL_PACK: u32 = GL_PIXEL_PACK_BUFFER;
+pub const PIXEL_UNPACK: u32 = GL_PIXEL_UNPACK_BUFFER;
 
 pub struct Buffer {
     _type: GLenum,
     buf: GLuint,
+    usage: Usage,
+    size: usize,
     len: usize,
 }
 
 impl Buffer {
-    pub fn new<Data>(_type: GLenum, usage: Usage, data: &[Data]) -> Self {
+    pub fn new<Data: Copy>(_type: GLenum, usage: Usage, data: &[Data]) -> Self {
         unsafe {
             let mut buf = 0;
             glGenBuffers(1, &mut buf);
@@ -15,7 +38,7 @@ impl Buffer {
 
             glBufferData(
                 _type,
-                (size_of::<Data>() * data.len()) as _,
+                size_of_val(data) as _,
                 data.as_ptr() as _,
                 usage as _,
             );
@@ -23,26 +46,255 @@ impl Buffer {
             Self {
                 _type,
                 buf,
+                usage,
+                size: size_of_val(data),
                 len: data.len(),
             }
         }
     }
 
-    pub fn update<Data>(&mut self, data: &[Data]) {
+    /// Uninitialised buffer with room for `len` items
+    pub fn with_capacity<Data: Copy>(_type: GLenum, usage: Usage, len: usize) -> Self {
+        let size = len * size_of::<Data>();
+        unsafe {
+            let mut buf = 0;
+            glGenBuffers(1, &mut buf);
+            glBindBuffer(_type, buf);
+            glBufferData(_type, size as _, core::ptr::null(), usage as _);
+
+            Self {
+                _type,
+                buf,
+                usage,
+                size,
+                len,
+            }
+        }
+    }
+
+    /// Replace the contents, reallocating if `data` doesn't fit
+    pub fn update<Data: Copy>(&mut self, data: &[Data]) {
+        self.bind();
+        unsafe {
+            if size_of_val(data) > self.size {
+                log::debug!("growing buffer {} to {}B", self.buf, size_of_val(data));
+                glBufferData(
+                    self._type,
+                    size_of_val(data) as _,
+                    data.as_ptr() as _,
+                    self.usage as _,
+                );
+                self.size = size_of_val(data);
+            } else {
+                glBufferSubData(self._type, 0, size_of_val(data) as _, data.as_ptr() as _);
+            }
+        }
+
+        self.len = data.len();
+    }
+
+    /// Write `data` starting at item `offset`, growing the buffer and keeping
+    /// what was there if it ends past the current allocation.
+    pub fn update_at<Data: Copy>(&mut self, offset: usize, data: &[Data]) {
+        let start = offset * size_of::<Data>();
+        let end = start + size_of_val(data);
+        if end > self.size {
+            self.grow(end.max(2 * self.size));
+        }
+
         self.bind();
         unsafe {
             glBufferSubData(
                 self._type,
-                0,
-                (size_of::<Data>() * data.len()) as _,
+                start as _,
+                size_of_val(data) as _,
                 data.as_ptr() as _,
             );
         }
+
+        self.len = self.len.max(offset + data.len());
+    }
+
+    /// Reallocate to `size` bytes under the same name, so vertex arrays
+    /// referencing the buffer stay valid, preserving the current contents.
+    fn grow(&mut self, size: usize) {
+        log::debug!("growing buffer {} to {}B", self.buf, size);
+        unsafe {
+            let mut tmp = 0;
+            glGenBuffers(1, &mut tmp);
+            glBindBuffer(GL_COPY_WRITE_BUFFER, tmp);
+            glBufferData(
+                GL_COPY_WRITE_BUFFER,
+                self.size as _,
+                core::ptr::null(),
+                GL_STREAM_COPY,
+            );
+
+            glBindBuffer(GL_COPY_READ_BUFFER, self.buf);
+            glCopyBufferSubData(
+                GL_COPY_READ_BUFFER,
+                GL_COPY_WRITE_BUFFER,
+                0,
+                0,
+                self.size as _,
+            );
+            glBufferData(
+                GL_COPY_READ_BUFFER,
+                size as _,
+                core::ptr::null(),
+                self.usage as _,
+            );
+
+            glBindBuffer(GL_COPY_READ_BUFFER, tmp);
+            glBindBuffer(GL_COPY_WRITE_BUFFER, self.buf);
+            glCopyBufferSubData(
+                GL_COPY_READ_BUFFER,
+                GL_COPY_WRITE_BUFFER,
+                0,
+                0,
+                self.size as _,
+            );
+
+            glDeleteBuffers(1, &tmp);
+        }
+
+        self.size = size;
+    }
+
+    /// Detach the current storage so the driver doesn't have to wait for
+    /// pending draws before the next write.
+    pub fn orphan(&mut self) {
+        self.bind();
+        unsafe {
+            glBufferData(
+                self._type,
+                self.size as _,
+                core::ptr::null(),
+                self.usage as _,
+            );
+        }
+    }
+
+    /// Copy `size` bytes from byte `src_offset` to byte `dst_offset` of `dst`
+    pub fn copy_to(&self, dst: &mut Buffer, src_offset: usize, dst_offset: usize, size: usize) {
+        assert!(src_offset + size <= self.size && dst_offset + size <= dst.size);
+        unsafe {
+            glBindBuffer(GL_COPY_READ_BUFFER, self.buf);
+            glBindBuffer(GL_COPY_WRITE_BUFFER, dst.buf);
+            glCopyBufferSubData(
+                GL_COPY_READ_BUFFER,
+                GL_COPY_WRITE_BUFFER,
+                src_offset as _,
+                dst_offset as _,
+                size as _,
+            );
+        }
+    }
+
+    /// Map the whole buffer for reading, see `map_range`
+    pub fn map<Data: Pod>(&mut self, access: &[Map]) -> Option<Mapping<'_, Data>> {
+        self.map_range(0..self.size / size_of::<Data>(), access)
+    }
+
+    /// Map the items in `range` for reading, unmapped again when the guard
+    /// drops. Returns `None` if `access` lacks `Map::Read`, the range lies
+    /// outside the buffer or the driver refuses.
+    pub fn map_range<Data: Pod>(
+        &mut self,
+        range: Range<usize>,
+        access: &[Map],
+    ) -> Option<Mapping<'_, Data>> {
+        if !access.iter().any(|&map| map as u32 == Map::Read as u32) {
+            return None;
+        }
+
+        self.map_raw(range, access).map(Mapping)
+    }
+
+    /// Map the whole buffer for writing, see `map_range_mut`
+    pub fn map_mut<Data: Pod>(&mut self, access: &[Map]) -> Option<MappingMut<'_, Data>> {
+        self.map_range_mut(0..self.size / size_of::<Data>(), access)
+    }
+
+    /// Map the items in `range` for writing, like `map_range` but requiring
+    /// `Map::Write` instead. Reading a mapping without `Map::Read` gives
+    /// undefined values.
+    pub fn map_range_mut<Data: Pod>(
+        &mut self,
+        range: Range<usize>,
+        access: &[Map],
+    ) -> Option<MappingMut<'_, Data>> {
+        if !access.iter().any(|&map| map as u32 == Map::Write as u32) {
+            return None;
+        }
+
+        self.map_raw(range, access).map(MappingMut)
+    }
+
+    fn map_raw<Data: Pod>(
+        &mut self,
+        range: Range<usize>,
+        access: &[Map],
+    ) -> Option<Mapped<'_, Data>> {
+        let bits = access.iter().fold(0, |bits, &map| bits | map as GLbitfield);
+        if range.end * size_of::<Data>() > self.size || range.is_empty() {
+            return None;
+        }
+
+        self.bind();
+        let data = unsafe {
+            glMapBufferRange(
+                self._type,
+                (range.start * size_of::<Data>()) as _,
+                (range.len() * size_of::<Data>()) as _,
+                bits,
+            )
+        };
+
+        if data.is_null() {
+            return None;
+        }
+
+        Some(Mapped {
+            buf: self,
+            data: data as _,
+            len: range.len(),
+            _data: PhantomData,
+        })
     }
 
     pub fn len(&self) -> usize {
         self.len
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Allocated size in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn usage(&self) -> Usage {
+        self.usage
+    }
+
+    /// Bind to a target other than the one the buffer was created for, e.g. a
+    /// vertex buffer written by a compute pass as `SHADER_STORAGE`.
+    pub fn bind_to(&self, target: u32) {
+        unsafe { glBindBuffer(target, self.buf) }
+    }
+
+    /// Bind to binding point `index` of an indexed target (`SHADER_STORAGE`,
+    /// `UNIFORM` or `ATOMIC_COUNTER`).
+    pub fn bind_base(&self, target: u32, index: u32) {
+        unsafe { glBindBufferBase(target, index, self.buf) }
+    }
+
+    pub fn bind_range(&self, target: u32, index: u32, offset: usize, size: usize) {
+        unsafe { glBindBufferRange(target, index, self.buf, offset as _, size as _) }
+    }
 }
 
 impl Resource for Buffer {
@@ -59,9 +311,106 @@ impl Drop for Buffer {
     }
 }
 
+/// Plain data any bit pattern is a valid value of, so it can be viewed
+/// straight from mapped GPU memory.
+///
+/// # Safety
+///
+/// The type must have no padding, no invalid bit patterns and no pointers.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! pod {
+    ($($ty:ty),*) => {$(
+        unsafe impl Pod for $ty {}
+    )*};
+}
+
+pod!(u8, i8, u16, i16, u32, i32, u64, i64, usize, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+struct Mapped<'a, Data> {
+    buf: &'a Buffer,
+    data: *mut Data,
+    len: usize,
+    _data: PhantomData<&'a mut [Data]>,
+}
+
+impl<'a, Data> Drop for Mapped<'a, Data> {
+    fn drop(&mut self) {
+        self.buf.bind();
+        unsafe {
+            glUnmapBuffer(self.buf._type);
+        }
+    }
+}
+
+/// Buffer mapped for reading, see `Buffer::map_range`
+pub struct Mapping<'a, Data>(Mapped<'a, Data>);
+
+impl<'a, Data> Deref for Mapping<'a, Data> {
+    type Target = [Data];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { core::slice::from_raw_parts(self.0.data, self.0.len) }
+    }
+}
+
+/// Buffer mapped for writing, see `Buffer::map_range_mut`
+pub struct MappingMut<'a, Data>(Mapped<'a, Data>);
+
+impl<'a, Data> MappingMut<'a, Data> {
+    /// Flush writes to the items in `range`, relative to the start of the
+    /// mapping, when mapped with `Map::FlushExplicit`.
+    pub fn flush(&self, range: Range<usize>) {
+        self.0.buf.bind();
+        unsafe {
+            glFlushMappedBufferRange(
+                self.0.buf._type,
+                (range.start * size_of::<Data>()) as _,
+                (range.len() * size_of::<Data>()) as _,
+            );
+        }
+    }
+}
+
+impl<'a, Data> Deref for MappingMut<'a, Data> {
+    type Target = [Data];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { core::slice::from_raw_parts(self.0.data, self.0.len) }
+    }
+}
+
+impl<'a, Data> DerefMut for MappingMut<'a, Data> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { core::slice::from_raw_parts_mut(self.0.data, self.0.len) }
+    }
+}
+
 #[repr(u32)]
 #[derive(Clone, Copy)]
 pub enum Usage {
     StaticDraw = GL_STATIC_DRAW,
+    StaticRead = GL_STATIC_READ,
+    StaticCopy = GL_STATIC_COPY,
+    DynamicDraw = GL_DYNAMIC_DRAW,
+    DynamicRead = GL_DYNAMIC_READ,
+    DynamicCopy = GL_DYNAMIC_COPY,
     StreamDraw = GL_STREAM_DRAW,
+    StreamRead = GL_STREAM_READ,
+    StreamCopy = GL_STREAM_COPY,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy)]
+pub enum Map {
+    Read = GL_MAP_READ_BIT,
+    Write = GL_MAP_WRITE_BIT,
+    InvalidateRange = GL_MAP_INVALIDATE_RANGE_BIT,
+    InvalidateBuffer = GL_MAP_INVALIDATE_BUFFER_BIT,
+    FlushExplicit = GL_MAP_FLUSH_EXPLICIT_BIT,
+    Unsynchronized = GL_MAP_UNSYNCHRONIZED_BIT,
+    Persistent = GL_MAP_PERSISTENT_BIT,
+    Coherent = GL_MAP_COHERENT_BIT,
 }
@@ -0,0 +1,102 @@
+use {
+    super::{Buffer, Pod, Usage},
+    crate::{gfx::*, mem::vec::Vec},
+    core::{mem::size_of, ops::Range},
+};
+
+/// Persistently mapped buffer split into `segments` parts of `len` items,
+/// written round-robin one per frame. Each segment is fenced once the draws
+/// reading it are issued, and only handed out again after the GPU is done.
+pub struct RingBuffer<Data: Pod> {
+    buf: Buffer,
+    data: *mut Data,
+    len: usize,
+    head: usize,
+    fences: Vec<GLsync>,
+}
+
+impl<Data: Pod> RingBuffer<Data> {
+    /// `None` if the driver refuses to map the buffer
+    pub fn new(_type: u32, len: usize, segments: usize) -> Option<Self> {
+        assert!(
+            len > 0 && segments > 0 && size_of::<Data>() > 0,
+            "empty ring buffer"
+        );
+        let size = len
+            .checked_mul(segments)
+            .and_then(|items| items.checked_mul(size_of::<Data>()))
+            .filter(|&size| size <= isize::MAX as usize)
+            .expect("ring buffer too large");
+        let flags = GL_MAP_WRITE_BIT | GL_MAP_PERSISTENT_BIT | GL_MAP_COHERENT_BIT;
+
+        unsafe {
+            let mut buf = 0;
+            glGenBuffers(1, &mut buf);
+            glBindBuffer(_type, buf);
+            glBufferStorage(_type, size as _, core::ptr::null(), flags);
+            let data = glMapBufferRange(_type, 0, size as _, flags) as *mut Data;
+            if data.is_null() {
+                glDeleteBuffers(1, &buf);
+                return None;
+            }
+
+            Some(Self {
+                buf: Buffer {
+                    _type,
+                    buf,
+                    usage: Usage::StreamDraw,
+                    size,
+                    len: len * segments,
+                },
+                data,
+                len,
+                head: 0,
+                fences: (0..segments).map(|_| core::ptr::null_mut()).collect(),
+            })
+        }
+    }
+
+    /// Wait until the GPU has released the current segment and hand it out
+    pub fn acquire(&mut self) -> &mut [Data] {
+        let fence = core::mem::replace(&mut self.fences[self.head], core::ptr::null_mut());
+        if !fence.is_null() {
+            unsafe {
+                while glClientWaitSync(fence, GL_SYNC_FLUSH_COMMANDS_BIT, 1_000_000)
+                    == GL_TIMEOUT_EXPIRED
+                {}
+                glDeleteSync(fence);
+            }
+        }
+
+        unsafe { core::slice::from_raw_parts_mut(self.data.add(self.range().start), self.len) }
+    }
+
+    /// Items of the buffer covered by the current segment, e.g. the vertex
+    /// range to pass to `Mesh::draw_range`
+    pub fn range(&self) -> Range<usize> {
+        self.head * self.len..(self.head + 1) * self.len
+    }
+
+    /// Fence the current segment after the draws reading it and advance
+    pub fn fence(&mut self) {
+        self.fences[self.head] = unsafe { glFenceSync(GL_SYNC_GPU_COMMANDS_COMPLETE, 0) };
+        self.head = (self.head + 1) % self.fences.len();
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        &self.buf
+    }
+}
+
+impl<Data: Pod> Drop for RingBuffer<Data> {
+    fn drop(&mut self) {
+        unsafe {
+            for &fence in self.fences.iter().filter(|fence| !fence.is_null()) {
+                glDeleteSync(fence);
+            }
+
+            self.buf.bind();
+            glUnmapBuffer(self.buf._type);
+        }
+    }
+}
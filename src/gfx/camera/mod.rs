@@ -0,0 +1,285 @@
+use {
+    crate::{
+        math::{
+            add, cos, cross, look_at, multiply, normalize, perspective, perspective_reverse_z,
+            powf, scale, sin, sub, unproject, Matrix,
+        },
+        win::event::{Event, KeyCode, MouseButton},
+    },
+    core::f32::consts::FRAC_PI_2,
+};
+
+/// Pitch is kept just short of straight up or down, where `look_at` has no
+/// defined right vector
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.001;
+
+/// Perspective projection parameters shared by the camera types
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lens {
+    /// Vertical field of view in radians
+    pub fov_y: f32,
+    /// Viewport width over height
+    pub aspect: f32,
+    pub near: f32,
+    /// Ignored with `reverse_z`, whose far plane is at infinity
+    pub far: f32,
+    /// Use `perspective_reverse_z`, see `gfx::state::clip_zero_to_one`
+    pub reverse_z: bool,
+}
+
+impl Lens {
+    pub fn new(fov_y: f32, aspect: f32) -> Self {
+        Self {
+            fov_y,
+            aspect,
+            near: 0.1,
+            far: 1000.0,
+            reverse_z: false,
+        }
+    }
+
+    pub fn projection(&self) -> Matrix<4, 4> {
+        if self.reverse_z {
+            perspective_reverse_z(self.fov_y, self.aspect, self.near)
+        } else {
+            perspective(self.fov_y, self.aspect, self.near, self.far)
+        }
+    }
+}
+
+pub trait Camera {
+    fn view(&self) -> Matrix<4, 4>;
+
+    fn lens(&self) -> &Lens;
+
+    /// Update from input, returning whether the event was used
+    fn handle(&mut self, event: &Event) -> bool;
+
+    fn projection(&self) -> Matrix<4, 4> {
+        self.lens().projection()
+    }
+
+    /// Transform for the `pos3d*.vert` shaders
+    fn view_projection(&self) -> Matrix<4, 4> {
+        multiply(&self.projection(), &self.view())
+    }
+
+    /// Origin and direction of the ray through the window position `pos` of
+    /// an event, y down from the top of `viewport`
+    fn ray(&self, [x, y]: [i32; 2], viewport: [i32; 4]) -> Option<([f32; 3], [f32; 3])> {
+        let view_proj = self.view_projection();
+        let window = [x as f32 + 0.5, (viewport[1] + viewport[3] - y) as f32 - 0.5];
+
+        // Reverse-z has its far plane at infinity, so aim through a point
+        // twice the near distance away instead
+        let (near, far) = if self.lens().reverse_z {
+            (1.0, 0.75)
+        } else {
+            (0.0, 1.0)
+        };
+
+        let origin = unproject([window[0], window[1], near], &view_proj, viewport)?;
+        let through = unproject([window[0], window[1], far], &view_proj, viewport)?;
+
+        Some((origin, normalize(sub(through, origin))))
+    }
+}
+
+/// Camera circling `target`, rotated by dragging with the left button,
+/// panned with the right or middle button and zoomed with the wheel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitCamera {
+    pub target: [f32; 3],
+    pub distance: f32,
+    /// Rotation about the y axis in radians, 0 looking down -z
+    pub yaw: f32,
+    /// Elevation above the target in radians
+    pub pitch: f32,
+    pub lens: Lens,
+    /// Radians per pixel dragged
+    pub sensitivity: f32,
+    /// Distance scale per wheel step
+    pub zoom: f32,
+    pub min_distance: f32,
+}
+
+impl OrbitCamera {
+    pub fn new(target: [f32; 3], distance: f32, lens: Lens) -> Self {
+        Self {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            lens,
+            sensitivity: 0.005,
+            zoom: 0.9,
+            min_distance: 0.01,
+        }
+    }
+
+    pub fn eye(&self) -> [f32; 3] {
+        let offset = [
+            cos(self.pitch) * sin(self.yaw),
+            sin(self.pitch),
+            cos(self.pitch) * cos(self.yaw),
+        ];
+
+        add(self.target, scale(offset, self.distance))
+    }
+}
+
+impl Camera for OrbitCamera {
+    fn view(&self) -> Matrix<4, 4> {
+        look_at(self.eye(), self.target, [0.0, 1.0, 0.0])
+    }
+
+    fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    fn handle(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::MouseMotion {
+                rel: [dx, dy],
+                buttons,
+                ..
+            } => {
+                let [dx, dy] = [dx as f32, dy as f32];
+
+                if buttons & MouseButton::Left.mask() != 0 {
+                    self.yaw -= dx * self.sensitivity;
+                    self.pitch = (self.pitch + dy * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+                    true
+                } else if buttons & (MouseButton::Right.mask() | MouseButton::Middle.mask()) != 0 {
+                    // Move the target with the cursor at the target's depth
+                    let forward = normalize(sub(self.target, self.eye()));
+                    let right = normalize(cross(forward, [0.0, 1.0, 0.0]));
+                    let up = cross(right, forward);
+                    let step = self.distance * self.sensitivity;
+
+                    self.target = add(self.target, scale(right, -dx * step));
+                    self.target = add(self.target, scale(up, dy * step));
+                    true
+                } else {
+                    false
+                }
+            }
+
+            Event::MouseWheel { scroll: [_, dy] } => {
+                self.distance = (self.distance * powf(self.zoom, dy)).max(self.min_distance);
+                true
+            }
+
+            _ => false,
+        }
+    }
+}
+
+/// Free-flying camera looking with relative mouse motion, see
+/// `EventChannel::relative_mouse`, and moving with WASD, space and left shift
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FpsCamera {
+    pub pos: [f32; 3],
+    /// Rotation about the y axis in radians, 0 looking down -z
+    pub yaw: f32,
+    pub pitch: f32,
+    pub lens: Lens,
+    /// Radians per pixel moved
+    pub sensitivity: f32,
+    /// Units per second
+    pub speed: f32,
+    held: u8,
+}
+
+impl FpsCamera {
+    const FORWARD: u8 = 1 << 0;
+    const BACK: u8 = 1 << 1;
+    const LEFT: u8 = 1 << 2;
+    const RIGHT: u8 = 1 << 3;
+    const UP: u8 = 1 << 4;
+    const DOWN: u8 = 1 << 5;
+
+    pub fn new(pos: [f32; 3], lens: Lens) -> Self {
+        Self {
+            pos,
+            yaw: 0.0,
+            pitch: 0.0,
+            lens,
+            sensitivity: 0.002,
+            speed: 5.0,
+            held: 0,
+        }
+    }
+
+    pub fn forward(&self) -> [f32; 3] {
+        [
+            -cos(self.pitch) * sin(self.yaw),
+            sin(self.pitch),
+            -cos(self.pitch) * cos(self.yaw),
+        ]
+    }
+
+    /// Move by the held keys over `dt` seconds, called once per frame
+    pub fn update(&mut self, dt: f32) {
+        let axis = |pos, neg| match (self.held & pos != 0, self.held & neg != 0) {
+            (true, false) => 1.0,
+            (false, true) => -1.0,
+            _ => 0.0,
+        };
+
+        // Walk on the horizontal plane regardless of pitch
+        let forward = [-sin(self.yaw), 0.0, -cos(self.yaw)];
+        let right = [cos(self.yaw), 0.0, -sin(self.yaw)];
+        let dir = add(
+            add(
+                scale(forward, axis(Self::FORWARD, Self::BACK)),
+                scale(right, axis(Self::RIGHT, Self::LEFT)),
+            ),
+            [0.0, axis(Self::UP, Self::DOWN), 0.0],
+        );
+
+        self.pos = add(self.pos, scale(normalize(dir), self.speed * dt));
+    }
+}
+
+impl Camera for FpsCamera {
+    fn view(&self) -> Matrix<4, 4> {
+        look_at(self.pos, add(self.pos, self.forward()), [0.0, 1.0, 0.0])
+    }
+
+    fn lens(&self) -> &Lens {
+        &self.lens
+    }
+
+    fn handle(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::MouseMotion { rel: [dx, dy], .. } => {
+                self.yaw -= dx as f32 * self.sensitivity;
+                self.pitch =
+                    (self.pitch - dy as f32 * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+                true
+            }
+
+            Event::Keyboard { down, sym, .. } => {
+                let bit = match sym {
+                    KeyCode::W => Self::FORWARD,
+                    KeyCode::S => Self::BACK,
+                    KeyCode::A => Self::LEFT,
+                    KeyCode::D => Self::RIGHT,
+                    KeyCode::Space => Self::UP,
+                    KeyCode::LShift => Self::DOWN,
+                    _ => return false,
+                };
+
+                if down {
+                    self.held |= bit;
+                } else {
+                    self.held &= !bit;
+                }
+                true
+            }
+
+            _ => false,
+        }
+    }
+}
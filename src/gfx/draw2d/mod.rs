@@ -0,0 +1,348 @@
+use {
+    crate::{
+        gfx::{
+            buffer::Usage,
+            mesh::{vertex::Norm, Mesh, Topology},
+            program::Program,
+            shader::{DRAW2D_FRAG, DRAW2D_VERT},
+            state::{Blend, RenderState},
+            texture::{Format, Texture, TextureRgba, TEX_2D},
+            *,
+        },
+        math::{cos, ortho, sin, sqrt},
+        mem::vec::Vec,
+    },
+    core::{f32::consts::PI, ops::Range},
+};
+
+type Vert = ([f32; 2], [f32; 2], Norm<[u8; 4]>);
+
+/// Consecutive shapes sharing a texture and render state, drawn at once
+#[derive(Debug)]
+struct Batch {
+    tex: GLuint,
+    state: RenderState,
+    indices: Range<usize>,
+}
+
+/// Immediate-style 2D renderer. Shapes are collected in pixel coordinates
+/// with the origin at the bottom left and drawn in submission order on
+/// `flush`, with one draw call per change of texture or state.
+pub struct Draw2d {
+    program: Program,
+    white: TextureRgba,
+    mesh: Mesh<Vert>,
+    verts: Vec<Vert>,
+    indices: Vec<u32>,
+    batches: Vec<Batch>,
+    state: RenderState,
+}
+
+impl Default for Draw2d {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Draw2d {
+    pub fn new() -> Self {
+        Self {
+            program: Program::new(DRAW2D_VERT, DRAW2D_FRAG),
+            white: Texture::from_pixels(TEX_2D, [1, 1], &[[0xFF; 4]]),
+            mesh: Mesh::new(&[], Usage::StreamDraw, Topology::Tris)
+                .with_indices::<u32>(&[], Usage::StreamDraw),
+            verts: Vec::new(),
+            indices: Vec::new(),
+            batches: Vec::new(),
+            state: RenderState {
+                blend: Some(Blend::ALPHA),
+                ..RenderState::default()
+            },
+        }
+    }
+
+    /// State shapes submitted from now on are drawn with, alpha blending by
+    /// default
+    pub fn set_state(&mut self, state: RenderState) {
+        self.state = state;
+    }
+
+    pub fn rect(&mut self, [x0, y0]: [f32; 2], [x1, y1]: [f32; 2], color: [u8; 4]) {
+        self.fill(
+            &[[x0, y0], [x1, y0], [x1, y1], [x0, y1]],
+            [0, 1, 2, 0, 2, 3],
+            color,
+        );
+    }
+
+    pub fn rounded_rect(&mut self, min: [f32; 2], max: [f32; 2], radius: f32, color: [u8; 4]) {
+        let radius = radius
+            .min((max[0] - min[0]) / 2.0)
+            .min((max[1] - min[1]) / 2.0);
+        if radius <= 0.0 {
+            return self.rect(min, max, color);
+        }
+
+        let corners = [
+            ([max[0] - radius, min[1] + radius], -PI / 2.0),
+            ([max[0] - radius, max[1] - radius], 0.0),
+            ([min[0] + radius, max[1] - radius], PI / 2.0),
+            ([min[0] + radius, min[1] + radius], PI),
+        ];
+        let outline = corners
+            .iter()
+            .flat_map(|&(center, start)| arc_points(center, radius, start, start + PI / 2.0))
+            .collect::<Vec<_>>();
+
+        self.convex(&outline, color);
+    }
+
+    /// Line from `a` to `b` with square ends
+    pub fn line(&mut self, [ax, ay]: [f32; 2], [bx, by]: [f32; 2], thickness: f32, color: [u8; 4]) {
+        let [dx, dy] = [bx - ax, by - ay];
+        let len = sqrt(dx * dx + dy * dy);
+        if len == 0.0 {
+            return;
+        }
+
+        let [nx, ny] = [-dy / len * thickness / 2.0, dx / len * thickness / 2.0];
+        self.fill(
+            &[
+                [ax + nx, ay + ny],
+                [ax - nx, ay - ny],
+                [bx - nx, by - ny],
+                [bx + nx, by + ny],
+            ],
+            [0, 1, 2, 0, 2, 3],
+            color,
+        );
+    }
+
+    pub fn circle(&mut self, center: [f32; 2], radius: f32, color: [u8; 4]) {
+        let outline = arc_points(center, radius, 0.0, 2.0 * PI);
+        self.convex(&outline[..outline.len() - 1], color);
+    }
+
+    /// Outline of a circle from angle `start` to `end` in radians,
+    /// counter-clockwise from the positive x axis
+    pub fn arc(
+        &mut self,
+        center: [f32; 2],
+        radius: f32,
+        [start, end]: [f32; 2],
+        thickness: f32,
+        color: [u8; 4],
+    ) {
+        let outer = arc_points(center, radius + thickness / 2.0, start, end);
+        let inner = arc_points(center, (radius - thickness / 2.0).max(0.0), start, end);
+        let points = outer
+            .iter()
+            .zip(&inner)
+            .flat_map(|(&o, &i)| [o, i])
+            .collect::<Vec<_>>();
+
+        let quads = (0..outer.len() as u32 - 1)
+            .flat_map(|n| [0, 1, 3, 0, 3, 2].map(|i| 2 * n + i))
+            .collect::<Vec<_>>();
+        self.fill(&points, quads, color);
+    }
+
+    /// Simple polygon of either winding, triangulated by ear clipping unless
+    /// it is convex
+    pub fn polygon(&mut self, points: &[[f32; 2]], color: [u8; 4]) {
+        if points.len() < 3 {
+            return;
+        }
+
+        if is_convex(points) {
+            self.convex(points, color);
+        } else {
+            self.fill(points, triangulate(points), color);
+        }
+    }
+
+    /// Quad showing the `[min, max]` region `uv` of a `TEX_2D` texture,
+    /// multiplied by `tint`. The texture has to live until the next `flush`.
+    pub fn textured<F: Format>(
+        &mut self,
+        tex: &Texture<F>,
+        [x0, y0]: [f32; 2],
+        [x1, y1]: [f32; 2],
+        [[u0, v0], [u1, v1]]: [[f32; 2]; 2],
+        tint: [u8; 4],
+    ) {
+        self.push(
+            **tex,
+            &[
+                ([x0, y0], [u0, v0]),
+                ([x1, y0], [u1, v0]),
+                ([x1, y1], [u1, v1]),
+                ([x0, y1], [u0, v1]),
+            ],
+            [0, 1, 2, 0, 2, 3],
+            tint,
+        );
+    }
+
+    /// Textured quad with any corners, counter-clockwise from the bottom
+    /// left, e.g. a rotated sprite
+    pub fn quad<F: Format>(
+        &mut self,
+        tex: &Texture<F>,
+        corners: [[f32; 2]; 4],
+        uvs: [[f32; 2]; 4],
+        tint: [u8; 4],
+    ) {
+        let verts = [0, 1, 2, 3].map(|i| (corners[i], uvs[i]));
+        self.push(**tex, &verts, [0, 1, 2, 0, 2, 3], tint);
+    }
+
+    /// Draw everything submitted since the last flush onto the bound
+    /// framebuffer of `size` pixels
+    pub fn flush(&mut self, [w, h]: [i32; 2]) {
+        if self.batches.is_empty() {
+            return;
+        }
+
+        self.mesh.update(&self.verts);
+        self.mesh.update_indices(&self.indices);
+
+        let prev = RenderState::current();
+        self.program.bind();
+        ortho([0.0, 0.0], [w as f32, h as f32]).bind(0);
+        for batch in self.batches.drain(..) {
+            batch.state.apply();
+            unsafe {
+                glActiveTexture(GL_TEXTURE0);
+                glBindTexture(GL_TEXTURE_2D, batch.tex);
+            }
+            self.mesh.draw_range(batch.indices);
+        }
+        prev.apply();
+
+        self.verts.clear();
+        self.indices.clear();
+    }
+
+    fn convex(&mut self, outline: &[[f32; 2]], color: [u8; 4]) {
+        if outline.len() < 3 {
+            return;
+        }
+
+        let fan = (1..outline.len() as u32 - 1).flat_map(|n| [0, n, n + 1]);
+        self.fill(outline, fan, color);
+    }
+
+    fn fill(
+        &mut self,
+        points: &[[f32; 2]],
+        indices: impl IntoIterator<Item = u32>,
+        color: [u8; 4],
+    ) {
+        let verts = points
+            .iter()
+            .map(|&pos| (pos, [0.5, 0.5]))
+            .collect::<Vec<_>>();
+        self.push(*self.white, &verts, indices, color);
+    }
+
+    fn push(
+        &mut self,
+        tex: GLuint,
+        verts: &[([f32; 2], [f32; 2])],
+        indices: impl IntoIterator<Item = u32>,
+        color: [u8; 4],
+    ) {
+        let base = self.verts.len() as u32;
+        let start = self.indices.len();
+        self.verts
+            .extend(verts.iter().map(|&(pos, uv)| (pos, uv, Norm(color))));
+        self.indices
+            .extend(indices.into_iter().map(|idx| base + idx));
+        let end = self.indices.len();
+
+        match self.batches.last_mut() {
+            Some(batch) if batch.tex == tex && batch.state == self.state => batch.indices.end = end,
+            _ => self.batches.push(Batch {
+                tex,
+                state: self.state,
+                indices: start..end,
+            }),
+        }
+    }
+}
+
+/// Points along a circular arc, enough for the error to stay under a pixel
+fn arc_points([cx, cy]: [f32; 2], radius: f32, start: f32, end: f32) -> Vec<[f32; 2]> {
+    let segments = ((sqrt(radius) * 2.0 * (end - start).abs()) as usize).clamp(2, 256);
+    (0..=segments)
+        .map(|n| start + (end - start) * n as f32 / segments as f32)
+        .map(|angle| [cx + radius * cos(angle), cy + radius * sin(angle)])
+        .collect()
+}
+
+fn cross([ax, ay]: [f32; 2], [bx, by]: [f32; 2], [cx, cy]: [f32; 2]) -> f32 {
+    (bx - ax) * (cy - ay) - (by - ay) * (cx - ax)
+}
+
+/// Twice the signed area, positive for counter-clockwise polygons
+fn area(points: &[[f32; 2]]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|([ax, ay], [bx, by])| ax * by - bx * ay)
+        .sum()
+}
+
+fn is_convex(points: &[[f32; 2]]) -> bool {
+    let n = points.len();
+    let sign = area(points).signum();
+    (0..n).all(|i| cross(points[i], points[(i + 1) % n], points[(i + 2) % n]) * sign >= 0.0)
+}
+
+/// Triangulate a simple polygon by repeatedly clipping off a convex corner
+/// with no other vertex inside it
+fn triangulate(points: &[[f32; 2]]) -> Vec<u32> {
+    let mut remaining = (0..points.len() as u32).collect::<Vec<_>>();
+    if area(points) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut tris = Vec::with_capacity((points.len() - 2) * 3);
+    let (mut i, mut misses) = (0, 0);
+    while remaining.len() > 3 && misses < remaining.len() {
+        let len = remaining.len();
+        let corner = [
+            remaining[(i + len - 1) % len],
+            remaining[i % len],
+            remaining[(i + 1) % len],
+        ];
+        let [a, b, c] = corner.map(|idx| points[idx as usize]);
+
+        let ear = cross(a, b, c) > 0.0
+            && !remaining
+                .iter()
+                .filter(|idx| !corner.contains(idx))
+                .any(|&idx| {
+                    let p = points[idx as usize];
+                    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+                });
+
+        if ear {
+            tris.extend(corner);
+            remaining.remove(i % len);
+            misses = 0;
+        } else {
+            i += 1;
+            misses += 1;
+        }
+        i %= remaining.len();
+    }
+
+    // Whatever is left if the polygon intersects itself is drawn as a fan
+    tris.extend(
+        (1..remaining.len() - 1).flat_map(|n| [remaining[0], remaining[n], remaining[n + 1]]),
+    );
+
+    tris
+}
@@ -1,37 +1,256 @@
-use crate::gfx::{
-    texture::{Format, Texture},
-    *,
+use crate::{
+    gfx::{
+        buffer::{Buffer, Map, Usage, PIXEL_PACK},
+        image::Image,
+        renderbuffer::Renderbuffer,
+        texture::{Filter, Format, Texture},
+        *,
+    },
+    mem::vec::Vec,
 };
 
-pub const SWAP_CHAIN: Framebuffer = Framebuffer(0);
+pub const SWAP_CHAIN: Framebuffer = Framebuffer {
+    id: 0,
+    draw_buffers: Vec::new(),
+};
 
 #[derive(Debug)]
-pub struct Framebuffer(GLuint);
+pub struct Framebuffer {
+    id: GLuint,
+    /// Colour attachments fragment outputs are written to, in order
+    draw_buffers: Vec<GLenum>,
+}
 
 impl Framebuffer {
     pub fn new() -> Self {
-        let mut fb = 0;
+        let mut id = 0;
+        unsafe {
+            glGenFramebuffers(1, &mut id);
+            glBindFramebuffer(GL_FRAMEBUFFER, id);
+        }
+
+        Self {
+            id,
+            draw_buffers: Vec::new(),
+        }
+    }
+
+    /// Attach mip level 0 of `tex`. Array, 3D and cube map textures are
+    /// attached as a whole for layered rendering with `gl_Layer`.
+    pub fn attach<F: Format>(&mut self, attach: Attachment, tex: &Texture<F>) {
+        self.attach_level(attach, tex, 0);
+    }
+
+    pub fn attach_level<F: Format>(&mut self, attach: Attachment, tex: &Texture<F>, level: i32) {
+        self.bind();
         unsafe {
-            glGenFramebuffers(1, &mut fb);
-            glBindFramebuffer(GL_FRAMEBUFFER, fb);
+            glFramebufferTexture(GL_FRAMEBUFFER, attach.gl(), **tex, level);
         }
+        self.attached(attach);
+    }
+
+    /// Attach a single layer of an array or 3D texture, or a single face of a
+    /// cube map in +X, -X, +Y, -Y, +Z, -Z order
+    pub fn attach_layer<F: Format>(
+        &mut self,
+        attach: Attachment,
+        tex: &Texture<F>,
+        level: i32,
+        layer: i32,
+    ) {
+        self.bind();
+        unsafe {
+            glFramebufferTextureLayer(GL_FRAMEBUFFER, attach.gl(), **tex, level, layer);
+        }
+        self.attached(attach);
+    }
 
-        Self(fb)
+    pub fn attach_renderbuffer<F: Format>(&mut self, attach: Attachment, rb: &Renderbuffer<F>) {
+        self.bind();
+        unsafe {
+            glFramebufferRenderbuffer(GL_FRAMEBUFFER, attach.gl(), GL_RENDERBUFFER, **rb);
+        }
+        self.attached(attach);
     }
 
-    pub fn attach<F: Format>(&self, attach: Attachment, tex: &Texture<F>) {
+    /// Keep the draw buffer list in sync with the colour attachments, so
+    /// output `n` goes to `Color(n)`
+    fn attached(&mut self, attach: Attachment) {
+        let Attachment::Color(_) = attach else {
+            if self.draw_buffers.is_empty() {
+                unsafe {
+                    glDrawBuffer(GL_NONE);
+                }
+            }
+            return;
+        };
+
+        if let Err(idx) = self.draw_buffers.binary_search(&attach.gl()) {
+            self.draw_buffers.insert(idx, attach.gl());
+        }
+
+        let last = self
+            .draw_buffers
+            .last()
+            .map_or(0, |buf| buf - GL_COLOR_ATTACHMENT0);
+        let buffers = (0..=last)
+            .map(|n| GL_COLOR_ATTACHMENT0 + n)
+            .map(|buf| {
+                if self.draw_buffers.contains(&buf) {
+                    buf
+                } else {
+                    GL_NONE
+                }
+            })
+            .collect::<Vec<_>>();
         unsafe {
-            glFramebufferTexture(GL_FRAMEBUFFER, attach as _, **tex, 0);
-            glDrawBuffers(1, [attach as _].as_ptr());
+            glDrawBuffers(buffers.len() as _, buffers.as_ptr());
+        }
+    }
+
+    /// Whether the attachments form a framebuffer that can be rendered to
+    pub fn check(&self) -> Result<(), Incomplete> {
+        self.bind();
+        let status = unsafe { glCheckFramebufferStatus(GL_FRAMEBUFFER) };
+
+        Err(match status {
+            GL_FRAMEBUFFER_COMPLETE => return Ok(()),
+            GL_FRAMEBUFFER_UNDEFINED => Incomplete::Undefined,
+            GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT => Incomplete::Attachment,
+            GL_FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => Incomplete::MissingAttachment,
+            GL_FRAMEBUFFER_INCOMPLETE_DRAW_BUFFER => Incomplete::DrawBuffer,
+            GL_FRAMEBUFFER_INCOMPLETE_READ_BUFFER => Incomplete::ReadBuffer,
+            GL_FRAMEBUFFER_UNSUPPORTED => Incomplete::Unsupported,
+            GL_FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => Incomplete::Multisample,
+            GL_FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => Incomplete::LayerTargets,
+            status => Incomplete::Unknown(status),
+        })
+    }
+
+    /// Copy the `[x0, y0, x1, y1]` rectangle `src` of this framebuffer into
+    /// `dst` of `target`, scaling with `filter` if they differ in size. Only
+    /// `Filter::Nearest` is allowed when copying depth or stencil.
+    pub fn blit_to(
+        &self,
+        target: &Framebuffer,
+        [sx0, sy0, sx1, sy1]: [i32; 4],
+        [dx0, dy0, dx1, dy1]: [i32; 4],
+        mask: &[Mask],
+        filter: Filter,
+    ) {
+        let mask = mask.iter().fold(0, |mask, &bit| mask | bit as u32);
+        unsafe {
+            glBindFramebuffer(GL_READ_FRAMEBUFFER, self.id);
+            glBindFramebuffer(GL_DRAW_FRAMEBUFFER, target.id);
+            glReadBuffer(self.read_buffer());
+            glBlitFramebuffer(sx0, sy0, sx1, sy1, dx0, dy0, dx1, dy1, mask, filter as _);
+        }
+    }
+
+    /// Resolve the samples of a multisampled framebuffer into the colour
+    /// buffer of `target`, usually one with a texture to sample from
+    pub fn resolve(&self, target: &Framebuffer, [w, h]: [i32; 2]) {
+        let rect = [0, 0, w, h];
+        self.blit_to(target, rect, rect, &[Mask::Color], Filter::Nearest);
+    }
+
+    fn read_buffer(&self) -> GLenum {
+        if self.id == 0 {
+            GL_BACK
+        } else {
+            GL_COLOR_ATTACHMENT0
+        }
+    }
+
+    /// Read back the `size` rectangle at `offset` of the first colour buffer
+    pub fn read_pixels(&self, offset: [i32; 2], size: [i32; 2]) -> Image {
+        let mut image = Image::new(size);
+        self.read_into(offset, size, image.pixels.as_mut_ptr() as _);
+        image.flip_vertical();
+
+        image
+    }
+
+    /// Start reading back into a pixel buffer without waiting for rendering
+    /// to finish, to be collected frames later with `Readback::poll`.
+    pub fn read_pixels_async(&self, offset: [i32; 2], size: [i32; 2]) -> Readback {
+        let buf = Buffer::with_capacity::<[u8; 4]>(
+            PIXEL_PACK,
+            Usage::StreamRead,
+            (size[0] * size[1]) as _,
+        );
+
+        self.read_into(offset, size, core::ptr::null_mut());
+        unsafe {
+            glBindBuffer(GL_PIXEL_PACK_BUFFER, 0);
+        }
+
+        Readback {
+            buf,
+            size,
+            fence: unsafe { glFenceSync(GL_SYNC_GPU_COMMANDS_COMPLETE, 0) },
+        }
+    }
+
+    fn read_into(&self, [x, y]: [i32; 2], [w, h]: [i32; 2], pixels: *mut core::ffi::c_void) {
+        self.bind();
+        unsafe {
+            glReadBuffer(self.read_buffer());
+            glPixelStorei(GL_PACK_ALIGNMENT, 1);
+            glReadPixels(x, y, w, h, GL_RGBA, GL_UNSIGNED_BYTE, pixels);
+        }
+    }
+}
+
+pub struct Readback {
+    buf: Buffer,
+    size: [i32; 2],
+    fence: GLsync,
+}
+
+impl Readback {
+    pub fn is_ready(&self) -> bool {
+        unsafe { glClientWaitSync(self.fence, 0, 0) != GL_TIMEOUT_EXPIRED }
+    }
+
+    /// The image if the GPU has finished writing it, or the pending readback
+    pub fn poll(self) -> Result<Image, Self> {
+        if self.is_ready() {
+            Ok(self.wait())
+        } else {
+            Err(self)
+        }
+    }
+
+    pub fn wait(mut self) -> Image {
+        unsafe {
+            glClientWaitSync(self.fence, GL_SYNC_FLUSH_COMMANDS_BIT, GL_TIMEOUT_IGNORED);
+        }
+
+        let mut image = Image::new(self.size);
+        if let Some(pixels) = self.buf.map::<[u8; 4]>(&[Map::Read]) {
+            let len = image.pixels.len();
+            image.pixels.copy_from_slice(&pixels[..len]);
+        }
+        image.flip_vertical();
+
+        image
+    }
+}
+
+impl Drop for Readback {
+    fn drop(&mut self) {
+        unsafe {
+            glDeleteSync(self.fence);
         }
     }
 }
 
 impl Resource for Framebuffer {
     fn bind(&self) {
-        log::debug!("binding framebuffer {}", self.0);
+        log::debug!("binding framebuffer {}", self.id);
         unsafe {
-            glBindFramebuffer(GL_FRAMEBUFFER, self.0);
+            glBindFramebuffer(GL_FRAMEBUFFER, self.id);
         }
     }
 }
@@ -40,16 +259,59 @@ impl Target for Framebuffer {}
 
 impl Drop for Framebuffer {
     fn drop(&mut self) {
-        log::debug!("dropping framebuffer {}", self.0);
+        log::debug!("dropping framebuffer {}", self.id);
         unsafe {
-            glDeleteFramebuffers(1, &self.0);
+            glDeleteFramebuffers(1, &self.id);
         }
     }
 }
 
-#[repr(u32)]
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Attachment {
-    Color0 = GL_COLOR_ATTACHMENT0,
-    Stencil = GL_STENCIL_ATTACHMENT,
+    /// Colour attachment `n`, written by fragment output location `n`
+    Color(u32),
+    Depth,
+    Stencil,
+    DepthStencil,
+}
+
+impl Attachment {
+    fn gl(self) -> GLenum {
+        match self {
+            Attachment::Color(n) => GL_COLOR_ATTACHMENT0 + n,
+            Attachment::Depth => GL_DEPTH_ATTACHMENT,
+            Attachment::Stencil => GL_STENCIL_ATTACHMENT,
+            Attachment::DepthStencil => GL_DEPTH_STENCIL_ATTACHMENT,
+        }
+    }
+}
+
+/// Buffers copied by `Framebuffer::blit_to`
+#[repr(u32)]
+#[derive(Debug, Copy, Clone)]
+pub enum Mask {
+    Color = GL_COLOR_BUFFER_BIT,
+    Depth = GL_DEPTH_BUFFER_BIT,
+    Stencil = GL_STENCIL_BUFFER_BIT,
+}
+
+/// Reasons `glCheckFramebufferStatus` gives for a framebuffer being unusable
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Incomplete {
+    /// The default framebuffer doesn't exist
+    Undefined,
+    /// An attachment is missing storage or has an unrenderable format
+    Attachment,
+    MissingAttachment,
+    /// A draw buffer names an attachment point with nothing attached
+    DrawBuffer,
+    /// The read buffer names an attachment point with nothing attached
+    ReadBuffer,
+    /// The combination of formats isn't supported by the implementation
+    Unsupported,
+    /// Attachments disagree on sample count or fixed sample locations
+    Multisample,
+    /// Some attachments are layered and others aren't
+    LayerTargets,
+    Unknown(GLenum),
 }
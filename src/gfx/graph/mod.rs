@@ -0,0 +1,649 @@
+use {
+    crate::{
+        gfx::{
+            framebuffer::{Attachment, Framebuffer},
+            query,
+            state::RenderState,
+            texture::{Format, Texture, TEX_2D},
+            Resource, *,
+        },
+        mem::{boxed::Box, vec::Vec},
+    },
+    core::{
+        any::{Any, TypeId},
+        fmt,
+        marker::PhantomData,
+    },
+};
+
+/// Size of a transient target
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    /// Follows the size given to `TargetPool::resize`, at least 1x1 for a minimized window
+    Window,
+    /// Fraction of the window size, e.g. 0.5 for a half resolution blur
+    Scaled(f32),
+    Fixed([i32; 2]),
+}
+
+/// Value a target is filled with before its first write each frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clear {
+    Color([f32; 4]),
+    Depth(f32),
+    Stencil(i32),
+    DepthStencil(f32, i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetDesc {
+    pub size: Size,
+    /// `None` leaves the previous contents, which for an aliased target are
+    /// whatever another pass left in it
+    pub clear: Option<Clear>,
+}
+
+impl TargetDesc {
+    pub fn new(size: Size, clear: Option<Clear>) -> Self {
+        Self { size, clear }
+    }
+}
+
+/// Texture of a frame graph, valid for the graph it was created by
+pub struct Handle<F: Format> {
+    idx: usize,
+    _format: PhantomData<F>,
+}
+
+impl<F: Format> Clone for Handle<F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<F: Format> Copy for Handle<F> {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphError {
+    /// Passes depend on each other's output, naming one of them
+    Cycle(&'static str),
+    /// A transient target is read but never written
+    Unwritten(&'static str),
+    /// The attachments of a pass can't be rendered to together
+    Incomplete(&'static str),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::Cycle(pass) => write!(f, "pass '{}' depends on itself", pass),
+            GraphError::Unwritten(target) => write!(f, "'{}' is read but never written", target),
+            GraphError::Incomplete(pass) => write!(f, "pass '{}' has incomplete targets", pass),
+        }
+    }
+}
+
+type AttachFn = fn(&dyn Any, &mut Framebuffer, Attachment);
+type CreateFn = fn([i32; 2]) -> (Box<dyn Any>, GLuint);
+
+fn attach<F: Format + 'static>(tex: &dyn Any, fb: &mut Framebuffer, attachment: Attachment) {
+    if let Some(tex) = tex.downcast_ref::<Texture<F>>() {
+        fb.attach(attachment, tex);
+    }
+}
+
+fn id<F: Format + 'static>(tex: &dyn Any) -> GLuint {
+    tex.downcast_ref::<Texture<F>>().map_or(0, |tex| **tex)
+}
+
+/// Textures and framebuffers kept between frames for the transient targets
+/// of `FrameGraph`s, reused by targets of the same format and size
+pub struct TargetPool {
+    size: [i32; 2],
+    slots: Vec<Slot>,
+    framebuffers: Vec<CachedFramebuffer>,
+}
+
+struct Slot {
+    format: TypeId,
+    size: [i32; 2],
+    tex: Box<dyn Any>,
+    id: GLuint,
+    used: bool,
+}
+
+struct CachedFramebuffer {
+    attachments: Vec<(Attachment, GLuint)>,
+    fb: Framebuffer,
+    used: bool,
+}
+
+impl TargetPool {
+    pub fn new(size: [i32; 2]) -> Self {
+        Self {
+            size,
+            slots: Vec::new(),
+            framebuffers: Vec::new(),
+        }
+    }
+
+    /// Change the window size, reallocating targets that depend on it on
+    /// their next use
+    pub fn resize(&mut self, size: [i32; 2]) {
+        if size != self.size {
+            self.size = size;
+            self.slots.clear();
+            self.framebuffers.clear();
+        }
+    }
+
+    pub fn size(&self) -> [i32; 2] {
+        self.size
+    }
+
+    /// Drop what wasn't used since the last call
+    fn trim(&mut self) {
+        // Framebuffers only use slots of the same frame, so this drops
+        // those of trimmed slots too
+        self.slots.retain(|slot| slot.used);
+        self.framebuffers.retain(|cached| cached.used);
+
+        for slot in &mut self.slots {
+            slot.used = false;
+        }
+        for cached in &mut self.framebuffers {
+            cached.used = false;
+        }
+    }
+}
+
+enum Source<'a> {
+    Transient {
+        desc: TargetDesc,
+        format: TypeId,
+        create: CreateFn,
+    },
+    Imported {
+        tex: &'a dyn Any,
+        id: GLuint,
+        size: [i32; 2],
+    },
+}
+
+struct TargetNode<'a> {
+    name: &'static str,
+    source: Source<'a>,
+    attach: AttachFn,
+}
+
+struct PassNode<'a> {
+    name: &'static str,
+    reads: Vec<usize>,
+    writes: Vec<(usize, Attachment)>,
+    present: Option<Option<Clear>>,
+    side_effect: bool,
+    run: Box<dyn FnOnce(&PassContext) + 'a>,
+}
+
+/// Passes of one frame. Each pass declares the targets it reads and
+/// writes, from which the graph orders the passes, skips those whose output
+/// is never used, and assigns transient targets to pooled textures, sharing
+/// them between targets whose uses don't overlap.
+///
+/// Readers of a target see it after all of its writers, which run in the
+/// order they were added.
+pub struct FrameGraph<'a> {
+    pool: &'a mut TargetPool,
+    targets: Vec<TargetNode<'a>>,
+    passes: Vec<PassNode<'a>>,
+}
+
+impl<'a> FrameGraph<'a> {
+    pub fn new(pool: &'a mut TargetPool) -> Self {
+        Self {
+            pool,
+            targets: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Texture that only lives for this frame
+    pub fn create<F: Format + 'static>(
+        &mut self,
+        name: &'static str,
+        desc: TargetDesc,
+    ) -> Handle<F> {
+        fn create<F: Format + 'static>(size: [i32; 2]) -> (Box<dyn Any>, GLuint) {
+            let tex = Texture::<F>::storage(TEX_2D, size, 1);
+            let id = *tex;
+            (Box::new(tex), id)
+        }
+
+        self.push_target(
+            name,
+            Source::Transient {
+                desc,
+                format: TypeId::of::<F>(),
+                create: create::<F>,
+            },
+            attach::<F>,
+        )
+    }
+
+    /// Texture owned elsewhere that passes can read or write, e.g. to keep
+    /// the output beyond the frame. Writes to imported textures always run.
+    pub fn import<F: Format + 'static>(
+        &mut self,
+        name: &'static str,
+        tex: &'a Texture<F>,
+    ) -> Handle<F> {
+        self.push_target(
+            name,
+            Source::Imported {
+                tex,
+                id: id::<F>(tex),
+                size: tex.size(),
+            },
+            attach::<F>,
+        )
+    }
+
+    fn push_target<F: Format>(
+        &mut self,
+        name: &'static str,
+        source: Source<'a>,
+        attach: AttachFn,
+    ) -> Handle<F> {
+        self.targets.push(TargetNode {
+            name,
+            source,
+            attach,
+        });
+
+        Handle {
+            idx: self.targets.len() - 1,
+            _format: PhantomData,
+        }
+    }
+
+    /// Start declaring a pass, added once given its `PassBuilder::execute`
+    pub fn pass<'g>(&'g mut self, name: &'static str) -> PassBuilder<'g, 'a> {
+        PassBuilder {
+            graph: self,
+            node: PassNode {
+                name,
+                reads: Vec::new(),
+                writes: Vec::new(),
+                present: None,
+                side_effect: false,
+                run: Box::new(|_| {}),
+            },
+        }
+    }
+
+    /// Run the passes, releasing pooled targets this frame didn't need
+    pub fn execute(self) -> Result<(), GraphError> {
+        let order = self.order()?;
+        let FrameGraph {
+            pool,
+            targets,
+            passes,
+        } = self;
+
+        // Last position in `order` each transient is used at
+        let mut last_use = crate::mem::vec![0; targets.len()];
+        for (step, &pass) in order.iter().enumerate() {
+            let pass = &passes[pass];
+            for idx in pass
+                .reads
+                .iter()
+                .chain(pass.writes.iter().map(|(idx, _)| idx))
+            {
+                last_use[*idx] = step;
+            }
+        }
+
+        // Pool slot of each transient, and which slots are taken until when
+        let mut assigned: Vec<Option<usize>> = crate::mem::vec![None; targets.len()];
+        let mut busy_until: Vec<Option<usize>> = crate::mem::vec![None; pool.slots.len()];
+        let mut cleared = crate::mem::vec![false; targets.len()];
+
+        let mut passes: Vec<_> = passes.into_iter().map(Some).collect();
+        for (step, &idx) in order.iter().enumerate() {
+            let pass = passes[idx].take().expect("pass ordered twice");
+
+            for &(target, _) in &pass.writes {
+                if let Source::Transient {
+                    desc,
+                    format,
+                    create,
+                } = &targets[target].source
+                {
+                    if assigned[target].is_some() {
+                        continue;
+                    }
+
+                    let size = resolve(pool.size, desc.size);
+                    let free = pool.slots.iter().enumerate().position(|(slot, candidate)| {
+                        candidate.format == *format
+                            && candidate.size == size
+                            && !matches!(busy_until[slot], Some(until) if until >= step)
+                    });
+                    let slot = free.unwrap_or_else(|| {
+                        log::debug!("allocating {:?} target '{}'", size, targets[target].name);
+                        let (tex, id) = create(size);
+                        pool.slots.push(Slot {
+                            format: *format,
+                            size,
+                            tex,
+                            id,
+                            used: false,
+                        });
+                        busy_until.push(None);
+
+                        pool.slots.len() - 1
+                    });
+
+                    pool.slots[slot].used = true;
+                    busy_until[slot] = Some(last_use[target]);
+                    assigned[target] = Some(slot);
+                }
+            }
+
+            let context = PassContext {
+                targets: &targets,
+                assigned: &assigned,
+                slots: &pool.slots,
+                window: pool.size,
+                size: [0, 0],
+            };
+
+            let size = if pass.present.is_some() {
+                SWAP_CHAIN.bind();
+                Some(pool.size)
+            } else if pass.writes.is_empty() {
+                None
+            } else {
+                let attachments: Vec<_> = pass
+                    .writes
+                    .iter()
+                    .map(|&(target, attachment)| (attachment, context.id(target)))
+                    .collect();
+
+                let cached = match pool
+                    .framebuffers
+                    .iter()
+                    .position(|cached| cached.attachments == attachments)
+                {
+                    Some(cached) => cached,
+                    None => {
+                        let mut fb = Framebuffer::new();
+                        for &(target, attachment) in &pass.writes {
+                            (targets[target].attach)(context.any(target), &mut fb, attachment);
+                        }
+                        if fb.check().is_err() {
+                            return Err(GraphError::Incomplete(pass.name));
+                        }
+
+                        pool.framebuffers.push(CachedFramebuffer {
+                            attachments,
+                            fb,
+                            used: false,
+                        });
+                        pool.framebuffers.len() - 1
+                    }
+                };
+                pool.framebuffers[cached].used = true;
+                pool.framebuffers[cached].fb.bind();
+
+                Some(context.target_size(pass.writes[0].0))
+            };
+
+            if let Some([w, h]) = size {
+                unsafe {
+                    glViewport(0, 0, w, h);
+                }
+            }
+
+            // Clear transients before their first write, and the swap chain
+            // if asked to
+            for &(target, attachment) in &pass.writes {
+                if let Source::Transient {
+                    desc:
+                        TargetDesc {
+                            clear: Some(clear), ..
+                        },
+                    ..
+                } = targets[target].source
+                {
+                    if !cleared[target] {
+                        clear_attachment(attachment, clear);
+                    }
+                }
+                cleared[target] = true;
+            }
+            if let Some(Some(clear)) = pass.present {
+                clear_attachment(Attachment::Color(0), clear);
+            }
+
+            log::trace!("running pass '{}'", pass.name);
+            let _scope = query::scope(pass.name);
+            (pass.run)(&PassContext {
+                targets: &targets,
+                assigned: &assigned,
+                slots: &pool.slots,
+                window: pool.size,
+                size: size.unwrap_or(pool.size),
+            });
+        }
+
+        pool.trim();
+
+        Ok(())
+    }
+
+    /// Passes to run, dependencies first, ties broken by the order they were
+    /// added in
+    fn order(&self) -> Result<Vec<usize>, GraphError> {
+        let writers = |target: usize| {
+            (0..self.passes.len()).filter(move |&pass| {
+                self.passes[pass]
+                    .writes
+                    .iter()
+                    .any(|&(idx, _)| idx == target)
+            })
+        };
+
+        // Only passes leading to an output are needed
+        let mut needed: Vec<bool> = self
+            .passes
+            .iter()
+            .map(|pass| {
+                pass.present.is_some()
+                    || pass.side_effect
+                    || pass.writes.iter().any(|&(idx, _)| {
+                        matches!(self.targets[idx].source, Source::Imported { .. })
+                    })
+            })
+            .collect();
+        let mut stack: Vec<_> = (0..needed.len()).filter(|&pass| needed[pass]).collect();
+        while let Some(pass) = stack.pop() {
+            for &target in &self.passes[pass].reads {
+                if writers(target).next().is_none() {
+                    if let Source::Transient { .. } = self.targets[target].source {
+                        return Err(GraphError::Unwritten(self.targets[target].name));
+                    }
+                }
+
+                for writer in writers(target) {
+                    if !needed[writer] {
+                        needed[writer] = true;
+                        stack.push(writer);
+                    }
+                }
+            }
+        }
+
+        // A pass waits on the writers of what it reads, and on earlier
+        // writers of what it writes
+        let depends = |pass: usize, on: usize| {
+            let node = &self.passes[pass];
+            node.reads
+                .iter()
+                .any(|&target| writers(target).any(|w| w == on))
+                || (on < pass
+                    && node
+                        .writes
+                        .iter()
+                        .any(|&(target, _)| writers(target).any(|w| w == on)))
+        };
+
+        let mut order = Vec::new();
+        let mut done = crate::mem::vec![false; self.passes.len()];
+        while order.len() < needed.iter().filter(|&&needed| needed).count() {
+            let next = (0..self.passes.len()).find(|&pass| {
+                needed[pass]
+                    && !done[pass]
+                    && (0..self.passes.len())
+                        .all(|on| on == pass || !needed[on] || done[on] || !depends(pass, on))
+            });
+
+            match next {
+                Some(pass) => {
+                    done[pass] = true;
+                    order.push(pass);
+                }
+                None => {
+                    let stuck = (0..self.passes.len())
+                        .find(|&pass| needed[pass] && !done[pass])
+                        .map_or("", |pass| self.passes[pass].name);
+                    return Err(GraphError::Cycle(stuck));
+                }
+            }
+        }
+
+        Ok(order)
+    }
+}
+
+fn resolve(window: [i32; 2], size: Size) -> [i32; 2] {
+    match size {
+        Size::Window => window.map(|n| n.max(1)),
+        Size::Scaled(scale) => window.map(|n| ((n as f32 * scale) as i32).max(1)),
+        Size::Fixed(size) => size,
+    }
+}
+
+fn clear_attachment(attachment: Attachment, clear: Clear) {
+    // Targets are cleared whole, whatever scissor the caller has set
+    let prev = RenderState::current();
+    RenderState {
+        scissor: None,
+        ..prev
+    }
+    .apply();
+
+    RenderState::clearing(|| unsafe {
+        match (attachment, clear) {
+            (Attachment::Color(n), Clear::Color(rgba)) => {
+                glClearBufferfv(GL_COLOR, n as _, rgba.as_ptr())
+            }
+            (_, Clear::Depth(depth)) => glClearBufferfv(GL_DEPTH, 0, &depth),
+            (_, Clear::Stencil(stencil)) => glClearBufferiv(GL_STENCIL, 0, &stencil),
+            (_, Clear::DepthStencil(depth, stencil)) => {
+                glClearBufferfi(GL_DEPTH_STENCIL, 0, depth, stencil)
+            }
+            (attachment, clear) => {
+                log::warn!("can't clear {:?} with {:?}", attachment, clear)
+            }
+        }
+    });
+    prev.apply();
+}
+
+/// Declaration of a pass, see `FrameGraph::pass`
+pub struct PassBuilder<'g, 'a> {
+    graph: &'g mut FrameGraph<'a>,
+    node: PassNode<'a>,
+}
+
+impl<'g, 'a> PassBuilder<'g, 'a> {
+    /// Sample `target`, which has to be written by another pass
+    pub fn read<F: Format>(mut self, target: Handle<F>) -> Self {
+        self.node.reads.push(target.idx);
+        self
+    }
+
+    /// Render to `target`. All targets written by a pass need the same size.
+    pub fn write<F: Format>(mut self, target: Handle<F>, attachment: Attachment) -> Self {
+        self.node.writes.push((target.idx, attachment));
+        self
+    }
+
+    /// Render to the window, clearing it first with `clear`
+    pub fn present(mut self, clear: Option<Clear>) -> Self {
+        self.node.present = Some(clear);
+        self
+    }
+
+    /// Run the pass even if nothing reads its output
+    pub fn side_effect(mut self) -> Self {
+        self.node.side_effect = true;
+        self
+    }
+
+    /// Add the pass, `run` being called with its targets bound when the
+    /// graph executes
+    pub fn execute(mut self, run: impl FnOnce(&PassContext) + 'a) {
+        self.node.run = Box::new(run);
+        self.graph.passes.push(self.node);
+    }
+}
+
+/// Access to the targets of a graph while a pass runs
+pub struct PassContext<'p> {
+    targets: &'p [TargetNode<'p>],
+    assigned: &'p [Option<usize>],
+    slots: &'p [Slot],
+    window: [i32; 2],
+    size: [i32; 2],
+}
+
+impl PassContext<'_> {
+    /// Texture behind `target`. Transient targets can only be accessed by
+    /// passes declaring them.
+    pub fn texture<F: Format + 'static>(&self, target: Handle<F>) -> &Texture<F> {
+        self.any(target.idx)
+            .downcast_ref()
+            .expect("frame graph target of another format")
+    }
+
+    /// Size of the targets being rendered to
+    pub fn size(&self) -> [i32; 2] {
+        self.size
+    }
+
+    fn any(&self, target: usize) -> &dyn Any {
+        match &self.targets[target].source {
+            Source::Imported { tex, .. } => *tex,
+            Source::Transient { .. } => {
+                let slot = self.assigned[target].expect("transient target used before written");
+                &*self.slots[slot].tex
+            }
+        }
+    }
+
+    fn id(&self, target: usize) -> GLuint {
+        match &self.targets[target].source {
+            Source::Imported { id, .. } => *id,
+            Source::Transient { .. } => self.assigned[target].map_or(0, |slot| self.slots[slot].id),
+        }
+    }
+
+    fn target_size(&self, target: usize) -> [i32; 2] {
+        match &self.targets[target].source {
+            Source::Imported { size, .. } => *size,
+            Source::Transient { desc, .. } => resolve(self.window, desc.size),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use {
+    super::{DecodeError, Image},
+    crate::mem::vec::Vec,
+};
+
+pub fn decode(data: &[u8]) -> Result<Image, DecodeError> {
+    let u16_at = |at: usize| {
+        data.get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .ok_or(DecodeError::Truncated)
+    };
+    let u32_at = |at: usize| {
+        data.get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or(DecodeError::Truncated)
+    };
+
+    let offset = u32_at(10)? as usize;
+    let dib = u32_at(14)? as usize;
+    let (w, h, bpp, compression, colors) = match dib {
+        12 => (
+            u16_at(18)? as i32,
+            u16_at(20)? as i16 as i32,
+            u16_at(24)?,
+            0,
+            0,
+        ),
+        _ => (
+            u32_at(18)? as i32,
+            u32_at(22)? as i32,
+            u16_at(28)?,
+            u32_at(30)?,
+            u32_at(46)? as usize,
+        ),
+    };
+
+    // Colour masks of BI_BITFIELDS/BI_ALPHABITFIELDS follow the 40 byte
+    // header, or are part of it for V4 and V5 headers
+    let masks = match (compression, bpp) {
+        (0, 16) => [0x7C00, 0x03E0, 0x001F, 0],
+        (0, 24 | 32) => [0xFF_0000, 0xFF00, 0xFF, 0],
+        (3 | 6, 16 | 32) => [
+            u32_at(54)?,
+            u32_at(58)?,
+            u32_at(62)?,
+            if dib >= 56 || compression == 6 {
+                u32_at(66)?
+            } else {
+                0
+            },
+        ],
+        (0, 1 | 4 | 8) => [0; 4],
+        _ => return Err(DecodeError::Unsupported),
+    };
+
+    let palette = match bpp {
+        1 | 4 | 8 => {
+            let entry = if dib == 12 { 3 } else { 4 };
+            let count = if colors == 0 { 1 << bpp } else { colors };
+            let start = 14 + dib;
+            data.get(start..start + entry * count)
+                .ok_or(DecodeError::Truncated)?
+                .chunks_exact(entry)
+                .map(|bgr| [bgr[2], bgr[1], bgr[0], 0xFF])
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+
+    if w <= 0 || h == 0 {
+        return Err(DecodeError::Corrupt);
+    }
+
+    let [w, rows] = [w as usize, h.unsigned_abs() as usize];
+    let stride = w
+        .checked_mul(bpp as usize)
+        .ok_or(DecodeError::Corrupt)?
+        .div_ceil(32)
+        * 4;
+    let payload = data.len().saturating_sub(offset);
+    if !matches!(stride.checked_mul(rows), Some(size) if size <= payload) {
+        return Err(DecodeError::Truncated);
+    }
+    let mut image = Image::sized([w, rows], payload.saturating_mul(8))?;
+    for y in 0..rows {
+        let row = data
+            .get(offset + y * stride..offset + (y + 1) * stride)
+            .ok_or(DecodeError::Truncated)?;
+
+        // Rows are stored bottom up unless the height is negative
+        let dst = if h < 0 { y } else { rows - 1 - y };
+        for x in 0..w {
+            image.pixels[dst * w + x] = match bpp {
+                1 | 4 | 8 => {
+                    let bit = x * bpp as usize;
+                    let idx =
+                        (row[bit / 8] >> (8 - bpp as usize - bit % 8)) & ((1 << bpp) - 1) as u8;
+                    *palette.get(idx as usize).ok_or(DecodeError::Corrupt)?
+                }
+                16 => masked(
+                    u16::from_le_bytes([row[2 * x], row[2 * x + 1]]) as u32,
+                    masks,
+                ),
+                24 => [row[3 * x + 2], row[3 * x + 1], row[3 * x], 0xFF],
+                _ => masked(
+                    u32::from_le_bytes([
+                        row[4 * x],
+                        row[4 * x + 1],
+                        row[4 * x + 2],
+                        row[4 * x + 3],
+                    ]),
+                    masks,
+                ),
+            };
+        }
+    }
+
+    Ok(image)
+}
+
+fn masked(value: u32, masks: [u32; 4]) -> [u8; 4] {
+    masks.map(|mask| {
+        if mask == 0 {
+            return 0xFF;
+        }
+
+        let shift = mask.trailing_zeros();
+        let max = (1u64 << (mask >> shift).count_ones()) - 1;
+        (((value & mask) >> shift) as u64 * 255 / max) as u8
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BMP with a 40 byte header, `rows` as stored
+    fn bmp(bpp: u16, h: i32, palette: &[[u8; 4]], rows: &[u8]) -> Vec<u8> {
+        let offset = 54 + 4 * palette.len() as u32;
+        let mut bmp = b"BM".to_vec();
+        for v in [offset + rows.len() as u32, 0, offset, 40, 2, h as u32] {
+            bmp.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [1, bpp] {
+            bmp.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [0, rows.len() as u32, 2835, 2835, palette.len() as u32, 0] {
+            bmp.extend_from_slice(&v.to_le_bytes());
+        }
+        bmp.extend(palette.iter().flatten());
+        bmp.extend_from_slice(rows);
+
+        bmp
+    }
+
+    #[test]
+    fn bgr_bottom_up() {
+        let rows = [
+            0xFF, 0, 0, 0xFF, 0xFF, 0xFF, 0, 0, // blue, white
+            0, 0, 0xFF, 0, 0xFF, 0, 0, 0, // red, green
+        ];
+        let image = decode(&bmp(24, 2, &[], &rows)).unwrap();
+        assert_eq!(image.size, [2, 2]);
+        assert_eq!(
+            image.pixels,
+            [
+                [0xFF, 0, 0, 0xFF],
+                [0, 0xFF, 0, 0xFF],
+                [0, 0, 0xFF, 0xFF],
+                [0xFF, 0xFF, 0xFF, 0xFF],
+            ]
+        );
+    }
+
+    #[test]
+    fn palette_top_down() {
+        let palette = [[0, 0, 0, 0], [0x40, 0x80, 0xC0, 0]];
+        let rows = [0b0100_0000, 0, 0, 0, 0b1000_0000, 0, 0, 0];
+        let image = decode(&bmp(1, -2, &palette, &rows)).unwrap();
+        let [k, c] = [[0, 0, 0, 0xFF], [0xC0, 0x80, 0x40, 0xFF]];
+        assert_eq!(image.pixels, [k, c, c, k]);
+    }
+
+    #[test]
+    fn truncated() {
+        let data = bmp(24, 2, &[], &[0; 16]);
+        assert_eq!(decode(&data[..60]).unwrap_err(), DecodeError::Truncated);
+    }
+}
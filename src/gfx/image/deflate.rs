@@ -0,0 +1,179 @@
+use {
+    super::inflate::{adler32, DIST_BASE, DIST_EXTRA, LEN_BASE, LEN_EXTRA},
+    crate::mem::{vec, vec::Vec},
+};
+
+const WINDOW: usize = 1 << 15;
+const HASH_BITS: u32 = 15;
+const MAX_CHAIN: usize = 32;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// Compress into a zlib stream of one fixed Huffman block, with greedy LZ77
+/// matching over hash chains
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter {
+        bytes: vec![0x78, 0x01],
+        acc: 0,
+        len: 0,
+    };
+
+    out.bits(1, 1);
+    out.bits(1, 2);
+
+    let hash = |at: usize| {
+        let v = (data[at] as u32) << 16 | (data[at + 1] as u32) << 8 | data[at + 2] as u32;
+        (v.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+    };
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let mut pos = 0;
+    while pos < data.len() {
+        let mut best = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(pos);
+            let mut candidate = head[h];
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || pos - candidate > WINDOW - 1 {
+                    break;
+                }
+
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..])
+                    .take(MAX_MATCH)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best.0 {
+                    best = (len, pos - candidate);
+                }
+
+                candidate = prev[candidate % WINDOW];
+            }
+
+            prev[pos % WINDOW] = head[h];
+            head[h] = pos;
+        }
+
+        match best {
+            (len, dist) if len >= MIN_MATCH => {
+                out.length(len);
+                out.distance(dist);
+
+                // Keep the skipped positions findable by later matches
+                for at in pos + 1..(pos + len).min(data.len().saturating_sub(MIN_MATCH - 1)) {
+                    let h = hash(at);
+                    prev[at % WINDOW] = head[h];
+                    head[h] = at;
+                }
+                pos += len;
+            }
+            _ => {
+                out.literal(data[pos] as u16);
+                pos += 1;
+            }
+        }
+    }
+
+    out.literal(256);
+    let mut bytes = out.finish();
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+
+    bytes
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    len: u32,
+}
+
+impl BitWriter {
+    fn bits(&mut self, value: u32, n: u32) {
+        self.acc |= value << self.len;
+        self.len += n;
+        while self.len >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    /// Huffman codes are packed starting from their most significant bit
+    fn code(&mut self, code: u32, n: u32) {
+        self.bits(code.reverse_bits() >> (32 - n), n);
+    }
+
+    fn literal(&mut self, sym: u16) {
+        match sym {
+            0..=143 => self.code(0x30 + sym as u32, 8),
+            144..=255 => self.code(0x190 + sym as u32 - 144, 9),
+            256..=279 => self.code(sym as u32 - 256, 7),
+            _ => self.code(0xC0 + sym as u32 - 280, 8),
+        }
+    }
+
+    fn length(&mut self, len: usize) {
+        let idx = LEN_BASE
+            .iter()
+            .rposition(|&base| base as usize <= len)
+            .unwrap_or(0);
+        self.literal(257 + idx as u16);
+        self.bits((len - LEN_BASE[idx] as usize) as u32, LEN_EXTRA[idx] as u32);
+    }
+
+    fn distance(&mut self, dist: usize) {
+        let idx = DIST_BASE
+            .iter()
+            .rposition(|&base| base as usize <= dist)
+            .unwrap_or(0);
+        self.code(idx as u32, 5);
+        self.bits(
+            (dist - DIST_BASE[idx] as usize) as u32,
+            DIST_EXTRA[idx] as u32,
+        );
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::gfx::image::inflate};
+
+    fn round_trip(data: &[u8]) {
+        let packed = zlib(data);
+        assert_eq!(inflate::zlib(&packed).unwrap(), data);
+    }
+
+    #[test]
+    fn empty() {
+        round_trip(&[]);
+        round_trip(b"a");
+    }
+
+    #[test]
+    fn matches() {
+        round_trip(b"abcabcabcabcabcabc");
+        let long = vec![7; 1000];
+        round_trip(&long);
+        assert!(zlib(&long).len() < 40);
+    }
+
+    #[test]
+    fn far_matches() {
+        // A block repeated further back than the window reaches
+        let block = (0..40_000u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 24) as u8)
+            .collect::<Vec<_>>();
+        let data = [&block[..], &block[..]].concat();
+        round_trip(&data);
+    }
+}
@@ -0,0 +1,304 @@
+use {super::DecodeError, crate::mem::vec::Vec};
+
+pub const LEN_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+pub const LEN_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+pub const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+pub const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// Order code length code lengths are stored in
+const CLEN_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompress a zlib stream, checking its header and Adler-32 trailer
+pub fn zlib(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    match data {
+        [cmf, flg, ..] if cmf & 0x0F == 8 && flg & 0x20 == 0 => {
+            if !u16::from_be_bytes([*cmf, *flg]).is_multiple_of(31) {
+                return Err(DecodeError::Corrupt);
+            }
+        }
+        [_, _, ..] => return Err(DecodeError::Unsupported),
+        _ => return Err(DecodeError::Truncated),
+    }
+
+    let mut out = Vec::new();
+    let end = inflate(&data[2..], &mut out)? + 2;
+    let adler = data
+        .get(end..end + 4)
+        .ok_or(DecodeError::Truncated)?
+        .iter()
+        .fold(0, |adler, &byte| adler << 8 | byte as u32);
+
+    if adler != adler32(&out) {
+        return Err(DecodeError::Checksum);
+    }
+
+    Ok(out)
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data
+        .chunks(5552)
+        .fold((1u32, 0u32), |(mut a, mut b), chunk| {
+            for &byte in chunk {
+                a += byte as u32;
+                b += a;
+            }
+
+            (a % 65521, b % 65521)
+        });
+
+    b << 16 | a
+}
+
+/// Decompress raw DEFLATE blocks into `out`, returning the number of bytes
+/// of `data` consumed
+pub fn inflate(data: &[u8], out: &mut Vec<u8>) -> Result<usize, DecodeError> {
+    let mut bits = Bits { data, pos: 0 };
+
+    loop {
+        let last = bits.read(1)? == 1;
+        match bits.read(2)? {
+            0 => {
+                // Stored block, byte aligned
+                bits.pos = (bits.pos + 7) & !7;
+                let start = bits.pos / 8;
+                let header = data.get(start..start + 4).ok_or(DecodeError::Truncated)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err(DecodeError::Corrupt);
+                }
+
+                let block = data
+                    .get(start + 4..start + 4 + len as usize)
+                    .ok_or(DecodeError::Truncated)?;
+                out.extend_from_slice(block);
+                bits.pos += (4 + len as usize) * 8;
+            }
+
+            1 => {
+                let mut lengths = [0u8; 288 + 32];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..288].fill(8);
+                lengths[288..].fill(5);
+
+                let lit = Huffman::new(&lengths[..288]);
+                let dist = Huffman::new(&lengths[288..]);
+                bits.codes(&lit, &dist, out)?;
+            }
+
+            2 => {
+                let nlit = bits.read(5)? as usize + 257;
+                let ndist = bits.read(5)? as usize + 1;
+                let nclen = bits.read(4)? as usize + 4;
+
+                let mut clens = [0u8; 19];
+                for &idx in CLEN_ORDER.iter().take(nclen) {
+                    clens[idx] = bits.read(3)? as u8;
+                }
+                let clen = Huffman::new(&clens);
+
+                let mut lengths = [0u8; 288 + 32];
+                let mut idx = 0;
+                while idx < nlit + ndist {
+                    let (len, repeat) = match clen.decode(&mut bits)? {
+                        sym @ 0..=15 => (sym as u8, 1),
+                        16 if idx > 0 => (lengths[idx - 1], 3 + bits.read(2)?),
+                        17 => (0, 3 + bits.read(3)?),
+                        18 => (0, 11 + bits.read(7)?),
+                        _ => return Err(DecodeError::Corrupt),
+                    };
+
+                    let end = idx + repeat as usize;
+                    if end > nlit + ndist {
+                        return Err(DecodeError::Corrupt);
+                    }
+                    lengths[idx..end].fill(len);
+                    idx = end;
+                }
+
+                let lit = Huffman::new(&lengths[..nlit]);
+                let dist = Huffman::new(&lengths[nlit..nlit + ndist]);
+                bits.codes(&lit, &dist, out)?;
+            }
+
+            _ => return Err(DecodeError::Corrupt),
+        }
+
+        if last {
+            return Ok(bits.pos.div_ceil(8));
+        }
+    }
+}
+
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn read(&mut self, n: u32) -> Result<u32, DecodeError> {
+        let mut value = 0;
+        for i in 0..n {
+            let byte = *self.data.get(self.pos / 8).ok_or(DecodeError::Truncated)?;
+            value |= ((byte >> (self.pos % 8)) as u32 & 1) << i;
+            self.pos += 1;
+        }
+
+        Ok(value)
+    }
+
+    fn codes(
+        &mut self,
+        lit: &Huffman,
+        dist: &Huffman,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DecodeError> {
+        loop {
+            match lit.decode(self)? as usize {
+                sym @ 0..=255 => out.push(sym as u8),
+                256 => return Ok(()),
+                sym @ 257..=285 => {
+                    let sym = sym - 257;
+                    let len = LEN_BASE[sym] as usize + self.read(LEN_EXTRA[sym] as u32)? as usize;
+
+                    let sym = dist.decode(self)? as usize;
+                    if sym >= 30 {
+                        return Err(DecodeError::Corrupt);
+                    }
+                    let dist =
+                        DIST_BASE[sym] as usize + self.read(DIST_EXTRA[sym] as u32)? as usize;
+                    if dist > out.len() {
+                        return Err(DecodeError::Corrupt);
+                    }
+
+                    // Copy byte by byte since the match may overlap itself
+                    let start = out.len() - dist;
+                    for i in 0..len {
+                        out.push(out[start + i]);
+                    }
+                }
+                _ => return Err(DecodeError::Corrupt),
+            }
+        }
+    }
+}
+
+/// Canonical Huffman code, decoded one bit at a time
+struct Huffman {
+    counts: [u16; 16],
+    symbols: [u16; 288],
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = [0u16; 288];
+        for (sym, &len) in lengths.iter().enumerate().filter(|(_, &len)| len != 0) {
+            symbols[offsets[len as usize] as usize] = sym as u16;
+            offsets[len as usize] += 1;
+        }
+
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, DecodeError> {
+        let (mut code, mut first, mut idx) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= bits.read(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(idx + code - first) as usize]);
+            }
+
+            idx += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(DecodeError::Corrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_block() {
+        let data = [
+            0x78, 0x01, 0x01, 0x05, 0x00, 0xFA, 0xFF, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x06, 0x2C,
+            0x02, 0x15,
+        ];
+        assert_eq!(zlib(&data).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn fixed_block() {
+        let data = [
+            0x78, 0xDA, 0x4B, 0x4C, 0x4A, 0x4E, 0x44, 0x45, 0x00, 0x41, 0x7C, 0x06, 0xE5,
+        ];
+        assert_eq!(zlib(&data).unwrap(), b"abcabcabcabcabcabc");
+    }
+
+    #[test]
+    fn dynamic_block() {
+        let data = [
+            0x78, 0xDA, 0x1D, 0x88, 0xC7, 0x11, 0x00, 0x00, 0x0C, 0x82, 0x66, 0xB5, 0xEC, 0x3F,
+            0x43, 0x24, 0x3E, 0x90, 0x43, 0x4E, 0xA2, 0x6D, 0xB0, 0xDE, 0x8A, 0x2E, 0x9A, 0x1B,
+            0x4B, 0x34, 0x4F, 0xD1, 0x01, 0xE7, 0x3F, 0x13, 0x18,
+        ];
+        assert_eq!(
+            zlib(&data).unwrap(),
+            b"abcccaaaacaabacaaaadcaabccabaabcabadaaaabbadabaaba"
+        );
+    }
+
+    #[test]
+    fn bad_streams() {
+        let mut data = [
+            0x78, 0x01, 0x01, 0x05, 0x00, 0xFA, 0xFF, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x06, 0x2C,
+            0x02, 0x15,
+        ];
+        assert_eq!(zlib(&data[..10]), Err(DecodeError::Truncated));
+
+        data[15] ^= 1;
+        assert_eq!(zlib(&data), Err(DecodeError::Checksum));
+
+        // NLEN is not the complement of LEN
+        data[5] ^= 1;
+        assert_eq!(zlib(&data), Err(DecodeError::Corrupt));
+
+        assert_eq!(zlib(&[0x79, 0x01]), Err(DecodeError::Unsupported));
+    }
+
+    #[test]
+    fn adler() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    }
+}
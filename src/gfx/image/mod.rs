@@ -0,0 +1,116 @@
+mod bmp;
+mod deflate;
+mod inflate;
+mod png;
+mod qoi;
+mod tga;
+
+use crate::{
+    gfx::texture::{Format, Texture, TEX_2D},
+    mem::{vec, vec::Vec},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DecodeError {
+    Truncated,
+    Corrupt,
+    Checksum,
+    Unsupported,
+}
+
+/// RGBA8 pixels, rows from top to bottom as stored in image files
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub size: [i32; 2],
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    /// Transparent black image of `size`
+    pub fn new(size: [i32; 2]) -> Self {
+        let [w, h] = size.map(|n| n.max(0) as usize);
+        Self {
+            size,
+            pixels: vec![[0; 4]; w * h],
+        }
+    }
+
+    /// Image for a decoder, whose payload holds at most `max_pixels` pixels,
+    /// checking the size from the header before allocating
+    fn sized([w, h]: [usize; 2], max_pixels: usize) -> Result<Self, DecodeError> {
+        let count = w
+            .checked_mul(h)
+            .filter(|_| w <= i32::MAX as usize && h <= i32::MAX as usize)
+            .ok_or(DecodeError::Corrupt)?;
+        if count > max_pixels {
+            return Err(DecodeError::Truncated);
+        }
+
+        Ok(Self::new([w as _, h as _]))
+    }
+
+    /// Decode a PNG, BMP, QOI or TGA file, converting palette, greyscale and
+    /// colour-keyed images to RGBA
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        if data.starts_with(&png::SIGNATURE) {
+            png::decode(data)
+        } else if data.starts_with(b"BM") {
+            bmp::decode(data)
+        } else if data.starts_with(&qoi::MAGIC) {
+            qoi::decode(data)
+        } else {
+            tga::decode(data)
+        }
+    }
+
+    pub fn flip_vertical(&mut self) {
+        let w = self.size[0] as usize;
+        let h = self.size[1] as usize;
+        for y in 0..h / 2 {
+            let (top, bottom) = self.pixels.split_at_mut((h - 1 - y) * w);
+            top[y * w..(y + 1) * w].swap_with_slice(&mut bottom[..w]);
+        }
+    }
+
+    pub fn premultiply_alpha(&mut self) {
+        for [r, g, b, a] in self.pixels.iter_mut() {
+            let mul = |c: &mut u8| *c = (*c as u16 * *a as u16 / 255) as u8;
+            mul(r);
+            mul(g);
+            mul(b);
+        }
+    }
+
+    /// Read back level 0 of an `Rgba8` or `Srgb8Alpha8` texture
+    pub fn from_texture<F: Format<Pixel = [u8; 4]>>(tex: &Texture<F>) -> Self {
+        let mut image = Self {
+            size: tex.size(),
+            pixels: tex.read_pixels(0),
+        };
+        image.flip_vertical();
+
+        image
+    }
+
+    pub fn encode_png(&self) -> Vec<u8> {
+        png::encode(self)
+    }
+
+    /// Binary PPM, dropping alpha
+    pub fn encode_ppm(&self) -> Vec<u8> {
+        let mut ppm =
+            crate::mem::format!("P6\n{} {}\n255\n", self.size[0], self.size[1]).into_bytes();
+        ppm.extend(self.pixels.iter().flat_map(|&[r, g, b, _]| [r, g, b]));
+
+        ppm
+    }
+
+    /// Upload as an `Rgba8` or `Srgb8Alpha8` texture, flipping rows into GL's
+    /// bottom-up order so the image appears upright
+    pub fn texture<F: Format<Pixel = [u8; 4]>>(&self) -> Texture<F> {
+        let mut flipped = self.clone();
+        flipped.flip_vertical();
+
+        Texture::from_pixels(TEX_2D, self.size, &flipped.pixels)
+    }
+}
@@ -0,0 +1,396 @@
+use {
+    super::{deflate, inflate, DecodeError, Image},
+    crate::mem::vec::Vec,
+};
+
+pub const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xEDB88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+
+    table
+};
+
+/// Pass origins and strides of Adam7 interlacing
+const ADAM7: [[usize; 4]; 7] = [
+    [0, 0, 8, 8],
+    [4, 0, 8, 8],
+    [0, 4, 4, 8],
+    [2, 0, 4, 4],
+    [0, 2, 2, 4],
+    [1, 0, 2, 2],
+    [0, 1, 1, 2],
+];
+
+struct Header {
+    size: [usize; 2],
+    depth: u8,
+    color: u8,
+    interlaced: bool,
+}
+
+pub fn decode(data: &[u8]) -> Result<Image, DecodeError> {
+    let mut chunks = data.get(SIGNATURE.len()..).ok_or(DecodeError::Truncated)?;
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut transparency = Vec::new();
+    let mut idat = Vec::new();
+
+    while chunks.len() >= 12 {
+        let len = u32::from_be_bytes([chunks[0], chunks[1], chunks[2], chunks[3]]) as usize;
+        let kind = &chunks[4..8];
+        let body = chunks.get(8..8 + len).ok_or(DecodeError::Truncated)?;
+        let stored = chunks
+            .get(8 + len..12 + len)
+            .ok_or(DecodeError::Truncated)?;
+        if crc(kind, body).to_be_bytes() != stored {
+            return Err(DecodeError::Checksum);
+        }
+        chunks = &chunks[12 + len..];
+
+        match kind {
+            b"IHDR" if body.len() == 13 => {
+                header = Some(Header {
+                    size: [
+                        u32::from_be_bytes([body[0], body[1], body[2], body[3]]) as usize,
+                        u32::from_be_bytes([body[4], body[5], body[6], body[7]]) as usize,
+                    ],
+                    depth: body[8],
+                    color: body[9],
+                    interlaced: body[12] == 1,
+                });
+            }
+            b"PLTE" => {
+                palette = body
+                    .chunks_exact(3)
+                    .map(|rgb| [rgb[0], rgb[1], rgb[2], 0xFF])
+                    .collect();
+            }
+            b"tRNS" => transparency = body.into(),
+            b"IDAT" => idat.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let header = header.ok_or(DecodeError::Corrupt)?;
+    let channels = match (header.color, header.depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (2, 8 | 16) => 3,
+        (4, 8 | 16) => 2,
+        (6, 8 | 16) => 4,
+        _ => return Err(DecodeError::Unsupported),
+    };
+
+    // Palette alpha is stored alongside the palette, other colour types have
+    // a single fully transparent key colour
+    for (entry, &alpha) in palette.iter_mut().zip(transparency.iter()) {
+        entry[3] = alpha;
+    }
+    let key = (header.color != 3 && !transparency.is_empty()).then(|| {
+        transparency
+            .chunks_exact(2)
+            .map(|sample| u16::from_be_bytes([sample[0], sample[1]]))
+            .collect::<Vec<_>>()
+    });
+
+    let raw = inflate::zlib(&idat)?;
+    let [w, h] = header.size;
+    let bits = channels * header.depth as usize;
+    // Every pixel takes `bits` of the inflated data, filter bytes aside
+    let mut image = Image::sized([w, h], raw.len().saturating_mul(8) / bits)?;
+
+    let passes: &[[usize; 4]] = if header.interlaced {
+        &ADAM7
+    } else {
+        &[[0, 0, 1, 1]]
+    };
+
+    let mut data = raw.as_slice();
+    for &[x0, y0, dx, dy] in passes {
+        let size = [(w + dx - 1 - x0.min(w)) / dx, (h + dy - 1 - y0.min(h)) / dy];
+        if size[0] == 0 || size[1] == 0 {
+            continue;
+        }
+
+        let (rows, used) = unfilter(data, size, bits)?;
+        data = &data[used..];
+
+        let stride = (size[0] * bits).div_ceil(8);
+        for (y, row) in rows.chunks_exact(stride).enumerate() {
+            for x in 0..size[0] {
+                let sample = |c| sample(row, x * channels + c, header.depth);
+                let scale = |value: u16| match header.depth {
+                    16 => (value >> 8) as u8,
+                    depth => (value as u32 * 255 / ((1 << depth) - 1)) as u8,
+                };
+
+                let samples = [sample(0), sample(1), sample(2), sample(3)];
+                let opaque = |n| match &key {
+                    Some(key) if key.as_slice() == &samples[..n] => 0,
+                    _ => 0xFF,
+                };
+
+                let pixel = match header.color {
+                    0 => {
+                        let v = scale(samples[0]);
+                        [v, v, v, opaque(1)]
+                    }
+                    2 => [
+                        scale(samples[0]),
+                        scale(samples[1]),
+                        scale(samples[2]),
+                        opaque(3),
+                    ],
+                    3 => palette
+                        .get(samples[0] as usize)
+                        .copied()
+                        .ok_or(DecodeError::Corrupt)?,
+                    4 => {
+                        let [v, a] = [scale(samples[0]), scale(samples[1])];
+                        [v, v, v, a]
+                    }
+                    _ => samples.map(scale),
+                };
+
+                image.pixels[(y0 + y * dy) * w + x0 + x * dx] = pixel;
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+/// Channel `idx` of a scanline with `depth` bit samples
+fn sample(row: &[u8], idx: usize, depth: u8) -> u16 {
+    match depth {
+        16 => row
+            .get(2 * idx..2 * idx + 2)
+            .map_or(0, |s| u16::from_be_bytes([s[0], s[1]])),
+        8 => row.get(idx).map_or(0, |&s| s as u16),
+        depth => {
+            let bit = idx * depth as usize;
+            row.get(bit / 8).map_or(0, |&byte| {
+                (byte >> (8 - depth as usize - bit % 8)) as u16 & ((1 << depth) - 1)
+            })
+        }
+    }
+}
+
+/// Undo per-scanline filtering of a `size` image with `bits` per pixel,
+/// returning the scanlines and the number of bytes of `data` consumed
+fn unfilter(data: &[u8], [w, h]: [usize; 2], bits: usize) -> Result<(Vec<u8>, usize), DecodeError> {
+    let stride = (w * bits).div_ceil(8);
+    let bpp = bits.div_ceil(8).max(1);
+    let used = (stride + 1) * h;
+    if data.len() < used {
+        return Err(DecodeError::Truncated);
+    }
+
+    let mut rows = Vec::with_capacity(stride * h);
+    for (y, line) in data[..used].chunks_exact(stride + 1).enumerate() {
+        let filter = line[0];
+        for (x, &byte) in line[1..].iter().enumerate() {
+            let here = rows.len();
+            let a = if x >= bpp { rows[here - bpp] } else { 0 };
+            let b = if y > 0 { rows[here - stride] } else { 0 };
+            let c = if x >= bpp && y > 0 {
+                rows[here - stride - bpp]
+            } else {
+                0
+            };
+
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(DecodeError::Corrupt),
+            };
+            rows.push(byte.wrapping_add(predicted));
+        }
+    }
+
+    Ok((rows, used))
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Encode as 8 bit RGBA, choosing each scanline's filter by the minimum sum
+/// of absolute differences
+pub fn encode(image: &Image) -> Vec<u8> {
+    let [w, h] = image.size.map(|n| n as usize);
+    let bytes = image.pixels.as_flattened();
+    let stride = 4 * w;
+
+    let mut raw = Vec::with_capacity((stride + 1) * h);
+    let mut filtered = [(); 5].map(|_| Vec::with_capacity(stride));
+    for y in 0..h {
+        let row = &bytes[y * stride..(y + 1) * stride];
+        let up = |x: usize| {
+            if y > 0 {
+                bytes[(y - 1) * stride + x]
+            } else {
+                0
+            }
+        };
+
+        for (filter, out) in filtered.iter_mut().enumerate() {
+            out.clear();
+            for (x, &byte) in row.iter().enumerate() {
+                let a = if x >= 4 { row[x - 4] } else { 0 };
+                let c = if x >= 4 { up(x - 4) } else { 0 };
+                let b = up(x);
+                let predicted = match filter {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                out.push(byte.wrapping_sub(predicted));
+            }
+        }
+
+        let cost = |row: &Vec<u8>| {
+            row.iter()
+                .map(|&b| (b as i8).unsigned_abs() as u32)
+                .sum::<u32>()
+        };
+        let (filter, best) = filtered
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, row)| cost(row))
+            .expect("no filters");
+        raw.push(filter as u8);
+        raw.extend_from_slice(best);
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(w as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(h as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    chunk(&mut png, b"IHDR", &ihdr);
+    chunk(&mut png, b"IDAT", &deflate::zlib(&raw));
+    chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(body);
+    png.extend_from_slice(&crc(kind, body).to_be_bytes());
+}
+
+/// CRC-32 of a chunk's type and data
+fn crc(kind: &[u8], body: &[u8]) -> u32 {
+    !kind.iter().chain(body).fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2x2 RGB with an unfiltered row and an up filtered row
+    const RGB: [u8; 79] = [
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00, 0x00, 0xFD,
+        0xD4, 0x9A, 0x73, 0x00, 0x00, 0x00, 0x16, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0xF8,
+        0xCF, 0xC0, 0xC0, 0xF0, 0x9F, 0x81, 0x89, 0x91, 0xE1, 0xFF, 0x7F, 0x86, 0xFF, 0x00, 0x1E,
+        0x04, 0x04, 0xFF, 0x4C, 0x8C, 0xD6, 0xF0, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4E, 0x44,
+        0xAE, 0x42, 0x60, 0x82,
+    ];
+
+    // 3x1 1 bit palette, black made transparent by tRNS
+    const PALETTE: [u8; 98] = [
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x01, 0x03, 0x00, 0x00, 0x00, 0x21,
+        0x2E, 0x86, 0xF7, 0x00, 0x00, 0x00, 0x06, 0x50, 0x4C, 0x54, 0x45, 0x00, 0x00, 0x00, 0xFF,
+        0xFF, 0xFF, 0xA5, 0xD9, 0x9F, 0xDD, 0x00, 0x00, 0x00, 0x01, 0x74, 0x52, 0x4E, 0x53, 0x00,
+        0x40, 0xE6, 0xD8, 0x66, 0x00, 0x00, 0x00, 0x0A, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63,
+        0x70, 0x00, 0x00, 0x00, 0x42, 0x00, 0x41, 0x29, 0x37, 0xF4, 0xEF, 0x00, 0x00, 0x00, 0x00,
+        0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82,
+    ];
+
+    #[test]
+    fn rgb() {
+        let image = decode(&RGB).unwrap();
+        assert_eq!(image.size, [2, 2]);
+        assert_eq!(
+            image.pixels,
+            [
+                [0xFF, 0, 0, 0xFF],
+                [0, 0xFF, 0, 0xFF],
+                [0, 0, 0xFF, 0xFF],
+                [0xFF, 0xFF, 0xFF, 0xFF],
+            ]
+        );
+    }
+
+    #[test]
+    fn palette() {
+        let image = decode(&PALETTE).unwrap();
+        assert_eq!(image.size, [3, 1]);
+        assert_eq!(
+            image.pixels,
+            [[0, 0, 0, 0], [0xFF, 0xFF, 0xFF, 0xFF], [0, 0, 0, 0]]
+        );
+    }
+
+    #[test]
+    fn bad_chunks() {
+        let mut png = RGB;
+        png[29] ^= 1;
+        assert_eq!(decode(&png).unwrap_err(), DecodeError::Checksum);
+        assert_eq!(decode(&RGB[..40]).unwrap_err(), DecodeError::Truncated);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut image = Image::new([5, 3]);
+        for (i, pixel) in image.pixels.iter_mut().enumerate() {
+            *pixel = [i as u8 * 17, 0xFF - i as u8, (i as u8) << 4, 0x80 + i as u8];
+        }
+
+        let decoded = decode(&encode(&image)).unwrap();
+        assert_eq!(decoded.size, image.size);
+        assert_eq!(decoded.pixels, image.pixels);
+    }
+}
@@ -0,0 +1,101 @@
+use super::{DecodeError, Image};
+
+pub const MAGIC: [u8; 4] = *b"qoif";
+
+pub fn decode(data: &[u8]) -> Result<Image, DecodeError> {
+    let header = data.get(..14).ok_or(DecodeError::Truncated)?;
+    let w = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let h = u32::from_be_bytes([header[8], header[9], header[10], header[11]]) as usize;
+
+    // A run op is the most pixels a byte can encode
+    let mut image = Image::sized([w, h], (data.len() - 14).saturating_mul(62))?;
+    let mut bytes = data[14..].iter().copied();
+    let mut next = || bytes.next().ok_or(DecodeError::Truncated);
+
+    let mut index = [[0u8; 4]; 64];
+    let mut px = [0, 0, 0, 0xFF];
+    let mut run = 0;
+    for pixel in image.pixels.iter_mut() {
+        if run > 0 {
+            run -= 1;
+        } else {
+            match next()? {
+                0xFE => px = [next()?, next()?, next()?, px[3]],
+                0xFF => px = [next()?, next()?, next()?, next()?],
+                op => match op >> 6 {
+                    0 => px = index[op as usize],
+                    1 => {
+                        px[0] = px[0].wrapping_add((op >> 4 & 3).wrapping_sub(2));
+                        px[1] = px[1].wrapping_add((op >> 2 & 3).wrapping_sub(2));
+                        px[2] = px[2].wrapping_add((op & 3).wrapping_sub(2));
+                    }
+                    2 => {
+                        let dg = (op & 0x3F).wrapping_sub(32);
+                        let b = next()?;
+                        px[0] = px[0].wrapping_add(dg.wrapping_sub(8).wrapping_add(b >> 4));
+                        px[1] = px[1].wrapping_add(dg);
+                        px[2] = px[2].wrapping_add(dg.wrapping_sub(8).wrapping_add(b & 0xF));
+                    }
+                    _ => run = op & 0x3F,
+                },
+            }
+
+            index[hash(px)] = px;
+        }
+
+        *pixel = px;
+    }
+
+    Ok(image)
+}
+
+fn hash([r, g, b, a]: [u8; 4]) -> usize {
+    (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::mem::vec::Vec};
+
+    fn qoi([w, h]: [u32; 2], ops: &[u8]) -> Vec<u8> {
+        let mut qoi = MAGIC.to_vec();
+        qoi.extend_from_slice(&w.to_be_bytes());
+        qoi.extend_from_slice(&h.to_be_bytes());
+        qoi.extend_from_slice(&[4, 0]);
+        qoi.extend_from_slice(ops);
+
+        qoi
+    }
+
+    #[test]
+    fn ops() {
+        // RGB, run of one, diff of -1 per channel, index of the first colour
+        let ops = [0xFE, 0xFF, 0, 0, 0xC0, 0x55, 0x32, 0, 0, 0, 0, 0, 0, 0, 1];
+        let image = decode(&qoi([2, 2], &ops)).unwrap();
+        assert_eq!(image.size, [2, 2]);
+        assert_eq!(
+            image.pixels,
+            [
+                [0xFF, 0, 0, 0xFF],
+                [0xFF, 0, 0, 0xFF],
+                [0xFE, 0xFF, 0xFF, 0xFF],
+                [0xFF, 0, 0, 0xFF],
+            ]
+        );
+    }
+
+    #[test]
+    fn luma() {
+        // dg = 4, dr - dg = -2, db - dg = 3
+        let ops = [0xFE, 10, 20, 30, 0x80 | 36, 6 << 4 | 11];
+        let image = decode(&qoi([2, 1], &ops)).unwrap();
+        assert_eq!(image.pixels, [[10, 20, 30, 0xFF], [12, 24, 37, 0xFF]]);
+    }
+
+    #[test]
+    fn truncated() {
+        let data = qoi([2, 2], &[0xC1]);
+        assert_eq!(decode(&data).unwrap_err(), DecodeError::Truncated);
+        assert_eq!(decode(&data[..10]).unwrap_err(), DecodeError::Truncated);
+    }
+}
@@ -0,0 +1,184 @@
+use {
+    super::{DecodeError, Image},
+    crate::mem::vec::Vec,
+};
+
+pub fn decode(data: &[u8]) -> Result<Image, DecodeError> {
+    let header = data.get(..18).ok_or(DecodeError::Truncated)?;
+    let u16_at = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]) as usize;
+
+    let [id_len, map_type, kind] = [header[0], header[1], header[2]].map(|b| b as usize);
+    let [map_first, map_len] = [u16_at(3), u16_at(5)];
+    let map_bits = header[7] as usize;
+    let [w, h] = [u16_at(12), u16_at(14)];
+    let (depth, descriptor) = (header[16] as usize, header[17]);
+
+    let rle = kind & 8 != 0;
+    let color = |bytes: &[u8], bits| match bits {
+        15 | 16 => {
+            let v = u16::from_le_bytes([bytes[0], bytes[1]]) as u32;
+            let scale = |c: u32| (c * 255 / 31) as u8;
+            [scale(v >> 10 & 31), scale(v >> 5 & 31), scale(v & 31), 0xFF]
+        }
+        24 => [bytes[2], bytes[1], bytes[0], 0xFF],
+        _ => [bytes[2], bytes[1], bytes[0], bytes[3]],
+    };
+
+    let mut pos = 18 + id_len;
+    let palette = if map_type == 1 {
+        if !matches!(map_bits, 15 | 16 | 24 | 32) {
+            return Err(DecodeError::Unsupported);
+        }
+
+        let entry = map_bits.div_ceil(8);
+        let map = data
+            .get(pos..pos + entry * map_len)
+            .ok_or(DecodeError::Truncated)?;
+        pos += entry * map_len;
+
+        map.chunks_exact(entry)
+            .map(|bytes| color(bytes, map_bits))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    match (kind & 7, depth) {
+        (1, 8) if !palette.is_empty() => {}
+        (2, 15 | 16 | 24 | 32) | (3, 8 | 16) => {}
+        _ => return Err(DecodeError::Unsupported),
+    }
+
+    let bytes = depth.div_ceil(8);
+    // A run packet is the most pixels a byte can encode
+    let payload = data.len().saturating_sub(pos);
+    let max_pixels = if rle {
+        payload.saturating_mul(128)
+    } else {
+        payload / bytes
+    };
+    if w * h > max_pixels {
+        return Err(DecodeError::Truncated);
+    }
+
+    let pixel = |bytes: &[u8]| match kind & 7 {
+        1 => palette
+            .get((bytes[0] as usize).wrapping_sub(map_first))
+            .copied()
+            .ok_or(DecodeError::Corrupt),
+        3 => Ok([bytes[0], bytes[0], bytes[0], *bytes.get(1).unwrap_or(&0xFF)]),
+        _ => Ok(color(bytes, depth)),
+    };
+
+    let mut pixels = Vec::with_capacity(w * h);
+    while pixels.len() < w * h {
+        let (count, repeat) = if rle {
+            let packet = *data.get(pos).ok_or(DecodeError::Truncated)?;
+            pos += 1;
+            ((packet & 0x7F) as usize + 1, packet & 0x80 != 0)
+        } else {
+            (w * h, false)
+        };
+
+        if repeat {
+            let px = pixel(data.get(pos..pos + bytes).ok_or(DecodeError::Truncated)?)?;
+            pos += bytes;
+            pixels.extend((0..count).map(|_| px));
+        } else {
+            let run = data
+                .get(pos..pos + count * bytes)
+                .ok_or(DecodeError::Truncated)?;
+            pos += count * bytes;
+            for bytes in run.chunks_exact(bytes) {
+                pixels.push(pixel(bytes)?);
+            }
+        }
+    }
+    pixels.truncate(w * h);
+
+    // Origin is bottom left unless the descriptor says otherwise
+    let mut image = Image::sized([w, h], w * h)?;
+    for (y, row) in pixels.chunks_exact(w.max(1)).enumerate() {
+        let dst = if descriptor & 0x20 != 0 { y } else { h - 1 - y };
+        let dst = &mut image.pixels[dst * w..(dst + 1) * w];
+        dst.copy_from_slice(row);
+        if descriptor & 0x10 != 0 {
+            dst.reverse();
+        }
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// TGA header of a 2x2 image, with a colour map of `map` entries and bits
+    fn tga(kind: u8, map: Option<[u8; 2]>, depth: u8, descriptor: u8) -> Vec<u8> {
+        let [len, bits] = map.unwrap_or([0; 2]);
+        let mut header = [0; 18];
+        header[1] = map.is_some() as u8;
+        header[2] = kind;
+        header[5] = len;
+        header[7] = bits;
+        header[12] = 2;
+        header[14] = 2;
+        header[16] = depth;
+        header[17] = descriptor;
+
+        header.to_vec()
+    }
+
+    #[test]
+    fn bgr_bottom_up() {
+        let mut data = tga(2, None, 24, 0);
+        data.extend_from_slice(&[0xFF, 0, 0, 0xFF, 0xFF, 0xFF, 0, 0, 0xFF, 0, 0xFF, 0]);
+        let image = decode(&data).unwrap();
+        assert_eq!(image.size, [2, 2]);
+        assert_eq!(
+            image.pixels,
+            [
+                [0xFF, 0, 0, 0xFF],
+                [0, 0xFF, 0, 0xFF],
+                [0, 0, 0xFF, 0xFF],
+                [0xFF, 0xFF, 0xFF, 0xFF],
+            ]
+        );
+    }
+
+    #[test]
+    fn rle_top_down() {
+        let mut data = tga(10, None, 24, 0x20);
+        // A run of two reds, then a raw packet of green and blue
+        data.extend_from_slice(&[0x81, 0, 0, 0xFF, 0x01, 0, 0xFF, 0, 0xFF, 0, 0]);
+        let image = decode(&data).unwrap();
+        assert_eq!(
+            image.pixels,
+            [
+                [0xFF, 0, 0, 0xFF],
+                [0xFF, 0, 0, 0xFF],
+                [0, 0xFF, 0, 0xFF],
+                [0, 0, 0xFF, 0xFF],
+            ]
+        );
+    }
+
+    #[test]
+    fn colour_map() {
+        let mut data = tga(1, Some([2, 24]), 8, 0x20);
+        data.extend_from_slice(&[0, 0, 0, 0xC0, 0x80, 0x40]);
+        data.extend_from_slice(&[0, 1, 1, 0]);
+        let [k, c] = [[0, 0, 0, 0xFF], [0x40, 0x80, 0xC0, 0xFF]];
+        assert_eq!(decode(&data).unwrap().pixels, [k, c, c, k]);
+
+        // Index past the colour map
+        let last = data.len() - 1;
+        data[last] = 2;
+        assert_eq!(decode(&data).unwrap_err(), DecodeError::Corrupt);
+
+        let mut data = tga(1, Some([2, 8]), 8, 0x20);
+        data.extend_from_slice(&[0, 0xFF, 0, 1, 1, 0]);
+        assert_eq!(decode(&data).unwrap_err(), DecodeError::Unsupported);
+    }
+}
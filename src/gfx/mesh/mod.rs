@@ -1,123 +1,327 @@
-mod vertex;
+pub mod vertex;
 
 use {
     crate::gfx::{
         buffer::{Buffer, Usage},
+        state::{Compare, RenderState, Stencil, StencilOp},
         Resource, *,
     },
-    core::marker::PhantomData,
+    core::{marker::PhantomData, mem::size_of, ops::Range},
     vertex::Vertex,
 };
 
-pub struct Mesh<V: Vertex> {
+pub struct Mesh<V: Vertex, I: Vertex = ()> {
     vao: GLuint,
     vertices: Buffer,
+    instances: Option<Buffer>,
+    indices: Option<Indices>,
     topology: Topology,
-    _data: PhantomData<V>,
+    _data: PhantomData<(V, I)>,
 }
 
 impl<V: Vertex> Mesh<V> {
     pub fn new(verts: &[V], usage: Usage, topology: Topology) -> Self {
+        Self::build(verts, None, usage, topology)
+    }
+
+    pub fn static_draw(verts: &[V], topology: Topology) -> Self {
+        Self::new(verts, Usage::StaticDraw, topology)
+    }
+}
+
+impl<V: Vertex, I: Vertex> Mesh<V, I> {
+    /// Mesh with a second buffer of per-instance attributes, bound after the
+    /// vertex attributes starting at location `V::LOCATIONS`.
+    pub fn instanced(verts: &[V], instances: &[I], usage: Usage, topology: Topology) -> Self {
+        Self::build(verts, Some(instances), usage, topology)
+    }
+
+    fn build(verts: &[V], instances: Option<&[I]>, usage: Usage, topology: Topology) -> Self {
         let mut vao = 0;
         unsafe {
             glGenVertexArrays(1, &mut vao);
             glBindVertexArray(vao);
         }
 
-        match &topology {
-            Topology::TriIndexed(indices) => {
-                indices.bind();
-            }
-            _ => {}
-        }
-
         let vertices = Buffer::new(GL_ARRAY_BUFFER, usage, verts);
         V::bind();
 
+        let instances = instances.map(|instances| {
+            let buf = Buffer::new(GL_ARRAY_BUFFER, usage, instances);
+            I::bind_attributes(V::LOCATIONS, I::SIZE, 0, 1);
+
+            buf
+        });
+
         Self {
             vao,
             vertices,
+            instances,
+            indices: None,
             topology,
             _data: PhantomData,
         }
     }
 
-    pub fn static_draw(verts: &[V], topology: Topology) -> Self {
-        Self::new(verts, Usage::StaticDraw, topology)
+    /// Draw through an index buffer of `u8`, `u16` or `u32` indices
+    pub fn with_indices<X: Index>(mut self, idx: &[X], usage: Usage) -> Self {
+        self.set_indices(idx, usage);
+        self
+    }
+
+    fn set_indices<X: Index>(&mut self, idx: &[X], usage: Usage) {
+        self.bind();
+        self.indices = Some(Indices {
+            buf: Buffer::new(GL_ELEMENT_ARRAY_BUFFER, usage, idx),
+            _type: X::TYPE,
+            size: size_of::<X>(),
+            restart: false,
+        });
     }
 
     pub fn update(&mut self, verts: &[V]) {
         self.vertices.update(verts);
     }
 
-    pub fn stencil(&self) {
+    /// Update the per-instance attributes, creating their buffer with the
+    /// usage of the vertices if the mesh wasn't made `instanced`
+    pub fn update_instances(&mut self, instances: &[I]) {
+        match &mut self.instances {
+            Some(buf) => buf.update(instances),
+            None => {
+                self.bind();
+                let buf = Buffer::new(GL_ARRAY_BUFFER, self.vertices.usage(), instances);
+                I::bind_attributes(V::LOCATIONS, I::SIZE, 0, 1);
+                self.instances = Some(buf);
+            }
+        }
+    }
+
+    /// Update the index buffer, switching index type if `X` differs, or
+    /// start drawing through one with the usage of the vertices
+    pub fn update_indices<X: Index>(&mut self, idx: &[X]) {
+        match &mut self.indices {
+            Some(indices) => {
+                indices.buf.update(idx);
+                indices._type = X::TYPE;
+                indices.size = size_of::<X>();
+            }
+            None => self.set_indices(idx, self.vertices.usage()),
+        }
+    }
+
+    /// Treat the maximum value of the index type as a strip/fan restart
+    pub fn primitive_restart(&mut self, enable: bool) {
+        if let Some(indices) = &mut self.indices {
+            indices.restart = enable;
+        }
+    }
+
+    /// Source vertices from `buf` instead, e.g. a segment of a `RingBuffer`
+    /// drawn with `draw_range`
+    pub fn source_vertices(&self, buf: &Buffer) {
+        self.bind();
+        buf.bind_to(GL_ARRAY_BUFFER);
+        V::bind();
+    }
+
+    pub fn vertices(&self) -> &Buffer {
+        &self.vertices
+    }
+
+    pub fn instances(&self) -> Option<&Buffer> {
+        self.instances.as_ref()
+    }
+
+    /// Fill the stencil buffer inside the mesh using the even-odd rule, then
+    /// run `cover` with drawing limited to the inside before restoring the
+    /// previous state
+    pub fn stencil(&self, cover: impl FnOnce()) {
+        let state = RenderState::current();
+        RenderState {
+            color_mask: [false; 4],
+            stencil: Some(Stencil {
+                front: [StencilOp::Invert; 3],
+                back: [StencilOp::Invert; 3],
+                ..Stencil::default()
+            }),
+            ..state
+        }
+        .apply();
+
         unsafe {
-            glEnable(GL_STENCIL_TEST);
-            glStencilMask(0xFF);
-            glColorMask(0, 0, 0, 0);
-            glStencilFunc(GL_ALWAYS, 1, 0xFF);
             glClearStencil(0);
             glClear(GL_STENCIL_BUFFER_BIT);
-            glStencilOp(GL_INVERT, GL_INVERT, GL_INVERT);
-
-            self.draw();
+        }
+        self.draw();
 
-            glStencilFunc(GL_NOTEQUAL, 0, 0xFF);
-            glStencilOp(GL_KEEP, GL_KEEP, GL_KEEP);
-            glColorMask(0xFF, 0xFF, 0xFF, 0xFF);
-            glStencilMask(0);
+        RenderState {
+            stencil: Some(Stencil {
+                func: Compare::NotEqual,
+                write_mask: 0,
+                ..Stencil::default()
+            }),
+            ..state
         }
+        .apply();
+        cover();
+
+        state.apply();
     }
 
     pub fn draw(&self) {
+        self.draw_instanced(1);
+    }
+
+    pub fn draw_instanced(&self, count: usize) {
+        self.draw_instanced_base(count, 0);
+    }
+
+    /// Draw `count` instances reading per-instance attributes from
+    /// `base_instance` onwards.
+    pub fn draw_instanced_base(&self, count: usize, base_instance: u32) {
+        self.draw_range_instanced(0..self.len(), 0, count, base_instance);
+    }
+
+    /// Draw the vertices, or indices if indexed, in `range`
+    pub fn draw_range(&self, range: Range<usize>) {
+        self.draw_range_base(range, 0);
+    }
+
+    /// Draw `range` with `base_vertex` added to every vertex index
+    pub fn draw_range_base(&self, range: Range<usize>, base_vertex: i32) {
+        self.draw_range_instanced(range, base_vertex, 1, 0);
+    }
+
+    pub fn draw_range_instanced(
+        &self,
+        range: Range<usize>,
+        base_vertex: i32,
+        count: usize,
+        base_instance: u32,
+    ) {
+        let mode = self.topology.mode();
         unsafe {
             self.bind();
-            match &self.topology {
-                Topology::TriIndexed(indices) => {
-                    glDrawElements(
-                        GL_TRIANGLES,
-                        indices.len() as _,
-                        GL_UNSIGNED_BYTE,
-                        core::ptr::null(),
-                    );
-                }
+            if let Topology::Patches(n) = self.topology {
+                glPatchParameteri(GL_PATCH_VERTICES, n);
+            }
 
-                Topology::Lines => {
-                    glDrawArrays(GL_LINE_STRIP, 0, self.vertices.len() as _);
-                }
+            match &self.indices {
+                Some(indices) => {
+                    if indices.restart {
+                        glEnable(GL_PRIMITIVE_RESTART_FIXED_INDEX);
+                    }
 
-                Topology::Points => {
-                    glDrawArrays(GL_POINTS, 0, self.vertices.len() as _);
-                }
+                    glDrawElementsInstancedBaseVertexBaseInstance(
+                        mode,
+                        range.len() as _,
+                        indices._type,
+                        (range.start * indices.size) as _,
+                        count as _,
+                        base_vertex,
+                        base_instance,
+                    );
 
-                Topology::TriFan => {
-                    glDrawArrays(GL_TRIANGLE_FAN, 0, self.vertices.len() as _);
+                    if indices.restart {
+                        glDisable(GL_PRIMITIVE_RESTART_FIXED_INDEX);
+                    }
                 }
 
-                Topology::TriStrip => {
-                    glDrawArrays(GL_TRIANGLE_STRIP, 0, self.vertices.len() as _);
+                None => {
+                    glDrawArraysInstancedBaseInstance(
+                        mode,
+                        range.start as i32 + base_vertex,
+                        range.len() as _,
+                        count as _,
+                        base_instance,
+                    );
                 }
             }
         }
     }
+
+    /// Number of indices if indexed, vertices otherwise
+    pub fn len(&self) -> usize {
+        self.indices
+            .as_ref()
+            .map_or(self.vertices.len(), |indices| indices.buf.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
-impl<V: Vertex> Resource for Mesh<V> {
+impl<V: Vertex, I: Vertex> Resource for Mesh<V, I> {
     fn bind(&self) {
         unsafe { glBindVertexArray(self.vao) }
     }
 }
 
+impl<V: Vertex, I: Vertex> Drop for Mesh<V, I> {
+    fn drop(&mut self) {
+        unsafe {
+            glDeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
 pub enum Topology {
     Points,
     Lines,
-    TriFan,
+    LineStrip,
+    LineLoop,
+    Tris,
     TriStrip,
-    TriIndexed(Buffer),
+    TriFan,
+    LinesAdjacency,
+    LineStripAdjacency,
+    TrisAdjacency,
+    TriStripAdjacency,
+    /// Patches of `n` control points each, for tessellated programs
+    Patches(i32),
 }
 
 impl Topology {
-    pub fn from_indices(idx: &[u8], usage: Usage) -> Self {
-        Self::TriIndexed(Buffer::new(GL_ELEMENT_ARRAY_BUFFER, usage, idx))
+    fn mode(self) -> GLenum {
+        match self {
+            Topology::Points => GL_POINTS,
+            Topology::Lines => GL_LINES,
+            Topology::LineStrip => GL_LINE_STRIP,
+            Topology::LineLoop => GL_LINE_LOOP,
+            Topology::Tris => GL_TRIANGLES,
+            Topology::TriStrip => GL_TRIANGLE_STRIP,
+            Topology::TriFan => GL_TRIANGLE_FAN,
+            Topology::LinesAdjacency => GL_LINES_ADJACENCY,
+            Topology::LineStripAdjacency => GL_LINE_STRIP_ADJACENCY,
+            Topology::TrisAdjacency => GL_TRIANGLES_ADJACENCY,
+            Topology::TriStripAdjacency => GL_TRIANGLE_STRIP_ADJACENCY,
+            Topology::Patches(_) => GL_PATCHES,
+        }
     }
 }
+
+pub trait Index: Copy {
+    const TYPE: GLenum;
+}
+
+impl Index for u8 {
+    const TYPE: GLenum = GL_UNSIGNED_BYTE;
+}
+
+impl Index for u16 {
+    const TYPE: GLenum = GL_UNSIGNED_SHORT;
+}
+
+impl Index for u32 {
+    const TYPE: GLenum = GL_UNSIGNED_INT;
+}
+
+struct Indices {
+    buf: Buffer,
+    _type: GLenum,
+    size: usize,
+    restart: bool,
+}
@@ -1,30 +1,200 @@
-use {crate::gfx::*, core::mem::size_of};
+use {
+    crate::gfx::*,
+    core::mem::{offset_of, size_of},
+};
 
-pub trait Vertex: Sized {
+pub trait Vertex: Copy {
     const NUM: i32 = 0;
     const SIZE: i32 = size_of::<Self>() as _;
     const TYPE: GLenum = GL_FLOAT;
     const NORM: bool = false;
+    /// Integer attributes reach the shader unconverted as `ivec`/`uvec`
+    const INT: bool = false;
+    /// Attribute locations taken up, e.g. one per matrix column
+    const LOCATIONS: u32 = 1;
 
     fn bind() {
-        Self::bind_parameters(0, Self::NUM, Self::TYPE, Self::NORM, Self::SIZE, 0);
+        Self::bind_attributes(0, Self::SIZE, 0, 0);
     }
 
-    fn bind_parameters(idx: u32, num: i32, _type: GLenum, norm: bool, size: i32, stride: i32) {
+    /// Bind from location `idx` for a vertex `stride` bytes wide, with this
+    /// attribute at byte `offset`, advancing every `divisor` instances (or
+    /// every vertex if 0). Returns the next free location.
+    fn bind_attributes(idx: u32, stride: i32, offset: i32, divisor: u32) -> u32 {
+        let column = Self::SIZE / Self::LOCATIONS as i32;
+        for loc in 0..Self::LOCATIONS {
+            Self::bind_parameters(idx + loc, stride, offset + loc as i32 * column, divisor);
+        }
+
+        idx + Self::LOCATIONS
+    }
+
+    fn bind_parameters(idx: u32, stride: i32, offset: i32, divisor: u32) {
         unsafe {
             glEnableVertexAttribArray(idx);
-            glVertexAttribPointer(idx, num, _type, norm as _, size, stride as _);
+            if Self::INT {
+                glVertexAttribIPointer(idx, Self::NUM, Self::TYPE, stride, offset as _);
+            } else {
+                glVertexAttribPointer(
+                    idx,
+                    Self::NUM,
+                    Self::TYPE,
+                    Self::NORM as _,
+                    stride,
+                    offset as _,
+                );
+            }
+            glVertexAttribDivisor(idx, divisor);
         }
     }
 }
 
-impl<V1: Vertex, V2: Vertex> Vertex for (V1, V2) {
-    fn bind() {
-        V1::bind_parameters(0, V1::NUM, V1::TYPE, V1::NORM, Self::SIZE, 0);
-        V2::bind_parameters(1, V2::NUM, V2::TYPE, V2::NORM, Self::SIZE, V1::SIZE);
+/// Integer attribute normalized to `[0, 1]` (unsigned) or `[-1, 1]` (signed)
+/// when read as a float in the shader, e.g. `Norm<[u8; 4]>` for packed RGBA.
+#[repr(transparent)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Norm<T>(pub T);
+
+/// No attributes, the instance layout of meshes that aren't instanced
+impl Vertex for () {
+    const LOCATIONS: u32 = 0;
+
+    fn bind_attributes(idx: u32, _: i32, _: i32, _: u32) -> u32 {
+        idx
     }
 }
 
+impl Vertex for f32 {
+    const NUM: i32 = 1;
+}
+
 impl<const N: usize> Vertex for [f32; N] {
     const NUM: i32 = N as _;
 }
+
+/// Matrices take one location per column, `crate::math::Matrix<M, N>` being
+/// `M` columns of `N` floats.
+impl<const M: usize, const N: usize> Vertex for [[f32; N]; M] {
+    const NUM: i32 = N as _;
+    const LOCATIONS: u32 = M as _;
+}
+
+macro_rules! integer {
+    ($($int:ty => $gl:ident),*) => {$(
+        impl Vertex for $int {
+            const NUM: i32 = 1;
+            const TYPE: GLenum = $gl;
+            const INT: bool = true;
+        }
+
+        impl<const N: usize> Vertex for [$int; N] {
+            const NUM: i32 = N as _;
+            const TYPE: GLenum = $gl;
+            const INT: bool = true;
+        }
+
+        impl<const N: usize> Vertex for Norm<[$int; N]> {
+            const NUM: i32 = N as _;
+            const TYPE: GLenum = $gl;
+            const NORM: bool = true;
+        }
+    )*};
+}
+
+integer!(
+    i8 => GL_BYTE,
+    u8 => GL_UNSIGNED_BYTE,
+    i16 => GL_SHORT,
+    u16 => GL_UNSIGNED_SHORT,
+    i32 => GL_INT,
+    u32 => GL_UNSIGNED_INT
+);
+
+macro_rules! tuple {
+    ($($v:ident $field:tt),+) => {
+        impl<$($v: Vertex),+> Vertex for ($($v,)+) {
+            const LOCATIONS: u32 = 0 $(+ $v::LOCATIONS)+;
+
+            fn bind_attributes(idx: u32, stride: i32, offset: i32, divisor: u32) -> u32 {
+                $(
+                    let idx = $v::bind_attributes(
+                        idx,
+                        stride,
+                        offset + offset_of!(Self, $field) as i32,
+                        divisor,
+                    );
+                )+
+
+                idx
+            }
+        }
+    };
+}
+
+tuple!(V1 0, V2 1);
+tuple!(V1 0, V2 1, V3 2);
+tuple!(V1 0, V2 1, V3 2, V4 3);
+tuple!(V1 0, V2 1, V3 2, V4 3, V5 4);
+tuple!(V1 0, V2 1, V3 2, V4 3, V5 4, V6 5);
+tuple!(V1 0, V2 1, V3 2, V4 3, V5 4, V6 5, V7 6);
+tuple!(V1 0, V2 1, V3 2, V4 3, V5 4, V6 5, V7 6, V8 7);
+
+/// Declare a `#[repr(C)]` vertex struct with an explicit location per field.
+///
+/// This stands in for a `#[derive(Vertex)]`, which would need a separate
+/// proc-macro crate. It takes the struct definition itself, adds
+/// `#[repr(C)]` and implements `Vertex` from the `#[location = N]` of each
+/// field, so the fields take no other attributes.
+///
+/// ```ignore
+/// hex_ln::vertex! {
+///     #[derive(Clone, Copy)]
+///     pub struct Textured {
+///         #[location = 0] pub pos: [f32; 3],
+///         #[location = 1] pub uv: [f32; 2],
+///         #[location = 2] pub rgba: Norm<[u8; 4]>,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! vertex {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $(#[location = $loc:literal] $field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[repr(C)]
+        $vis struct $name {
+            $($field_vis $field: $ty),*
+        }
+
+        impl $crate::gfx::mesh::vertex::Vertex for $name {
+            const LOCATIONS: u32 = {
+                let mut end = 0;
+                $(
+                    let field = $loc + <$ty as $crate::gfx::mesh::vertex::Vertex>::LOCATIONS;
+                    if field > end {
+                        end = field;
+                    }
+                )*
+
+                end
+            };
+
+            fn bind_attributes(_idx: u32, stride: i32, offset: i32, divisor: u32) -> u32 {
+                $(
+                    <$ty as $crate::gfx::mesh::vertex::Vertex>::bind_attributes(
+                        $loc,
+                        stride,
+                        offset + ::core::mem::offset_of!($name, $field) as i32,
+                        divisor,
+                    );
+                )*
+
+                Self::LOCATIONS
+            }
+        }
+    };
+}
@@ -1,8 +1,21 @@
+pub mod atlas;
 pub mod buffer;
+pub mod camera;
+pub mod draw2d;
 pub mod framebuffer;
+pub mod graph;
+pub mod image;
 pub mod mesh;
+pub mod model;
+pub mod path;
+pub mod post;
 pub mod program;
+pub mod query;
+pub mod renderbuffer;
+pub mod sdf;
 pub mod shader;
+pub mod sprite;
+pub mod state;
 pub mod texture;
 
 #[allow(non_camel_case_types)]
@@ -23,17 +36,24 @@ pub trait Resource {
 
 pub trait Target: Resource {
     fn clear_color(&self, [r, g, b, a]: [f32; 4]) {
-        unsafe {
+        state::RenderState::clearing(|| unsafe {
             glClearColor(r, g, b, a);
             glClear(GL_COLOR_BUFFER_BIT);
-        }
+        });
+    }
+
+    fn clear_depth(&self, clear: f32) {
+        state::RenderState::clearing(|| unsafe {
+            glClearDepth(clear as _);
+            glClear(GL_DEPTH_BUFFER_BIT);
+        });
     }
 
     fn clear_stencil(&self, clear: i32) {
-        unsafe {
+        state::RenderState::clearing(|| unsafe {
             glClearStencil(clear);
             glClear(GL_STENCIL_BUFFER_BIT);
-        }
+        });
     }
 
     fn viewport(&self, [x, y]: [i32; 2], [w, h]: [i32; 2]) {
@@ -54,3 +74,35 @@ impl Uniform for Matrix<4, 4> {
         }
     }
 }
+
+impl Uniform for f32 {
+    fn bind(&self, location: i32) {
+        unsafe {
+            glUniform1f(location, *self);
+        }
+    }
+}
+
+impl Uniform for i32 {
+    fn bind(&self, location: i32) {
+        unsafe {
+            glUniform1i(location, *self);
+        }
+    }
+}
+
+impl Uniform for [f32; 2] {
+    fn bind(&self, location: i32) {
+        unsafe {
+            glUniform2f(location, self[0], self[1]);
+        }
+    }
+}
+
+impl Uniform for [f32; 4] {
+    fn bind(&self, location: i32) {
+        unsafe {
+            glUniform4f(location, self[0], self[1], self[2], self[3]);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use crate::{
+    gfx::{
+        buffer::Usage,
+        mesh::{Mesh, Topology},
+    },
+    math::{add, cross, dot, normalize, scale, sub},
+    mem::vec::Vec,
+};
+
+/// Position, normal and texture coordinates, the vertex layout of meshes
+/// built from a `Geometry`
+pub type ModelVertex = ([f32; 3], [f32; 3], [f32; 2]);
+
+/// `ModelVertex` followed by a tangent, its `w` being the handedness of
+/// the bitangent `cross(normal, tangent.xyz) * w`
+pub type TangentVertex = ([f32; 3], [f32; 3], [f32; 2], [f32; 4]);
+
+/// Triangle list with attributes per vertex. `normals`, `uvs` and
+/// `tangents` are either empty or as long as `positions`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Geometry {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub tangents: Vec<[f32; 4]>,
+    pub indices: Vec<u32>,
+}
+
+impl Geometry {
+    /// Interleaved vertices, zeroing missing normals and UVs
+    pub fn vertices(&self) -> Vec<ModelVertex> {
+        self.positions
+            .iter()
+            .enumerate()
+            .map(|(i, &pos)| {
+                (
+                    pos,
+                    self.normals.get(i).copied().unwrap_or_default(),
+                    self.uvs.get(i).copied().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    /// Interleaved vertices with tangents, zeroing missing attributes
+    pub fn tangent_vertices(&self) -> Vec<TangentVertex> {
+        self.vertices()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (pos, normal, uv))| {
+                (
+                    pos,
+                    normal,
+                    uv,
+                    self.tangents.get(i).copied().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn mesh(&self) -> Mesh<ModelVertex> {
+        Mesh::static_draw(&self.vertices(), Topology::Tris)
+            .with_indices(&self.indices, Usage::StaticDraw)
+    }
+
+    pub fn tangent_mesh(&self) -> Mesh<TangentVertex> {
+        Mesh::static_draw(&self.tangent_vertices(), Topology::Tris)
+            .with_indices(&self.indices, Usage::StaticDraw)
+    }
+
+    /// Replace the normals with the average of the surrounding faces
+    pub fn smooth_normals(&mut self) {
+        self.normals = smooth_normals(&self.positions, &self.indices);
+    }
+
+    /// Replace the normals with those of each face, giving every triangle
+    /// its own vertices so edges stay sharp. Tangents are recomputed if
+    /// present.
+    pub fn flat_normals(&mut self) {
+        let positions = unweld(&self.positions, &self.indices);
+        let uvs = if self.uvs.is_empty() {
+            Vec::new()
+        } else {
+            unweld(&self.uvs, &self.indices)
+        };
+
+        self.normals = face_normals(&positions)
+            .into_iter()
+            .flat_map(|normal| [normal; 3])
+            .collect();
+        self.positions = positions;
+        self.uvs = uvs;
+        self.indices = (0..self.positions.len() as u32).collect();
+
+        if !self.tangents.is_empty() {
+            self.compute_tangents();
+        }
+    }
+
+    /// Compute tangents from the normals and UVs, see `tangents`
+    pub fn compute_tangents(&mut self) {
+        self.tangents = tangents(&self.positions, &self.normals, &self.uvs, &self.indices);
+    }
+
+    pub fn bounds(&self) -> Option<Bounds> {
+        Bounds::of(&self.positions)
+    }
+
+    /// Append `other`, offsetting its indices. Attributes only one side has
+    /// are zeroed for the other's vertices.
+    pub fn append(&mut self, other: &Geometry) {
+        let lens = [self.positions.len(), other.positions.len()];
+        append_attr(&mut self.normals, &other.normals, lens);
+        append_attr(&mut self.uvs, &other.uvs, lens);
+        append_attr(&mut self.tangents, &other.tangents, lens);
+
+        let base = self.positions.len() as u32;
+        self.positions.extend_from_slice(&other.positions);
+        self.indices.extend(other.indices.iter().map(|&i| i + base));
+    }
+}
+
+/// Append `other` to `attr` for `len` and `other_len` vertices, keeping
+/// both empty if neither has any
+fn append_attr<T: Copy + Default>(attr: &mut Vec<T>, other: &[T], [len, other_len]: [usize; 2]) {
+    if attr.is_empty() && other.is_empty() {
+        return;
+    }
+
+    attr.resize(len, T::default());
+    attr.extend_from_slice(other);
+    attr.resize(len + other_len, T::default());
+}
+
+fn unweld<T: Copy>(attr: &[T], indices: &[u32]) -> Vec<T> {
+    indices.iter().map(|&i| attr[i as usize]).collect()
+}
+
+/// Axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Bounds {
+    /// Box around `points`, `None` if empty
+    pub fn of(points: &[[f32; 3]]) -> Option<Self> {
+        let (&first, rest) = points.split_first()?;
+
+        Some(rest.iter().fold(
+            Self {
+                min: first,
+                max: first,
+            },
+            |bounds, &point| bounds.including(point),
+        ))
+    }
+
+    pub fn including(self, point: [f32; 3]) -> Self {
+        let mut out = self;
+        for (axis, &value) in point.iter().enumerate() {
+            out.min[axis] = out.min[axis].min(value);
+            out.max[axis] = out.max[axis].max(value);
+        }
+
+        out
+    }
+
+    pub fn center(&self) -> [f32; 3] {
+        scale(add(self.min, self.max), 0.5)
+    }
+
+    pub fn size(&self) -> [f32; 3] {
+        sub(self.max, self.min)
+    }
+}
+
+/// Unit normal of every triangle in a list of unindexed vertices
+pub fn face_normals(positions: &[[f32; 3]]) -> Vec<[f32; 3]> {
+    positions
+        .chunks_exact(3)
+        .map(|tri| normalize(cross(sub(tri[1], tri[0]), sub(tri[2], tri[0]))))
+        .collect()
+}
+
+/// Vertex normals averaging the faces around each vertex, weighted by area
+pub fn smooth_normals(positions: &[[f32; 3]], indices: &[u32]) -> Vec<[f32; 3]> {
+    let mut normals = crate::mem::vec![[0.0; 3]; positions.len()];
+
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+        // Unnormalized, so its length is twice the triangle's area
+        let normal = cross(
+            sub(positions[b], positions[a]),
+            sub(positions[c], positions[a]),
+        );
+
+        for i in [a, b, c] {
+            normals[i] = add(normals[i], normal);
+        }
+    }
+
+    normals.into_iter().map(normalize).collect()
+}
+
+/// Per-vertex tangents pointing along increasing u, orthogonalized against
+/// the normals. Without UVs, or where they are degenerate, tangents are an
+/// arbitrary perpendicular to the normal.
+pub fn tangents(
+    positions: &[[f32; 3]],
+    normals: &[[f32; 3]],
+    uvs: &[[f32; 2]],
+    indices: &[u32],
+) -> Vec<[f32; 4]> {
+    let mut tan = crate::mem::vec![[0.0; 3]; positions.len()];
+    let mut bitan = crate::mem::vec![[0.0; 3]; positions.len()];
+
+    let tris = if uvs.is_empty() { &[][..] } else { indices };
+    for tri in tris.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+        let [e1, e2] = [
+            sub(positions[b], positions[a]),
+            sub(positions[c], positions[a]),
+        ];
+        let [du1, dv1] = [uvs[b][0] - uvs[a][0], uvs[b][1] - uvs[a][1]];
+        let [du2, dv2] = [uvs[c][0] - uvs[a][0], uvs[c][1] - uvs[a][1]];
+
+        let det = du1 * dv2 - du2 * dv1;
+        if det == 0.0 {
+            continue;
+        }
+        let r = 1.0 / det;
+        let t = scale(sub(scale(e1, dv2), scale(e2, dv1)), r);
+        let s = scale(sub(scale(e2, du1), scale(e1, du2)), r);
+
+        for i in [a, b, c] {
+            tan[i] = add(tan[i], t);
+            bitan[i] = add(bitan[i], s);
+        }
+    }
+
+    normals
+        .iter()
+        .zip(tan.iter().zip(&bitan))
+        .map(|(&n, (&t, &b))| {
+            // Gram-Schmidt, falling back to any perpendicular for
+            // degenerate UVs
+            let mut t = normalize(sub(t, scale(n, dot(n, t))));
+            if dot(t, t) == 0.0 {
+                let axis = if n[0].abs() < 0.9 {
+                    [1.0, 0.0, 0.0]
+                } else {
+                    [0.0, 1.0, 0.0]
+                };
+                t = normalize(cross(axis, n));
+            }
+
+            let w = if dot(cross(n, t), b) < 0.0 { -1.0 } else { 1.0 };
+            [t[0], t[1], t[2], w]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::math::sqrt};
+
+    /// Unit square in the xy plane, as two triangles sharing an edge
+    fn quad() -> Geometry {
+        Geometry {
+            positions: [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ]
+            .into(),
+            uvs: [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]].into(),
+            indices: [0, 1, 2, 0, 2, 3].into(),
+            ..Geometry::default()
+        }
+    }
+
+    #[test]
+    fn append() {
+        let mut geometry = quad();
+        let mut other = quad();
+        other.uvs.clear();
+        other.normals = [[0.0, 0.0, 1.0]; 4].into();
+
+        geometry.append(&other);
+        assert_eq!(geometry.positions.len(), 8);
+        assert_eq!(geometry.indices[6..], [4, 5, 6, 4, 6, 7]);
+        assert_eq!(geometry.uvs[4..], [[0.0; 2]; 4]);
+        assert_eq!(geometry.normals[..4], [[0.0; 3]; 4]);
+        assert_eq!(geometry.normals[4..], other.normals[..]);
+        assert!(geometry.tangents.is_empty());
+    }
+
+    #[test]
+    fn flat_normals() {
+        let mut geometry = quad();
+        geometry.positions[2][2] = 1.0;
+        geometry.tangents = [[0.0; 4]; 4].into();
+        geometry.flat_normals();
+
+        assert_eq!(geometry.positions.len(), 6);
+        assert_eq!(geometry.indices, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(geometry.uvs[3..], [[0.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+
+        let s = 1.0 / sqrt(2.0);
+        assert_eq!(geometry.normals[..3], [[0.0, -s, s]; 3]);
+        assert_eq!(geometry.normals[3..], [[-s, 0.0, s]; 3]);
+        // Tangents are recomputed for the new vertices
+        assert_eq!(geometry.tangents.len(), 6);
+    }
+
+    #[test]
+    fn tangents() {
+        let mut geometry = quad();
+        geometry.smooth_normals();
+        geometry.compute_tangents();
+        assert_eq!(geometry.tangents, [[1.0, 0.0, 0.0, 1.0]; 4]);
+
+        // Without UVs any perpendicular will do
+        geometry.uvs.clear();
+        geometry.compute_tangents();
+        for (t, n) in geometry.tangents.iter().zip(&geometry.normals) {
+            assert_eq!(dot([t[0], t[1], t[2]], *n), 0.0);
+        }
+    }
+}
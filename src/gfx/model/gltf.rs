@@ -0,0 +1,716 @@
+use {
+    super::{
+        json::Value, Geometry, ImageSource, Material, Model, ModelError, ModelMesh, Node, Primitive,
+    },
+    crate::{
+        math::{multiply, Matrix},
+        mem::vec::Vec,
+    },
+};
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const MODE_TRIANGLES: usize = 4;
+const MODE_TRIANGLE_STRIP: usize = 5;
+const MODE_TRIANGLE_FAN: usize = 6;
+
+pub fn parse_glb(data: &[u8]) -> Result<Model, ModelError> {
+    let word = |pos: usize| {
+        data.get(pos..pos + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .ok_or(ModelError::Glb)
+    };
+
+    if word(0)? != GLB_MAGIC || word(4)? != 2 {
+        return Err(ModelError::Glb);
+    }
+    let len = (word(8)? as usize).min(data.len());
+
+    let mut json = None;
+    let mut bin = None;
+    let mut pos = 12;
+    while pos + 8 <= len {
+        let chunk_len = word(pos)? as usize;
+        let chunk = data
+            .get(pos + 8..pos + 8 + chunk_len)
+            .ok_or(ModelError::Glb)?;
+
+        match word(pos + 4)? {
+            CHUNK_JSON => json = Some(chunk),
+            CHUNK_BIN => bin = Some(chunk),
+            _ => {}
+        }
+        pos += 8 + chunk_len;
+    }
+
+    parse(json.ok_or(ModelError::Glb)?, bin, |uri| {
+        log::warn!("external buffer '{}' referenced from a GLB", uri);
+        None
+    })
+}
+
+pub fn parse(
+    json: &[u8],
+    bin: Option<&[u8]>,
+    mut load: impl FnMut(&str) -> Option<Vec<u8>>,
+) -> Result<Model, ModelError> {
+    let root = Value::parse(json)?;
+
+    let buffers = root
+        .get("buffers")
+        .items()
+        .iter()
+        .enumerate()
+        .map(|(idx, buffer)| match buffer.get("uri").str() {
+            Some(uri) => match data_uri(uri) {
+                Some((_, data)) => data.ok_or(ModelError::Gltf("invalid base64 buffer")),
+                None => load(uri).ok_or_else(|| ModelError::Missing(uri.into())),
+            },
+            // The first buffer of a GLB is its binary chunk
+            None if idx == 0 => bin
+                .map(Vec::from)
+                .ok_or(ModelError::Gltf("missing GLB buffer")),
+            None => Err(ModelError::Gltf("buffer without uri")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let gltf = Gltf {
+        root: &root,
+        buffers,
+    };
+
+    let mut model = Model {
+        images: gltf.images()?,
+        ..Model::default()
+    };
+
+    let textures = root.get("textures").items();
+    let images = model.images.len();
+    let texture = |info: &Value| -> Result<Option<usize>, ModelError> {
+        if info == &Value::Null {
+            return Ok(None);
+        }
+
+        textures
+            .get(info.get("index").usize().unwrap_or(usize::MAX))
+            .and_then(|texture| texture.get("source").usize())
+            .filter(|&source| source < images)
+            .map(Some)
+            .ok_or(ModelError::Gltf("invalid texture"))
+    };
+
+    for material in root.get("materials").items() {
+        let pbr = material.get("pbrMetallicRoughness");
+        model.materials.push(Material {
+            name: material.get("name").str().unwrap_or("").into(),
+            base_color: pbr.get("baseColorFactor").floats().unwrap_or([1.0; 4]),
+            base_color_texture: texture(pbr.get("baseColorTexture"))?,
+            metallic: pbr.get("metallicFactor").f32().unwrap_or(1.0),
+            roughness: pbr.get("roughnessFactor").f32().unwrap_or(1.0),
+            emissive: material.get("emissiveFactor").floats().unwrap_or([0.0; 3]),
+            normal_texture: texture(material.get("normalTexture"))?,
+            double_sided: material.get("doubleSided").bool().unwrap_or(false),
+        });
+    }
+
+    for mesh in root.get("meshes").items() {
+        let primitives = mesh
+            .get("primitives")
+            .items()
+            .iter()
+            .filter_map(|primitive| gltf.primitive(primitive).transpose())
+            .collect::<Result<_, _>>()?;
+
+        model.meshes.push(ModelMesh {
+            name: mesh.get("name").str().unwrap_or("").into(),
+            primitives,
+        });
+    }
+
+    for node in root.get("nodes").items() {
+        model.nodes.push(Node {
+            name: node.get("name").str().unwrap_or("").into(),
+            transform: node_transform(node),
+            mesh: index(node.get("mesh"), model.meshes.len(), "invalid mesh index")?,
+            children: indices(node.get("children"), root.get("nodes").items().len())?,
+        });
+    }
+    check_tree(&model.nodes)?;
+
+    let scene = root.get("scene").usize().unwrap_or(0);
+    model.roots = match root.get("scenes").items().get(scene) {
+        Some(scene) => indices(scene.get("nodes"), model.nodes.len())?,
+        // Without scenes every node that isn't a child is a root
+        None => (0..model.nodes.len())
+            .filter(|idx| !model.nodes.iter().any(|node| node.children.contains(idx)))
+            .collect(),
+    };
+
+    Ok(model)
+}
+
+struct Gltf<'a> {
+    root: &'a Value,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Gltf<'_> {
+    fn images(&self) -> Result<Vec<ImageSource>, ModelError> {
+        self.root
+            .get("images")
+            .items()
+            .iter()
+            .map(|image| match image.get("uri").str() {
+                Some(uri) => match data_uri(uri) {
+                    Some((mime, data)) => Ok(ImageSource::Embedded {
+                        mime: mime.into(),
+                        data: data.ok_or(ModelError::Gltf("invalid base64 image"))?,
+                    }),
+                    None => Ok(ImageSource::Uri(uri.into())),
+                },
+                None => Ok(ImageSource::Embedded {
+                    mime: image.get("mimeType").str().unwrap_or("").into(),
+                    data: self.view(image.get("bufferView"))?.0.into(),
+                }),
+            })
+            .collect()
+    }
+
+    /// Bytes of the buffer view with index `idx` and its stride, if any
+    fn view(&self, idx: &Value) -> Result<(&[u8], Option<usize>), ModelError> {
+        let err = ModelError::Gltf("invalid buffer view");
+        let view = self
+            .root
+            .get("bufferViews")
+            .items()
+            .get(idx.usize().ok_or(err.clone())?)
+            .ok_or(err.clone())?;
+
+        let buffer = self
+            .buffers
+            .get(view.get("buffer").usize().ok_or(err.clone())?)
+            .ok_or(err.clone())?;
+        let offset = view.get("byteOffset").usize().unwrap_or(0);
+        let len = view.get("byteLength").usize().ok_or(err.clone())?;
+
+        Ok((
+            buffer.get(offset..offset + len).ok_or(err)?,
+            view.get("byteStride").usize(),
+        ))
+    }
+
+    fn accessor(&self, idx: &Value) -> Result<Accessor<'_>, ModelError> {
+        let err = ModelError::Gltf("invalid accessor");
+        let accessor = self
+            .root
+            .get("accessors")
+            .items()
+            .get(idx.usize().ok_or(err.clone())?)
+            .ok_or(err.clone())?;
+
+        if accessor.get("sparse") != &Value::Null {
+            return Err(ModelError::Gltf("sparse accessors are unsupported"));
+        }
+
+        let count = accessor.get("count").usize().ok_or(err.clone())?;
+        let components = match accessor.get("type").str() {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            _ => return Err(ModelError::Gltf("unsupported accessor type")),
+        };
+
+        let kind = accessor.get("componentType").usize().ok_or(err.clone())?;
+        let size = match kind {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => return Err(ModelError::Gltf("unsupported component type")),
+        };
+
+        let view = accessor.get("bufferView");
+        let (bytes, stride) = if view == &Value::Null {
+            // All zeros, but no larger than the buffers it could have been
+            // read from, so a bogus count can't exhaust memory
+            let len = self.buffers.iter().map(Vec::len).sum::<usize>();
+            if !matches!(count.checked_mul(components * size), Some(bytes) if bytes <= len) {
+                return Err(err);
+            }
+
+            (None, components * size)
+        } else {
+            let (bytes, stride) = self.view(view)?;
+            let stride = stride.unwrap_or(components * size);
+            if stride < components * size {
+                return Err(err);
+            }
+
+            (Some(bytes), stride)
+        };
+
+        Ok(Accessor {
+            bytes,
+            offset: accessor.get("byteOffset").usize().unwrap_or(0),
+            stride,
+            count,
+            components,
+            kind,
+            size,
+            normalized: accessor.get("normalized").bool().unwrap_or(false),
+        })
+    }
+
+    /// Elements of accessor `idx` converted to floats, `N` components each
+    fn floats<const N: usize>(&self, idx: &Value) -> Result<Vec<[f32; N]>, ModelError> {
+        let accessor = self.accessor(idx)?;
+        if accessor.components < N {
+            return Err(ModelError::Gltf("invalid accessor"));
+        }
+
+        (0..accessor.count)
+            .map(|i| {
+                let mut out = [0.0; N];
+                for (c, out) in out.iter_mut().enumerate() {
+                    let Some(b) = accessor.component(i, c)? else {
+                        continue;
+                    };
+
+                    *out = match (accessor.kind, accessor.normalized) {
+                        (5126, _) => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                        (5120, true) => (b[0] as i8 as f32 / 127.0).max(-1.0),
+                        (5120, false) => b[0] as i8 as f32,
+                        (5121, true) => b[0] as f32 / 255.0,
+                        (5121, false) => b[0] as f32,
+                        (5122, true) => {
+                            (i16::from_le_bytes([b[0], b[1]]) as f32 / 32767.0).max(-1.0)
+                        }
+                        (5122, false) => i16::from_le_bytes([b[0], b[1]]) as f32,
+                        (5123, true) => u16::from_le_bytes([b[0], b[1]]) as f32 / 65535.0,
+                        (5123, false) => u16::from_le_bytes([b[0], b[1]]) as f32,
+                        _ => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32,
+                    };
+                }
+
+                Ok(out)
+            })
+            .collect()
+    }
+
+    /// Elements of an index accessor, read as integers so large indices
+    /// keep their precision
+    fn integers(&self, idx: &Value) -> Result<Vec<u32>, ModelError> {
+        let accessor = self.accessor(idx)?;
+        if accessor.components != 1 || !matches!(accessor.kind, 5121 | 5123 | 5125) {
+            return Err(ModelError::Gltf("invalid index accessor"));
+        }
+
+        (0..accessor.count)
+            .map(|i| {
+                Ok(match accessor.component(i, 0)? {
+                    None => 0,
+                    Some(&[b]) => b as u32,
+                    Some(&[b0, b1]) => u16::from_le_bytes([b0, b1]) as u32,
+                    Some(b) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+                })
+            })
+            .collect()
+    }
+
+    /// Like `floats`, but empty without an accessor
+    fn optional<const N: usize>(&self, idx: &Value) -> Result<Vec<[f32; N]>, ModelError> {
+        match idx {
+            Value::Null => Ok(Vec::new()),
+            idx => self.floats(idx),
+        }
+    }
+
+    /// Triangle geometry of a primitive, `None` for points and lines
+    fn primitive(&self, primitive: &Value) -> Result<Option<Primitive>, ModelError> {
+        let mode = primitive.get("mode").usize().unwrap_or(MODE_TRIANGLES);
+        if !matches!(
+            mode,
+            MODE_TRIANGLES | MODE_TRIANGLE_STRIP | MODE_TRIANGLE_FAN
+        ) {
+            log::warn!("skipping primitive with non-triangle mode {}", mode);
+            return Ok(None);
+        }
+
+        let material = index(
+            primitive.get("material"),
+            self.root.get("materials").items().len(),
+            "invalid material index",
+        )?;
+
+        let attributes = primitive.get("attributes");
+        let positions = self.floats(attributes.get("POSITION"))?;
+        let normals = self.optional(attributes.get("NORMAL"))?;
+        let uvs = self.optional(attributes.get("TEXCOORD_0"))?;
+        let tangents = self.optional(attributes.get("TANGENT"))?;
+        if [normals.len(), uvs.len(), tangents.len()]
+            .iter()
+            .any(|&len| len != 0 && len != positions.len())
+        {
+            return Err(ModelError::Gltf("attribute counts differ"));
+        }
+
+        let indices: Vec<u32> = match primitive.get("indices") {
+            Value::Null => (0..positions.len() as u32).collect(),
+            idx => self.integers(idx)?,
+        };
+        if indices.iter().any(|&idx| idx as usize >= positions.len()) {
+            return Err(ModelError::Gltf("index out of range"));
+        }
+
+        let indices = match mode {
+            MODE_TRIANGLE_STRIP => (2..indices.len())
+                .flat_map(|i| {
+                    // Every other triangle is flipped to keep the winding
+                    if i % 2 == 0 {
+                        [indices[i - 2], indices[i - 1], indices[i]]
+                    } else {
+                        [indices[i - 1], indices[i - 2], indices[i]]
+                    }
+                })
+                .collect(),
+            MODE_TRIANGLE_FAN => (2..indices.len())
+                .flat_map(|i| [indices[0], indices[i - 1], indices[i]])
+                .collect(),
+            _ => indices,
+        };
+
+        Ok(Some(Primitive {
+            geometry: Geometry {
+                positions,
+                normals,
+                uvs,
+                tangents,
+                indices,
+            },
+            material,
+        }))
+    }
+}
+
+struct Accessor<'a> {
+    /// `None` for an accessor without a buffer view, which is all zeros
+    bytes: Option<&'a [u8]>,
+    offset: usize,
+    stride: usize,
+    count: usize,
+    components: usize,
+    /// GL type of the components
+    kind: usize,
+    size: usize,
+    normalized: bool,
+}
+
+impl Accessor<'_> {
+    /// Bytes of component `c` of element `i`, `None` if all zeros
+    fn component(&self, i: usize, c: usize) -> Result<Option<&[u8]>, ModelError> {
+        let Some(bytes) = self.bytes else {
+            return Ok(None);
+        };
+
+        let at = self.offset + i * self.stride + c * self.size;
+        bytes
+            .get(at..at + self.size)
+            .map(Some)
+            .ok_or(ModelError::Gltf("invalid accessor"))
+    }
+}
+
+/// Optional index below `len`, failing with `err` if present but invalid
+fn index(value: &Value, len: usize, err: &'static str) -> Result<Option<usize>, ModelError> {
+    match value {
+        Value::Null => Ok(None),
+        value => value
+            .usize()
+            .filter(|&idx| idx < len)
+            .map(Some)
+            .ok_or(ModelError::Gltf(err)),
+    }
+}
+
+/// Node indices in `list`, all below `len`
+fn indices(list: &Value, len: usize) -> Result<Vec<usize>, ModelError> {
+    list.items()
+        .iter()
+        .map(|idx| idx.usize().filter(|&idx| idx < len))
+        .collect::<Option<_>>()
+        .ok_or(ModelError::Gltf("invalid node index"))
+}
+
+/// Check that no node has more than one parent or is its own ancestor, which
+/// would keep `Model::world_transforms` from terminating
+fn check_tree(nodes: &[Node]) -> Result<(), ModelError> {
+    let err = ModelError::Gltf("node hierarchy is not a tree");
+
+    let mut parented = crate::mem::vec![false; nodes.len()];
+    for &child in nodes.iter().flat_map(|node| &node.children) {
+        if core::mem::replace(&mut parented[child], true) {
+            return Err(err);
+        }
+    }
+
+    // With at most one parent each, nodes not reached from the parentless
+    // ones are part of a cycle
+    let mut stack = (0..nodes.len())
+        .filter(|&idx| !parented[idx])
+        .collect::<Vec<_>>();
+    let mut reached = 0;
+    while let Some(idx) = stack.pop() {
+        reached += 1;
+        stack.extend(&nodes[idx].children);
+    }
+
+    if reached == nodes.len() {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+/// `matrix`, or the product of `translation`, `rotation` and `scale`
+fn node_transform(node: &Value) -> Matrix<4, 4> {
+    if let Some(m) = node.get("matrix").floats::<16>() {
+        let mut out = [[0.0; 4]; 4];
+        for (i, &v) in m.iter().enumerate() {
+            out[i / 4][i % 4] = v;
+        }
+
+        return out;
+    }
+
+    let [tx, ty, tz] = node.get("translation").floats().unwrap_or([0.0; 3]);
+    let [x, y, z, w] = node
+        .get("rotation")
+        .floats()
+        .unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let [sx, sy, sz] = node.get("scale").floats().unwrap_or([1.0; 3]);
+
+    let rotation = [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y + z * w),
+            2.0 * (x * z - y * w),
+            0.0,
+        ],
+        [
+            2.0 * (x * y - z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z + x * w),
+            0.0,
+        ],
+        [
+            2.0 * (x * z + y * w),
+            2.0 * (y * z - x * w),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+        ],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+    let scale = [
+        [sx, 0.0, 0.0, 0.0],
+        [0.0, sy, 0.0, 0.0],
+        [0.0, 0.0, sz, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+
+    let mut out = multiply(&rotation, &scale);
+    out[3] = [tx, ty, tz, 1.0];
+
+    out
+}
+
+/// Media type and decoded bytes of a base64 `data:` URI, `None` if `uri`
+/// isn't one
+fn data_uri(uri: &str) -> Option<(&str, Option<Vec<u8>>)> {
+    let rest = uri.strip_prefix("data:")?;
+    let (header, data) = rest.split_once(',')?;
+    let mime = header.split(';').next().unwrap_or("");
+
+    Some((
+        mime,
+        header.ends_with(";base64").then(|| base64(data)).flatten(),
+    ))
+}
+
+fn base64(src: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(src.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+
+    for byte in src.bytes().take_while(|&byte| byte != b'=') {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+
+        acc = acc << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::mem::vec};
+
+    /// A triangle with u16 indices, normalized u8 UVs in a strided view, zero
+    /// normals without a view, and out of range u32 indices
+    const TRIANGLE: &str = r#"{
+        "buffers": [{
+            "byteLength": 68,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAD/AAAAAP8AAICAAAAAAAAAAQAAAAEAAAE="
+        }],
+        "bufferViews": [
+            {"buffer": 0, "byteLength": 36},
+            {"buffer": 0, "byteOffset": 36, "byteLength": 6},
+            {"buffer": 0, "byteOffset": 44, "byteLength": 12, "byteStride": 4},
+            {"buffer": 0, "byteOffset": 56, "byteLength": 12}
+        ],
+        "accessors": [
+            {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+            {"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"},
+            {"bufferView": 2, "componentType": 5121, "normalized": true, "count": 3, "type": "VEC2"},
+            {"componentType": 5126, "count": 3, "type": "VEC3"},
+            {"bufferView": 3, "componentType": 5125, "count": 3, "type": "SCALAR"}
+        ],
+        "meshes": [{"primitives": [{
+            "attributes": {"POSITION": 0, "NORMAL": 3, "TEXCOORD_0": 2},
+            "indices": 1
+        }]}],
+        "nodes": [{"mesh": 0, "children": [1]}, {"translation": [1, 2, 3]}]
+    }"#;
+
+    fn parse_json(json: &str) -> Result<Model, ModelError> {
+        parse(json.as_bytes(), None, |_| None)
+    }
+
+    #[test]
+    fn accessors() {
+        let model = parse_json(TRIANGLE).unwrap();
+        let geometry = &model.meshes[0].primitives[0].geometry;
+        assert_eq!(
+            geometry.positions,
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        );
+        assert_eq!(geometry.indices, [0, 1, 2]);
+        assert_eq!(geometry.uvs, [[1.0, 0.0], [0.0, 1.0], [128.0 / 255.0; 2]]);
+        assert_eq!(geometry.normals, [[0.0; 3]; 3]);
+        assert!(geometry.tangents.is_empty());
+        assert_eq!(model.roots, [0]);
+    }
+
+    #[test]
+    fn index_range() {
+        let json = TRIANGLE.replace(r#""indices": 1"#, r#""indices": 4"#);
+        assert_eq!(
+            parse_json(&json).unwrap_err(),
+            ModelError::Gltf("index out of range")
+        );
+
+        // Float positions can't be indices
+        let json = TRIANGLE.replace(r#""indices": 1"#, r#""indices": 0"#);
+        assert_eq!(
+            parse_json(&json).unwrap_err(),
+            ModelError::Gltf("invalid index accessor")
+        );
+    }
+
+    #[test]
+    fn accessor_bounds() {
+        // More zeros than the buffers hold
+        let json = TRIANGLE.replace(
+            r#"{"componentType": 5126, "count": 3"#,
+            r#"{"componentType": 5126, "count": 1000000000"#,
+        );
+        assert_eq!(
+            parse_json(&json).unwrap_err(),
+            ModelError::Gltf("invalid accessor")
+        );
+
+        // Elements past the end of the view
+        let json = TRIANGLE.replace(r#""byteLength": 6}"#, r#""byteLength": 4}"#);
+        assert_eq!(
+            parse_json(&json).unwrap_err(),
+            ModelError::Gltf("invalid accessor")
+        );
+    }
+
+    #[test]
+    fn dangling_indices() {
+        let err = |from: &str, to: &str| {
+            parse_json(&TRIANGLE.replace(from, to))
+                .map(|_| ())
+                .unwrap_err()
+        };
+
+        assert_eq!(
+            err(r#"{"mesh": 0"#, r#"{"mesh": 1"#),
+            ModelError::Gltf("invalid mesh index")
+        );
+        assert_eq!(
+            err(r#""indices": 1"#, r#""indices": 1, "material": 0"#),
+            ModelError::Gltf("invalid material index")
+        );
+        assert_eq!(
+            err(
+                r#""meshes""#,
+                r#""materials": [{"normalTexture": {"index": 0}}], "meshes""#
+            ),
+            ModelError::Gltf("invalid texture")
+        );
+        assert_eq!(
+            err(
+                r#""meshes""#,
+                r#""textures": [{"source": 0}], "materials": [{"normalTexture": {"index": 0}}], "meshes""#
+            ),
+            ModelError::Gltf("invalid texture")
+        );
+    }
+
+    #[test]
+    fn attribute_counts() {
+        // Two normals for three positions
+        let json = TRIANGLE.replace(
+            r#"{"componentType": 5126, "count": 3"#,
+            r#"{"componentType": 5126, "count": 2"#,
+        );
+        assert_eq!(
+            parse_json(&json).unwrap_err(),
+            ModelError::Gltf("attribute counts differ")
+        );
+    }
+
+    #[test]
+    fn hierarchy() {
+        let roots = |nodes: &str| {
+            let json = ["{\"nodes\": ", nodes, "}"].concat();
+            parse_json(&json).map(|model| model.roots)
+        };
+
+        assert_eq!(roots(r#"[{}, {"children": [0, 2]}, {}]"#), Ok(vec![1]));
+
+        let err = Err(ModelError::Gltf("node hierarchy is not a tree"));
+        assert_eq!(roots(r#"[{"children": [1]}, {"children": [0]}]"#), err);
+        assert_eq!(roots(r#"[{"children": [0]}]"#), err);
+        assert_eq!(roots(r#"[{"children": [2]}, {"children": [2]}, {}]"#), err);
+        assert_eq!(
+            roots(r#"[{"children": [2]}]"#),
+            Err(ModelError::Gltf("invalid node index"))
+        );
+    }
+}
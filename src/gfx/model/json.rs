@@ -0,0 +1,355 @@
+use crate::mem::{string::String, vec::Vec};
+
+/// Just enough JSON for glTF and sprite sheet metadata. Objects keep their
+/// members in order and are searched linearly, which is fine for the handful
+/// of keys looked up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+/// Byte offset of malformed JSON
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParseError(pub usize);
+
+impl Value {
+    pub fn parse(src: &[u8]) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            src,
+            pos: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_ws();
+
+        if parser.pos == src.len() {
+            Ok(value)
+        } else {
+            Err(ParseError(parser.pos))
+        }
+    }
+
+    /// Member `key` of an object, `Null` if missing or not an object
+    pub fn get(&self, key: &str) -> &Value {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map_or(&Value::Null, |(_, value)| value),
+            _ => &Value::Null,
+        }
+    }
+
+    /// Elements of an array, empty if not an array
+    pub fn items(&self) -> &[Value] {
+        match self {
+            Value::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    pub fn f32(&self) -> Option<f32> {
+        match *self {
+            Value::Number(n) => Some(n as f32),
+            _ => None,
+        }
+    }
+
+    pub fn usize(&self) -> Option<usize> {
+        match *self {
+            Value::Number(n) if n >= 0.0 && n as usize as f64 == n => Some(n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Fixed length array of numbers
+    pub fn floats<const N: usize>(&self) -> Option<[f32; N]> {
+        let items = self.items();
+        if items.len() != N {
+            return None;
+        }
+
+        let mut out = [0.0; N];
+        for (out, item) in out.iter_mut().zip(items) {
+            *out = item.f32()?;
+        }
+
+        Some(out)
+    }
+}
+
+/// Arrays and objects nested deeper than this are rejected instead of
+/// overflowing the stack
+const MAX_DEPTH: u32 = 128;
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    depth: u32,
+}
+
+impl Parser<'_> {
+    fn err<T>(&self) -> Result<T, ParseError> {
+        Err(ParseError(self.pos))
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.src.get(self.pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_ws();
+        if self.src.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Step into an array or object
+    fn open(&mut self) -> Result<(), ParseError> {
+        if self.depth == MAX_DEPTH {
+            return self.err();
+        }
+        self.depth += 1;
+        self.pos += 1;
+
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &[u8], value: Value) -> Result<Value, ParseError> {
+        if self.src[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            self.err()
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_ws();
+        match self.src.get(self.pos) {
+            Some(b'{') => {
+                self.open()?;
+                let mut members = Vec::new();
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_ws();
+                        let key = self.string()?;
+                        if !self.eat(b':') {
+                            return self.err();
+                        }
+                        members.push((key, self.value()?));
+
+                        if self.eat(b'}') {
+                            break;
+                        } else if !self.eat(b',') {
+                            return self.err();
+                        }
+                    }
+                }
+
+                self.depth -= 1;
+                Ok(Value::Object(members))
+            }
+
+            Some(b'[') => {
+                self.open()?;
+                let mut items = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        items.push(self.value()?);
+
+                        if self.eat(b']') {
+                            break;
+                        } else if !self.eat(b',') {
+                            return self.err();
+                        }
+                    }
+                }
+
+                self.depth -= 1;
+                Ok(Value::Array(items))
+            }
+
+            Some(b'"') => self.string().map(Value::String),
+            Some(b't') => self.literal(b"true", Value::Bool(true)),
+            Some(b'f') => self.literal(b"false", Value::Bool(false)),
+            Some(b'n') => self.literal(b"null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while matches!(
+                    self.src.get(self.pos),
+                    Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                ) {
+                    self.pos += 1;
+                }
+
+                core::str::from_utf8(&self.src[start..self.pos])
+                    .ok()
+                    .and_then(|num| num.parse().ok())
+                    .map(Value::Number)
+                    .ok_or(ParseError(start))
+            }
+
+            _ => self.err(),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        if self.src.get(self.pos) != Some(&b'"') {
+            return self.err();
+        }
+        self.pos += 1;
+
+        let mut bytes = Vec::new();
+        loop {
+            let byte = match self.src.get(self.pos) {
+                Some(&byte) => byte,
+                None => return self.err(),
+            };
+            self.pos += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self.src.get(self.pos).copied();
+                    self.pos += 1;
+
+                    let ch = match escape {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let mut code = self.hex4()?;
+                            // Combine a surrogate pair, leaving an escape
+                            // after an unpaired high surrogate to be read next
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.src[self.pos..].starts_with(b"\\u")
+                            {
+                                let pos = self.pos;
+                                self.pos += 2;
+                                match self.hex4() {
+                                    Ok(low @ 0xDC00..=0xDFFF) => {
+                                        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
+                                    }
+                                    _ => self.pos = pos,
+                                }
+                            }
+
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => return self.err(),
+                    };
+
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+                _ => bytes.push(byte),
+            }
+        }
+
+        String::from_utf8(bytes).or_else(|_| self.err())
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParseError> {
+        let code = self
+            .src
+            .get(self.pos..self.pos + 4)
+            .and_then(|hex| core::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+        self.pos += 4;
+
+        code.ok_or(ParseError(self.pos - 4))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::mem::vec};
+
+    fn string(src: &str) -> String {
+        match Value::parse(src.as_bytes()) {
+            Ok(Value::String(s)) => s,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn values() {
+        let value =
+            Value::parse(br#" {"a": [1, -2.5e1, true, null], "b": "x", "c": {}} "#).unwrap();
+        let a = value.get("a").items();
+        assert_eq!(a[0].usize(), Some(1));
+        assert_eq!(a[1].f32(), Some(-25.0));
+        assert_eq!(a[1].usize(), None);
+        assert_eq!(a[2].bool(), Some(true));
+        assert_eq!(a[3], Value::Null);
+        assert_eq!(value.get("b").str(), Some("x"));
+        assert_eq!(value.get("c"), &Value::Object(vec![]));
+        assert_eq!(value.get("d"), &Value::Null);
+        assert_eq!(value.get("a").floats::<2>(), None);
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            string(r#""\"\\\/\b\f\n\r\t\u00e9""#),
+            "\"\\/\u{8}\u{c}\n\r\té"
+        );
+        assert_eq!(string(r#""\ud83d\ude00""#), "\u{1F600}");
+        // Unpaired surrogates become replacement characters, keeping what
+        // follows them
+        assert_eq!(string(r#""\ud83d""#), "\u{FFFD}");
+        assert_eq!(string(r#""\ud83d\u0041""#), "\u{FFFD}A");
+        assert_eq!(string(r#""\ude00\ud83d""#), "\u{FFFD}\u{FFFD}");
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src: &str| Value::parse(src.as_bytes()).unwrap_err();
+        assert_eq!(err("[1,]"), ParseError(3));
+        assert_eq!(err(r#"{"a" 1}"#), ParseError(5));
+        assert_eq!(err("1 2"), ParseError(2));
+        assert_eq!(err(r#""abc"#), ParseError(4));
+        assert_eq!(err(r#""\u12""#), ParseError(3));
+        assert_eq!(err("tru"), ParseError(0));
+    }
+
+    #[test]
+    fn depth() {
+        let nested = |depth: usize| ["[".repeat(depth), "]".repeat(depth)].concat();
+        assert!(Value::parse(nested(MAX_DEPTH as usize).as_bytes()).is_ok());
+        assert_eq!(
+            Value::parse(nested(MAX_DEPTH as usize + 1).as_bytes()),
+            Err(ParseError(MAX_DEPTH as usize))
+        );
+    }
+}
@@ -0,0 +1,176 @@
+pub mod geometry;
+mod gltf;
+pub(crate) mod json;
+mod obj;
+pub mod shapes;
+
+pub use geometry::{Geometry, ModelVertex, TangentVertex};
+
+use {
+    crate::{
+        math::{multiply, Matrix},
+        mem::{string::String, vec::Vec},
+    },
+    core::fmt,
+};
+
+/// Scene loaded from a model file. Everything refers to other parts by
+/// index, so meshes and materials can be shared between nodes.
+#[derive(Debug, Default, Clone)]
+pub struct Model {
+    pub meshes: Vec<ModelMesh>,
+    pub materials: Vec<Material>,
+    pub images: Vec<ImageSource>,
+    pub nodes: Vec<Node>,
+    /// Nodes without a parent
+    pub roots: Vec<usize>,
+}
+
+impl Model {
+    /// Parse an OBJ file, calling `load` with the name of every referenced
+    /// MTL library
+    pub fn obj(src: &str, load: impl FnMut(&str) -> Option<Vec<u8>>) -> Result<Self, ModelError> {
+        obj::parse(src, load)
+    }
+
+    /// Parse a `.gltf` file, calling `load` with the URI of every external
+    /// buffer. `data:` URIs are decoded without it.
+    pub fn gltf(
+        json: &[u8],
+        load: impl FnMut(&str) -> Option<Vec<u8>>,
+    ) -> Result<Self, ModelError> {
+        gltf::parse(json, None, load)
+    }
+
+    /// Parse a binary `.glb` file with its buffer embedded
+    pub fn glb(data: &[u8]) -> Result<Self, ModelError> {
+        gltf::parse_glb(data)
+    }
+
+    /// Model to world transform of every node, indexed like `nodes`
+    pub fn world_transforms(&self) -> Vec<Matrix<4, 4>> {
+        let mut world = crate::mem::vec![IDENTITY; self.nodes.len()];
+        let mut stack: Vec<_> = self.roots.iter().map(|&root| (root, IDENTITY)).collect();
+
+        while let Some((idx, parent)) = stack.pop() {
+            let node = &self.nodes[idx];
+            world[idx] = multiply(&parent, &node.transform);
+            stack.extend(node.children.iter().map(|&child| (child, world[idx])));
+        }
+
+        world
+    }
+}
+
+const IDENTITY: Matrix<4, 4> = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+#[derive(Debug, Default, Clone)]
+pub struct ModelMesh {
+    pub name: String,
+    pub primitives: Vec<Primitive>,
+}
+
+/// Part of a mesh drawn with a single material
+#[derive(Debug, Default, Clone)]
+pub struct Primitive {
+    pub geometry: Geometry,
+    pub material: Option<usize>,
+}
+
+/// Metallic-roughness material. OBJ materials map their diffuse colour and
+/// map to the base colour, and specular exponent to roughness.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub base_color: [f32; 4],
+    /// Index into `Model::images`
+    pub base_color_texture: Option<usize>,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: [f32; 3],
+    pub normal_texture: Option<usize>,
+    pub double_sided: bool,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: [1.0; 4],
+            base_color_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            emissive: [0.0; 3],
+            normal_texture: None,
+            double_sided: false,
+        }
+    }
+}
+
+/// Image data is left encoded, see `gfx::image::Image::decode`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageSource {
+    /// Path relative to the model file
+    Uri(String),
+    Embedded {
+        mime: String,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub name: String,
+    /// Relative to the parent node
+    pub transform: Matrix<4, 4>,
+    pub mesh: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            transform: IDENTITY,
+            mesh: None,
+            children: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelError {
+    /// Malformed OBJ or MTL statement at the line number
+    Obj(usize),
+    /// Malformed JSON at the byte offset
+    Json(usize),
+    /// Invalid glTF structure, naming what was wrong
+    Gltf(&'static str),
+    /// Bad `.glb` header or chunks
+    Glb,
+    /// `load` returned nothing for the file
+    Missing(String),
+}
+
+impl From<json::ParseError> for ModelError {
+    fn from(json::ParseError(pos): json::ParseError) -> Self {
+        ModelError::Json(pos)
+    }
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelError::Obj(line) => write!(f, "malformed statement on line {}", line),
+            ModelError::Json(pos) => write!(f, "malformed JSON at byte {}", pos),
+            ModelError::Gltf(what) => write!(f, "invalid glTF: {}", what),
+            ModelError::Glb => write!(f, "invalid GLB container"),
+            ModelError::Missing(name) => write!(f, "could not load '{}'", name),
+        }
+    }
+}
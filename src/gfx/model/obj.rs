@@ -0,0 +1,303 @@
+use {
+    super::{ImageSource, Material, Model, ModelError, ModelMesh, Node, Primitive},
+    crate::{
+        math::sqrt,
+        mem::{collections::BTreeMap, string::String, vec::Vec},
+    },
+};
+
+pub fn parse(
+    src: &str,
+    mut load: impl FnMut(&str) -> Option<Vec<u8>>,
+) -> Result<Model, ModelError> {
+    let mut model = Model::default();
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut builder = PrimitiveBuilder::default();
+    let mut mesh = ModelMesh::default();
+
+    for (line_idx, line) in src.lines().enumerate() {
+        let err = ModelError::Obj(line_idx + 1);
+        let mut words = line.split('#').next().unwrap_or("").split_whitespace();
+
+        match words.next() {
+            Some("v") => positions.push(floats(words).ok_or(err)?),
+            Some("vn") => normals.push(floats(words).ok_or(err)?),
+            Some("vt") => {
+                let [u, v] = floats(words).ok_or(err)?;
+                uvs.push([u, v]);
+            }
+
+            Some("f") => {
+                let corners = words
+                    .map(|corner| builder.vertex(corner, &positions, &normals, &uvs))
+                    .collect::<Option<Vec<_>>>()
+                    .filter(|corners| corners.len() >= 3)
+                    .ok_or(err)?;
+
+                for i in 1..corners.len() - 1 {
+                    let indices = &mut builder.primitive.geometry.indices;
+                    indices.extend_from_slice(&[corners[0], corners[i], corners[i + 1]]);
+                }
+            }
+
+            Some("o" | "g") => {
+                builder.finish(&mut mesh);
+                push_mesh(&mut model, &mut mesh);
+                mesh.name = words.collect::<Vec<_>>().join(" ");
+            }
+
+            Some("usemtl") => {
+                builder.finish(&mut mesh);
+                let name = words.next().ok_or(err)?;
+                builder.primitive.material =
+                    model.materials.iter().position(|mtl| mtl.name == name);
+            }
+
+            Some("mtllib") => {
+                for lib in words {
+                    let data = load(lib).ok_or_else(|| ModelError::Missing(lib.into()))?;
+                    let src = core::str::from_utf8(&data).map_err(|_| ModelError::Obj(0))?;
+                    parse_mtl(src, &mut model)?;
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    builder.finish(&mut mesh);
+    push_mesh(&mut model, &mut mesh);
+
+    Ok(model)
+}
+
+/// Add `mesh` with a node placing it if it has anything to draw
+fn push_mesh(model: &mut Model, mesh: &mut ModelMesh) {
+    let mesh = core::mem::take(mesh);
+    if mesh.primitives.is_empty() {
+        return;
+    }
+
+    model.roots.push(model.nodes.len());
+    model.nodes.push(Node {
+        name: mesh.name.clone(),
+        mesh: Some(model.meshes.len()),
+        ..Node::default()
+    });
+    model.meshes.push(mesh);
+}
+
+#[derive(Default)]
+struct PrimitiveBuilder {
+    primitive: Primitive,
+    /// Index of each distinct position/UV/normal combination
+    seen: BTreeMap<[usize; 3], u32>,
+    has_normals: bool,
+    has_uvs: bool,
+}
+
+impl PrimitiveBuilder {
+    /// Index of a `v/vt/vn` face corner, adding the vertex if new
+    fn vertex(
+        &mut self,
+        corner: &str,
+        positions: &[[f32; 3]],
+        normals: &[[f32; 3]],
+        uvs: &[[f32; 2]],
+    ) -> Option<u32> {
+        // 1-based, or negative counting back from the latest; 0 is unset
+        let resolve = |idx: Option<&str>, len: usize| -> Option<usize> {
+            match idx.filter(|idx| !idx.is_empty()) {
+                None => Some(0),
+                Some(idx) => {
+                    let idx: isize = idx.parse().ok()?;
+                    let idx = if idx < 0 { len as isize + idx + 1 } else { idx };
+                    (1..=len as isize).contains(&idx).then_some(idx as usize)
+                }
+            }
+        };
+
+        let mut parts = corner.split('/');
+        let key = [
+            resolve(parts.next(), positions.len()).filter(|&idx| idx != 0)?,
+            resolve(parts.next(), uvs.len())?,
+            resolve(parts.next(), normals.len())?,
+        ];
+
+        if let Some(&idx) = self.seen.get(&key) {
+            return Some(idx);
+        }
+
+        let geometry = &mut self.primitive.geometry;
+        let idx = geometry.positions.len() as u32;
+        let [pos, uv, normal] = key;
+
+        geometry.positions.push(positions[pos - 1]);
+        geometry
+            .uvs
+            .push(if uv == 0 { [0.0; 2] } else { uvs[uv - 1] });
+        geometry.normals.push(if normal == 0 {
+            [0.0; 3]
+        } else {
+            normals[normal - 1]
+        });
+        self.has_uvs |= uv != 0;
+        self.has_normals |= normal != 0;
+        self.seen.insert(key, idx);
+
+        Some(idx)
+    }
+
+    /// Move the primitive built so far into `mesh`, keeping the material
+    fn finish(&mut self, mesh: &mut ModelMesh) {
+        let material = self.primitive.material;
+        let mut done = core::mem::take(self);
+        self.primitive.material = material;
+
+        if done.primitive.geometry.indices.is_empty() {
+            return;
+        }
+        if !done.has_normals {
+            done.primitive.geometry.normals.clear();
+        }
+        if !done.has_uvs {
+            done.primitive.geometry.uvs.clear();
+        }
+
+        mesh.primitives.push(done.primitive);
+    }
+}
+
+fn parse_mtl(src: &str, model: &mut Model) -> Result<(), ModelError> {
+    let mut current: Option<Material> = None;
+
+    for (line_idx, line) in src.lines().enumerate() {
+        let err = ModelError::Obj(line_idx + 1);
+        let mut words = line.split('#').next().unwrap_or("").split_whitespace();
+        let keyword = words.next();
+
+        if keyword == Some("newmtl") {
+            model.materials.extend(current.take());
+            current = Some(Material {
+                name: words.next().ok_or(err)?.into(),
+                metallic: 0.0,
+                ..Material::default()
+            });
+            continue;
+        }
+
+        let mtl = match (&mut current, keyword) {
+            (Some(mtl), Some(_)) => mtl,
+            _ => continue,
+        };
+
+        // Texture maps may be preceded by options, the file name comes last
+        let mut image = |words: core::str::SplitWhitespace| -> Result<_, ModelError> {
+            let uri = words.last().ok_or(err.clone())?;
+            model.images.push(ImageSource::Uri(String::from(uri)));
+
+            Ok(Some(model.images.len() - 1))
+        };
+
+        match keyword.unwrap_or("") {
+            "Kd" => {
+                let [r, g, b] = floats(words).ok_or(err)?;
+                mtl.base_color = [r, g, b, mtl.base_color[3]];
+            }
+            "d" => mtl.base_color[3] = floats::<1>(words).ok_or(err)?[0],
+            "Tr" => mtl.base_color[3] = 1.0 - floats::<1>(words).ok_or(err)?[0],
+            "Ke" => mtl.emissive = floats(words).ok_or(err)?,
+            "Pm" => mtl.metallic = floats::<1>(words).ok_or(err)?[0],
+            "Pr" => mtl.roughness = floats::<1>(words).ok_or(err)?[0],
+            // Blinn-Phong exponent to the roughness of a similar highlight
+            "Ns" => mtl.roughness = sqrt(2.0 / (floats::<1>(words).ok_or(err)?[0] + 2.0)),
+            "map_Kd" => mtl.base_color_texture = image(words)?,
+            "map_Bump" | "bump" | "norm" => mtl.normal_texture = image(words)?,
+            _ => {}
+        }
+    }
+
+    model.materials.extend(current);
+
+    Ok(())
+}
+
+/// Leading `N` numbers of a statement, ignoring extra ones such as the
+/// optional `w` of a position
+fn floats<'a, const N: usize>(mut words: impl Iterator<Item = &'a str>) -> Option<[f32; N]> {
+    let mut out = [0.0; N];
+    for out in &mut out {
+        *out = words.next()?.parse().ok()?;
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(model: &Model) -> &crate::gfx::model::Geometry {
+        &model.meshes[0].primitives[0].geometry
+    }
+
+    #[test]
+    fn fans() {
+        let src = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0.5 1.5 0\nf 1 2 3 4 5\n";
+        let model = parse(src, |_| None).unwrap();
+        let geometry = geometry(&model);
+        assert_eq!(geometry.positions.len(), 5);
+        assert_eq!(geometry.indices, [0, 1, 2, 0, 2, 3, 0, 3, 4]);
+        assert!(geometry.normals.is_empty() && geometry.uvs.is_empty());
+        assert_eq!(model.roots, [0]);
+    }
+
+    #[test]
+    fn shared_corners() {
+        let src = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0.5 0.5\nvn 0 0 1\n\
+                   f 1/1/1 2/1/1 3/1/1\nf 3/1/1 -1/-1/-1 1/1/1\nf 1//1 2//1 3//1\n";
+        let model = parse(src, |_| None).unwrap();
+        let geometry = geometry(&model);
+        // Corners without a UV are distinct vertices
+        assert_eq!(geometry.indices, [0, 1, 2, 2, 3, 0, 4, 5, 6]);
+        assert_eq!(geometry.uvs[3], [0.5, 0.5]);
+        assert_eq!(geometry.uvs[4], [0.0, 0.0]);
+        assert_eq!(geometry.normals[6], [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn materials() {
+        let src = "mtllib a.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nusemtl red\nf 3 2 1\n";
+        let model = parse(src, |name| {
+            (name == "a.mtl")
+                .then(|| b"newmtl red\nKd 1 0 0\nd 0.5\nmap_Kd -s 1 1 red.png\n".to_vec())
+        })
+        .unwrap();
+
+        let primitives = &model.meshes[0].primitives;
+        assert_eq!(primitives.len(), 2);
+        assert_eq!(
+            [primitives[0].material, primitives[1].material],
+            [None, Some(0)]
+        );
+        assert_eq!(model.materials[0].base_color, [1.0, 0.0, 0.0, 0.5]);
+        assert_eq!(model.materials[0].base_color_texture, Some(0));
+        assert!(matches!(&model.images[0], ImageSource::Uri(uri) if uri == "red.png"));
+
+        assert_eq!(
+            parse("mtllib b.mtl", |_| None).unwrap_err(),
+            ModelError::Missing("b.mtl".into())
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src| parse(src, |_| None).unwrap_err();
+        assert_eq!(err("v 0 0 0\nv 1 0 0\nf 1 2"), ModelError::Obj(3));
+        assert_eq!(err("v 0 0 0\nf 1 1 2"), ModelError::Obj(2));
+        assert_eq!(err("v 0 0 0\nf 1 1 -2"), ModelError::Obj(2));
+        assert_eq!(err("v 0 x 0"), ModelError::Obj(1));
+    }
+}
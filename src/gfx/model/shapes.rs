@@ -0,0 +1,389 @@
+//! Shapes centred on the origin with y up, wound counter-clockwise seen from
+//! outside, with normals, tangents and UVs
+
+use {
+    super::Geometry,
+    crate::{
+        math::{atan2, cos, normalize, sin, sqrt},
+        mem::{collections::BTreeMap, vec::Vec},
+    },
+    core::f32::consts::{FRAC_PI_2, PI, TAU},
+};
+
+/// Single quad facing +y
+pub fn plane([width, depth]: [f32; 2]) -> Geometry {
+    grid([width, depth], [1, 1])
+}
+
+/// Plane facing +y split into `cells` quads along x and z
+pub fn grid([width, depth]: [f32; 2], [cells_x, cells_z]: [u32; 2]) -> Geometry {
+    let mut geometry = Geometry::default();
+
+    for j in 0..=cells_z {
+        for i in 0..=cells_x {
+            let [u, v] = [i as f32 / cells_x as f32, j as f32 / cells_z as f32];
+            geometry
+                .positions
+                .push([(u - 0.5) * width, 0.0, (0.5 - v) * depth]);
+            geometry.normals.push([0.0, 1.0, 0.0]);
+            geometry.uvs.push([u, v]);
+        }
+    }
+
+    quads(&mut geometry.indices, 0, cells_x + 1, cells_z + 1);
+    finish(geometry)
+}
+
+/// Box with each face textured with the whole `0..1` UV range
+pub fn cube([x, y, z]: [f32; 3]) -> Geometry {
+    let mut geometry = Geometry::default();
+    let half = [x / 2.0, y / 2.0, z / 2.0];
+
+    // Normal and the directions of u and v across the face
+    let faces = [
+        ([1.0, 0.0, 0.0], [0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+        ([-1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, -1.0]),
+        ([0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+        ([0.0, 0.0, 1.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+        ([0.0, 0.0, -1.0], [-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ];
+
+    for (normal, u, v) in faces {
+        let base = geometry.positions.len() as u32;
+        for [su, sv] in [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]] {
+            let mut pos = [0.0; 3];
+            for axis in 0..3 {
+                let offset = normal[axis] + u[axis] * (su * 2.0 - 1.0) + v[axis] * (sv * 2.0 - 1.0);
+                pos[axis] = offset * half[axis];
+            }
+
+            geometry.positions.push(pos);
+            geometry.normals.push(normal);
+            geometry.uvs.push([su, sv]);
+        }
+
+        geometry
+            .indices
+            .extend([0, 1, 2, 0, 2, 3].map(|i| base + i));
+    }
+
+    finish(geometry)
+}
+
+/// Sphere of `segments` around the y axis and `rings` from pole to pole
+pub fn uv_sphere(radius: f32, segments: u32, rings: u32) -> Geometry {
+    let profile = (0..=rings).map(|i| {
+        let v = i as f32 / rings as f32;
+        let angle = (v - 0.5) * PI;
+
+        Profile {
+            pos: [radius * cos(angle), radius * sin(angle)],
+            normal: [cos(angle), sin(angle)],
+            v,
+        }
+    });
+
+    let mut geometry = Geometry::default();
+    lathe(&mut geometry, profile, segments);
+    finish(geometry)
+}
+
+/// Sphere made by splitting the faces of an icosahedron `subdivisions`
+/// times, more even than `uv_sphere`. UVs are spherical, so textures stretch
+/// across the triangles at the seam.
+pub fn icosphere(radius: f32, subdivisions: u32) -> Geometry {
+    let t = (1.0 + sqrt(5.0)) / 2.0;
+    let mut points: Vec<[f32; 3]> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .iter()
+    .map(|&point| normalize(point))
+    .collect();
+
+    let mut faces: Vec<[u32; 3]> = crate::mem::vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = BTreeMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let [pa, pb] = [points[a as usize], points[b as usize]];
+                points.push(normalize([pa[0] + pb[0], pa[1] + pb[1], pa[2] + pb[2]]));
+                points.len() as u32 - 1
+            })
+        };
+
+        faces = faces
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let [ab, bc, ca] = [midpoint(a, b), midpoint(b, c), midpoint(c, a)];
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut geometry = Geometry::default();
+    for &[x, y, z] in &points {
+        geometry
+            .positions
+            .push([x * radius, y * radius, z * radius]);
+        geometry.normals.push([x, y, z]);
+        geometry.uvs.push([
+            0.5 + atan2(x, z) / TAU,
+            0.5 + atan2(y, sqrt(x * x + z * z)) / PI,
+        ]);
+    }
+    geometry.indices = faces.into_iter().flatten().collect();
+
+    finish(geometry)
+}
+
+/// Capped cylinder along the y axis
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> Geometry {
+    let half = height / 2.0;
+    let side = IntoIterator::into_iter([-half, half])
+        .enumerate()
+        .map(|(i, y)| Profile {
+            pos: [radius, y],
+            normal: [1.0, 0.0],
+            v: i as f32,
+        });
+
+    let mut geometry = Geometry::default();
+    lathe(&mut geometry, side, segments);
+    cap(&mut geometry, radius, -half, segments);
+    cap(&mut geometry, radius, half, segments);
+    finish(geometry)
+}
+
+/// Cone along the y axis with its apex at the top, capped at the base
+pub fn cone(radius: f32, height: f32, segments: u32) -> Geometry {
+    let half = height / 2.0;
+    let len = sqrt(height * height + radius * radius);
+    let side = IntoIterator::into_iter([[radius, -half], [0.0, half]])
+        .enumerate()
+        .map(|(i, pos)| Profile {
+            pos,
+            normal: [height / len, radius / len],
+            v: i as f32,
+        });
+
+    let mut geometry = Geometry::default();
+    lathe(&mut geometry, side, segments);
+    cap(&mut geometry, radius, -half, segments);
+    finish(geometry)
+}
+
+/// Ring around the y axis, `major` being the distance from the centre to
+/// the middle of the tube of radius `minor`
+pub fn torus(major: f32, minor: f32, segments: u32, sides: u32) -> Geometry {
+    let profile = (0..=sides).map(|i| {
+        let v = i as f32 / sides as f32;
+        let angle = v * TAU;
+
+        Profile {
+            pos: [major + minor * cos(angle), minor * sin(angle)],
+            normal: [cos(angle), sin(angle)],
+            v,
+        }
+    });
+
+    let mut geometry = Geometry::default();
+    lathe(&mut geometry, profile, segments);
+    finish(geometry)
+}
+
+/// Cylinder of `height` along the y axis with hemispherical ends, each
+/// made of `rings` rings
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Geometry {
+    let half = height / 2.0;
+    // UVs spread by distance along the profile
+    let length = PI * radius + height;
+
+    let hemisphere = |from: f32, y: f32, v_start: f32| {
+        (0..=rings).map(move |i| {
+            let angle = from + i as f32 / rings as f32 * FRAC_PI_2;
+
+            Profile {
+                pos: [radius * cos(angle), y + radius * sin(angle)],
+                normal: [cos(angle), sin(angle)],
+                v: v_start + (angle - from) * radius / length,
+            }
+        })
+    };
+
+    let bottom = hemisphere(-FRAC_PI_2, -half, 0.0);
+    let top = hemisphere(0.0, half, (FRAC_PI_2 * radius + height) / length);
+
+    let mut geometry = Geometry::default();
+    lathe(&mut geometry, bottom.chain(top), segments);
+    finish(geometry)
+}
+
+/// Point of a cross section swept around the y axis by `lathe`, as radius
+/// and height
+struct Profile {
+    pos: [f32; 2],
+    normal: [f32; 2],
+    v: f32,
+}
+
+/// Sweep `profile`, ordered bottom to top, around the y axis
+fn lathe(geometry: &mut Geometry, profile: impl Iterator<Item = Profile>, segments: u32) {
+    let base = geometry.positions.len() as u32;
+    let mut rows = 0;
+
+    for Profile {
+        pos: [r, y],
+        normal: [nr, ny],
+        v,
+    } in profile
+    {
+        // The seam is duplicated so u can run the full 0..1
+        for j in 0..=segments {
+            let u = j as f32 / segments as f32;
+            let [s, c] = [sin(u * TAU), cos(u * TAU)];
+
+            geometry.positions.push([r * s, y, r * c]);
+            geometry.normals.push([nr * s, ny, nr * c]);
+            geometry.uvs.push([u, v]);
+        }
+        rows += 1;
+    }
+
+    quads(&mut geometry.indices, base, segments + 1, rows);
+}
+
+/// Disc closing a lathed shape at height `y`, facing away from the centre
+fn cap(geometry: &mut Geometry, radius: f32, y: f32, segments: u32) {
+    let base = geometry.positions.len() as u32;
+    let up = if y > 0.0 { 1.0 } else { -1.0 };
+
+    geometry.positions.push([0.0, y, 0.0]);
+    geometry.normals.push([0.0, up, 0.0]);
+    geometry.uvs.push([0.5, 0.5]);
+
+    for j in 0..=segments {
+        let [s, c] = [
+            sin(j as f32 / segments as f32 * TAU),
+            cos(j as f32 / segments as f32 * TAU),
+        ];
+
+        geometry.positions.push([radius * s, y, radius * c]);
+        geometry.normals.push([0.0, up, 0.0]);
+        geometry.uvs.push([0.5 + s / 2.0, 0.5 - up * c / 2.0]);
+    }
+
+    for j in 1..=segments {
+        let [a, b] = [base + j, base + j + 1];
+        geometry
+            .indices
+            .extend(if up > 0.0 { [base, a, b] } else { [base, b, a] });
+    }
+}
+
+/// Two triangles for every cell of a `columns` by `rows` vertex grid laid
+/// out row by row, with u increasing along rows and v across them
+fn quads(indices: &mut Vec<u32>, base: u32, columns: u32, rows: u32) {
+    for row in 0..rows.saturating_sub(1) {
+        for col in 0..columns - 1 {
+            let a = base + row * columns + col;
+            let [b, c, d] = [a + 1, a + columns + 1, a + columns];
+
+            indices.extend_from_slice(&[a, b, c, a, c, d]);
+        }
+    }
+}
+
+fn finish(mut geometry: Geometry) -> Geometry {
+    geometry.compute_tangents();
+    geometry
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::math::{add, cross, dot, sub},
+    };
+
+    /// Check the counts of vertices and indices, that every attribute is
+    /// present and that faces wind counter-clockwise seen from outside
+    fn check(geometry: &Geometry, vertices: usize, indices: usize) {
+        assert_eq!(geometry.positions.len(), vertices);
+        assert_eq!(geometry.indices.len(), indices);
+        assert_eq!(geometry.normals.len(), vertices);
+        assert_eq!(geometry.uvs.len(), vertices);
+        assert_eq!(geometry.tangents.len(), vertices);
+        assert!(geometry.indices.iter().all(|&i| (i as usize) < vertices));
+
+        for tri in geometry.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+            let p = &geometry.positions;
+            let face = cross(sub(p[b], p[a]), sub(p[c], p[a]));
+            // Triangles collapsed at poles and apexes have no facing
+            if dot(face, face) < 1e-12 {
+                continue;
+            }
+
+            let n = &geometry.normals;
+            assert!(dot(face, add(add(n[a], n[b]), n[c])) > 0.0, "{:?}", tri);
+        }
+    }
+
+    #[test]
+    fn counts() {
+        check(&plane([2.0, 1.0]), 4, 6);
+        check(&grid([2.0, 1.0], [3, 2]), 12, 36);
+        check(&cube([1.0, 2.0, 3.0]), 24, 36);
+        check(&uv_sphere(1.0, 8, 4), 45, 192);
+        check(&icosphere(1.0, 0), 12, 60);
+        check(&icosphere(1.0, 1), 42, 240);
+        check(&cylinder(1.0, 2.0, 8), 38, 96);
+        check(&cone(1.0, 2.0, 8), 28, 72);
+        check(&torus(2.0, 0.5, 8, 6), 63, 288);
+        check(&capsule(0.5, 1.0, 8, 3), 72, 336);
+    }
+
+    #[test]
+    fn extents() {
+        let bounds = cube([1.0, 2.0, 3.0]).bounds().unwrap();
+        assert_eq!(bounds.min, [-0.5, -1.0, -1.5]);
+        assert_eq!(bounds.max, [0.5, 1.0, 1.5]);
+
+        let size = capsule(0.5, 1.0, 8, 3).bounds().unwrap().size();
+        assert!((size[1] - 2.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,425 @@
+pub mod svg;
+
+use {
+    crate::{
+        gfx::{
+            buffer::Usage,
+            mesh::{Mesh, Topology},
+            program::Program,
+            shader::{PATH_FRAG, PATH_VERT},
+            state::{Compare, RenderState, Stencil, StencilOp},
+            *,
+        },
+        math::{cos, sin, sqrt, Matrix, Spline, Subdivide},
+        mem::{vec, vec::Vec},
+    },
+    core::{f32::consts::PI, mem::take},
+};
+
+/// Which points a set of contours encloses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillRule {
+    /// Points the contours wind around a non-zero number of times
+    NonZero,
+    /// Points inside an odd number of contours
+    EvenOdd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Join {
+    /// Sharp corners, beveled where the miter would be longer than the limit
+    /// times half the stroke width
+    Miter(f32),
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cap {
+    Butt,
+    Square,
+    Round,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub join: Join,
+    pub cap: Cap,
+    /// Alternating dash and gap lengths, empty for a solid line
+    pub dashes: Vec<f32>,
+    pub dash_offset: f32,
+    /// Connect the end of each contour back to its start
+    pub closed: bool,
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            join: Join::Miter(4.0),
+            cap: Cap::Butt,
+            dashes: Vec::new(),
+            dash_offset: 0.0,
+            closed: false,
+        }
+    }
+}
+
+/// Fills and strokes paths of one or more contours with stencil-then-cover:
+/// the path's triangles are counted into the stencil buffer, then a quad
+/// over its bounds is drawn where the count says the path is and the
+/// stencil reset. The bound framebuffer needs a stencil buffer, and a
+/// multisampled one for anti-aliasing.
+pub struct PathRenderer {
+    /// Transform from path coordinates to clip space
+    pub transform: Matrix<4, 4>,
+    /// Largest distance in path coordinates a flattened curve may stray
+    /// from the real one
+    pub tolerance: f32,
+    program: Program,
+    mesh: Mesh<[f32; 2]>,
+}
+
+impl Default for PathRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PathRenderer {
+    pub fn new() -> Self {
+        Self {
+            transform: [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            tolerance: 0.25,
+            program: Program::new(PATH_VERT, PATH_FRAG),
+            mesh: Mesh::new(&[], Usage::StreamDraw, Topology::Tris),
+        }
+    }
+
+    /// Fill the area enclosed by `contours`, each implicitly closed
+    pub fn fill(&mut self, contours: &[Spline], rule: FillRule, color: [u8; 4]) {
+        let mut tris = Vec::new();
+        for contour in contours {
+            let points = self.flatten(contour);
+            for n in 1..points.len().saturating_sub(1) {
+                tris.extend([points[0], points[n], points[n + 1]]);
+            }
+        }
+
+        let ops = |op| [StencilOp::Keep, StencilOp::Keep, op];
+        let stencil = match rule {
+            FillRule::NonZero => Stencil {
+                front: ops(StencilOp::IncrWrap),
+                back: ops(StencilOp::DecrWrap),
+                ..Stencil::default()
+            },
+            FillRule::EvenOdd => Stencil {
+                front: ops(StencilOp::Invert),
+                back: ops(StencilOp::Invert),
+                ..Stencil::default()
+            },
+        };
+
+        self.draw(tris, stencil, color);
+    }
+
+    pub fn stroke(&mut self, contours: &[Spline], stroke: &Stroke, color: [u8; 4]) {
+        let mut tris = Vec::new();
+        for contour in contours {
+            let mut points = self.flatten(contour);
+            if points.is_empty() {
+                continue;
+            }
+
+            if stroke.dashes.iter().sum::<f32>() > 0.0 {
+                // Dashes run around closed contours back to the start
+                if stroke.closed && points.first() != points.last() {
+                    points.push(points[0]);
+                }
+
+                for dash in dash(&points, &stroke.dashes, stroke.dash_offset) {
+                    self.polyline(&mut tris, &dash, false, stroke);
+                }
+            } else {
+                if stroke.closed && points.len() > 2 && points.first() == points.last() {
+                    points.pop();
+                }
+
+                self.polyline(&mut tris, &points, stroke.closed, stroke);
+            }
+        }
+
+        // Overlapping segments and joins must only be drawn once
+        let stencil = Stencil {
+            reference: 1,
+            front: [StencilOp::Keep, StencilOp::Keep, StencilOp::Replace],
+            back: [StencilOp::Keep, StencilOp::Keep, StencilOp::Replace],
+            ..Stencil::default()
+        };
+
+        self.draw(tris, stencil, color);
+    }
+
+    fn draw(&mut self, mut tris: Vec<[f32; 2]>, stencil: Stencil, color: [u8; 4]) {
+        if tris.is_empty() {
+            return;
+        }
+
+        let [x0, y0, x1, y1] = tris.iter().fold(
+            [f32::MAX, f32::MAX, f32::MIN, f32::MIN],
+            |[x0, y0, x1, y1], &[x, y]| [x0.min(x), y0.min(y), x1.max(x), y1.max(y)],
+        );
+        let len = tris.len();
+        tris.extend([[x0, y0], [x1, y0], [x1, y1], [x0, y0], [x1, y1], [x0, y1]]);
+        self.mesh.update(&tris);
+
+        self.program.bind();
+        self.transform.bind(0);
+        color.map(|c| c as f32 / 255.0).bind(1);
+
+        let prev = RenderState::current();
+        RenderState {
+            depth: None,
+            cull: None,
+            color_mask: [false; 4],
+            stencil: Some(stencil),
+            ..prev
+        }
+        .apply();
+        self.mesh.draw_range(0..len);
+
+        RenderState {
+            depth: None,
+            cull: None,
+            stencil: Some(Stencil {
+                func: Compare::NotEqual,
+                front: [StencilOp::Keep, StencilOp::Zero, StencilOp::Zero],
+                back: [StencilOp::Keep, StencilOp::Zero, StencilOp::Zero],
+                ..Stencil::default()
+            }),
+            ..prev
+        }
+        .apply();
+        self.mesh.draw_range(len..len + 6);
+
+        prev.apply();
+    }
+
+    /// Points along a contour, with enough segments per curve to stay within
+    /// `tolerance`
+    fn flatten(&self, contour: &Spline) -> Vec<[f32; 2]> {
+        let mut points: Vec<[f32; 2]> = Vec::new();
+        for bezier in contour {
+            let ctrl = bezier.points();
+            let segments = if ctrl.len() <= 2 {
+                1
+            } else {
+                let len = ctrl.windows(2).map(|w| distance(w[0], w[1])).sum::<f32>();
+                (sqrt(len / self.tolerance) as usize).clamp(1, 64)
+            };
+
+            for point in bezier.subdivide(segments) {
+                if points.last() != Some(&point) {
+                    points.push(point);
+                }
+            }
+        }
+
+        points
+    }
+
+    fn polyline(
+        &self,
+        tris: &mut Vec<[f32; 2]>,
+        points: &[[f32; 2]],
+        closed: bool,
+        stroke: &Stroke,
+    ) {
+        let hw = stroke.width / 2.0;
+        let n = points.len();
+        if n < 2 {
+            return;
+        }
+
+        let segments = if closed { n } else { n - 1 };
+        let dirs = (0..segments)
+            .map(|i| direction(points[i], points[(i + 1) % n]))
+            .collect::<Vec<_>>();
+
+        for (i, &d) in dirs.iter().enumerate() {
+            let [a, b] = [points[i], points[(i + 1) % n]];
+            let offset = scale(left(d), hw);
+            quad(
+                tris,
+                add(a, offset),
+                sub(a, offset),
+                sub(b, offset),
+                add(b, offset),
+            );
+        }
+
+        let joins = if closed { 0..n } else { 1..n - 1 };
+        for i in joins {
+            let (d0, d1) = (dirs[(i + segments - 1) % segments], dirs[i % segments]);
+            self.join(tris, points[i], d0, d1, hw, stroke.join);
+        }
+
+        if !closed {
+            self.cap(tris, points[0], scale(dirs[0], -1.0), hw, stroke.cap);
+            self.cap(tris, points[n - 1], dirs[segments - 1], hw, stroke.cap);
+        }
+    }
+
+    fn join(
+        &self,
+        tris: &mut Vec<[f32; 2]>,
+        p: [f32; 2],
+        d0: [f32; 2],
+        d1: [f32; 2],
+        hw: f32,
+        join: Join,
+    ) {
+        let turn = d0[0] * d1[1] - d0[1] * d1[0];
+        if turn.abs() < 1e-6 && dot(d0, d1) > 0.0 {
+            return;
+        }
+
+        // Fill the gap on the outside of the turn
+        let side = if turn > 0.0 { -hw } else { hw };
+        let (n0, n1) = (scale(left(d0), side), scale(left(d1), side));
+        match join {
+            Join::Round => self.circle(tris, p, hw),
+            Join::Bevel => tris.extend([p, add(p, n0), add(p, n1)]),
+            Join::Miter(limit) => {
+                tris.extend([p, add(p, n0), add(p, n1)]);
+
+                let mid = add(n0, n1);
+                let len = sqrt(dot(mid, mid));
+                let cos_half = dot(scale(mid, 1.0 / len.max(1e-6)), n0) / hw;
+                if len > 1e-6 && cos_half > 1.0 / limit {
+                    let tip = add(p, scale(mid, hw / cos_half / len));
+                    tris.extend([add(p, n0), tip, add(p, n1)]);
+                }
+            }
+        }
+    }
+
+    /// End cap at `p` for a line leaving in direction `d`
+    fn cap(&self, tris: &mut Vec<[f32; 2]>, p: [f32; 2], d: [f32; 2], hw: f32, cap: Cap) {
+        match cap {
+            Cap::Butt => {}
+            Cap::Square => {
+                let (offset, end) = (scale(left(d), hw), add(p, scale(d, hw)));
+                quad(
+                    tris,
+                    add(p, offset),
+                    sub(p, offset),
+                    sub(end, offset),
+                    add(end, offset),
+                );
+            }
+            Cap::Round => self.circle(tris, p, hw),
+        }
+    }
+
+    fn circle(&self, tris: &mut Vec<[f32; 2]>, [cx, cy]: [f32; 2], radius: f32) {
+        let segments = ((sqrt(radius / self.tolerance) * 4.0) as usize).clamp(8, 64);
+        let point = |n: usize| {
+            let angle = 2.0 * PI * n as f32 / segments as f32;
+            [cx + radius * cos(angle), cy + radius * sin(angle)]
+        };
+
+        for n in 0..segments {
+            tris.extend([[cx, cy], point(n), point(n + 1)]);
+        }
+    }
+}
+
+/// Split a polyline into the dashes of an alternating dash and gap pattern
+/// starting `offset` into it
+fn dash(points: &[[f32; 2]], pattern: &[f32], offset: f32) -> Vec<Vec<[f32; 2]>> {
+    let total = pattern.iter().sum::<f32>();
+    let mut phase = offset % total;
+    if phase < 0.0 {
+        phase += total;
+    }
+
+    let (mut idx, mut on) = (0, true);
+    while phase >= pattern[idx] {
+        phase -= pattern[idx];
+        idx = (idx + 1) % pattern.len();
+        on = !on;
+    }
+    let mut left = pattern[idx] - phase;
+
+    let mut dashes = Vec::new();
+    let mut current = if on { vec![points[0]] } else { Vec::new() };
+    for w in points.windows(2) {
+        let (mut a, b) = (w[0], w[1]);
+        let mut seg = distance(a, b);
+        while seg > left {
+            a = add(a, scale(sub(b, a), left / seg));
+            seg -= left;
+            if on {
+                current.push(a);
+                dashes.push(take(&mut current));
+            } else {
+                current = vec![a];
+            }
+
+            idx = (idx + 1) % pattern.len();
+            on = !on;
+            left = pattern[idx];
+        }
+
+        left -= seg;
+        if on {
+            current.push(b);
+        }
+    }
+
+    if on && current.len() >= 2 {
+        dashes.push(current);
+    }
+
+    dashes
+}
+
+fn quad(tris: &mut Vec<[f32; 2]>, a: [f32; 2], b: [f32; 2], c: [f32; 2], d: [f32; 2]) {
+    tris.extend([a, b, c, a, c, d]);
+}
+
+fn add([ax, ay]: [f32; 2], [bx, by]: [f32; 2]) -> [f32; 2] {
+    [ax + bx, ay + by]
+}
+
+fn sub([ax, ay]: [f32; 2], [bx, by]: [f32; 2]) -> [f32; 2] {
+    [ax - bx, ay - by]
+}
+
+fn scale([x, y]: [f32; 2], s: f32) -> [f32; 2] {
+    [x * s, y * s]
+}
+
+fn dot([ax, ay]: [f32; 2], [bx, by]: [f32; 2]) -> f32 {
+    ax * bx + ay * by
+}
+
+fn left([x, y]: [f32; 2]) -> [f32; 2] {
+    [-y, x]
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    let d = sub(b, a);
+    sqrt(dot(d, d))
+}
+
+fn direction(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    scale(sub(b, a), 1.0 / distance(a, b).max(1e-6))
+}
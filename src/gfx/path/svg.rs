@@ -0,0 +1,1076 @@
+//! SVG path data and the subset of SVG documents icons use: `svg`, `g`,
+//! `path`, `rect`, `circle`, `ellipse`, `line`, `polyline` and `polygon`
+//! elements with transforms, fill and stroke presentation attributes or
+//! `style` declarations, and the root `viewBox`.
+
+use {
+    super::{Cap, FillRule, Join, PathRenderer, Stroke},
+    crate::{
+        math::{atan2, cos, ortho, sin, sqrt, Bezier, Matrix, Spline},
+        mem::vec::Vec,
+    },
+    core::f32::consts::PI,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvgError {
+    /// Malformed path data at the byte offset into the `d` attribute
+    Path(usize),
+    /// Malformed markup at the byte offset into the document
+    Xml(usize),
+    /// The document has no `svg` root element
+    NoRoot,
+}
+
+/// Contours of a path, each remembering whether it was closed with `Z`
+#[derive(Debug, Default)]
+pub struct PathData {
+    pub contours: Vec<Spline>,
+    pub closed: Vec<bool>,
+}
+
+impl PathData {
+    fn push(&mut self, contour: Spline, closed: bool) {
+        if !contour.is_empty() {
+            self.contours.push(contour);
+            self.closed.push(closed);
+        }
+    }
+
+    fn transform(&mut self, m: &Affine) {
+        for contour in &mut self.contours {
+            for bezier in contour.iter_mut() {
+                let points = bezier
+                    .points()
+                    .iter()
+                    .map(|&p| m.apply(p))
+                    .collect::<Vec<_>>();
+                *bezier = points.as_slice().into();
+            }
+        }
+    }
+}
+
+/// Parse the `d` attribute of a `path` element
+pub fn parse_path(d: &str) -> Result<PathData, SvgError> {
+    let mut src = Cursor::new(d);
+    let mut path = PathData::default();
+    let mut contour = Spline::new();
+
+    let [mut current, mut start] = [[0.0; 2]; 2];
+    // Reflected control point for the smooth curve commands
+    let mut last_ctrl: Option<(u8, [f32; 2])> = None;
+    let mut cmd = None;
+
+    loop {
+        src.skip_separators();
+        let Some(&next) = src.peek() else {
+            break;
+        };
+
+        let op = if next.is_ascii_alphabetic() {
+            src.pos += 1;
+            next
+        } else {
+            // Numbers after a command repeat it, with moves continuing as lines
+            match cmd {
+                Some(b'M') => b'L',
+                Some(b'm') => b'l',
+                Some(op) if !matches!(op, b'Z' | b'z') => op,
+                _ => return Err(SvgError::Path(src.pos)),
+            }
+        };
+        cmd = Some(op);
+
+        let rel = op.is_ascii_lowercase();
+        let origin = if rel { current } else { [0.0; 2] };
+        let point = |src: &mut Cursor| -> Result<[f32; 2], SvgError> {
+            let [x, y] = [src.number()?, src.number()?];
+            Ok([origin[0] + x, origin[1] + y])
+        };
+
+        let mut ctrl = None;
+        match op.to_ascii_uppercase() {
+            b'M' => {
+                path.push(core::mem::take(&mut contour), false);
+                current = point(&mut src)?;
+                start = current;
+            }
+            b'L' => {
+                let to = point(&mut src)?;
+                contour.push([current, to].as_slice().into());
+                current = to;
+            }
+            b'H' => {
+                let x = src.number()? + origin[0];
+                let to = [x, current[1]];
+                contour.push([current, to].as_slice().into());
+                current = to;
+            }
+            b'V' => {
+                let y = src.number()? + origin[1];
+                let to = [current[0], y];
+                contour.push([current, to].as_slice().into());
+                current = to;
+            }
+            b'C' | b'S' => {
+                let c1 = if op.eq_ignore_ascii_case(&b'C') {
+                    point(&mut src)?
+                } else {
+                    reflect(current, last_ctrl, b'C')
+                };
+                let c2 = point(&mut src)?;
+                let to = point(&mut src)?;
+                contour.push([current, c1, c2, to].as_slice().into());
+                ctrl = Some((b'C', c2));
+                current = to;
+            }
+            b'Q' | b'T' => {
+                let c = if op.eq_ignore_ascii_case(&b'Q') {
+                    point(&mut src)?
+                } else {
+                    reflect(current, last_ctrl, b'Q')
+                };
+                let to = point(&mut src)?;
+                contour.push([current, c, to].as_slice().into());
+                ctrl = Some((b'Q', c));
+                current = to;
+            }
+            b'A' => {
+                let [rx, ry, rotation] = [src.number()?, src.number()?, src.number()?];
+                let [large, sweep] = [src.flag()?, src.flag()?];
+                let to = point(&mut src)?;
+                for [p0, c1, c2, p1] in arc(current, [rx, ry], rotation, large, sweep, to) {
+                    contour.push([p0, c1, c2, p1].as_slice().into());
+                }
+                current = to;
+            }
+            b'Z' => {
+                if current != start {
+                    contour.push([current, start].as_slice().into());
+                }
+                path.push(core::mem::take(&mut contour), true);
+                current = start;
+            }
+            _ => return Err(SvgError::Path(src.pos - 1)),
+        }
+        last_ctrl = ctrl;
+    }
+    path.push(contour, false);
+
+    Ok(path)
+}
+
+/// Control point mirroring the previous curve's last one, if it was of the
+/// same kind
+fn reflect(current: [f32; 2], last: Option<(u8, [f32; 2])>, kind: u8) -> [f32; 2] {
+    match last {
+        Some((last, [x, y])) if last == kind => [2.0 * current[0] - x, 2.0 * current[1] - y],
+        _ => current,
+    }
+}
+
+/// Cubics approximating an elliptical arc, converted from endpoint to centre
+/// parameterization as in the SVG implementation notes
+fn arc(
+    p0: [f32; 2],
+    [rx, ry]: [f32; 2],
+    rotation: f32,
+    large: bool,
+    sweep: bool,
+    p1: [f32; 2],
+) -> Vec<[[f32; 2]; 4]> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if p0 == p1 {
+        return Vec::new();
+    }
+    if rx == 0.0 || ry == 0.0 {
+        return [[p0, p0, p1, p1]].into();
+    }
+
+    let phi = rotation * PI / 180.0;
+    let (sin_phi, cos_phi) = (sin(phi), cos(phi));
+    let [dx, dy] = [(p0[0] - p1[0]) / 2.0, (p0[1] - p1[1]) / 2.0];
+    let [x, y] = [cos_phi * dx + sin_phi * dy, -sin_phi * dx + cos_phi * dy];
+
+    // Scale up radii too small to reach the end point
+    let lambda = (x * x) / (rx * rx) + (y * y) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= sqrt(lambda);
+        ry *= sqrt(lambda);
+    }
+
+    let num = rx * rx * ry * ry - rx * rx * y * y - ry * ry * x * x;
+    let den = rx * rx * y * y + ry * ry * x * x;
+    let sign = if large == sweep { -1.0 } else { 1.0 };
+    let coef = sign * sqrt((num / den).max(0.0));
+    let [cx_, cy_] = [coef * rx * y / ry, -coef * ry * x / rx];
+    let center = [
+        cos_phi * cx_ - sin_phi * cy_ + (p0[0] + p1[0]) / 2.0,
+        sin_phi * cx_ + cos_phi * cy_ + (p0[1] + p1[1]) / 2.0,
+    ];
+
+    let angle =
+        |[ux, uy]: [f32; 2], [vx, vy]: [f32; 2]| atan2(ux * vy - uy * vx, ux * vx + uy * vy);
+    let u = [(x - cx_) / rx, (y - cy_) / ry];
+    let v = [(-x - cx_) / rx, (-y - cy_) / ry];
+    let start = angle([1.0, 0.0], u);
+    let mut sweep_angle = angle(u, v);
+    if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    } else if sweep && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    }
+
+    // At most a quarter turn per cubic
+    let segments = (sweep_angle.abs() / (PI / 2.0) - 1e-3) as usize + 1;
+    let delta = sweep_angle / segments as f32;
+    let k = 4.0 / 3.0 * sin(delta / 4.0) / cos(delta / 4.0);
+    let map = |[ux, uy]: [f32; 2]| {
+        [
+            center[0] + cos_phi * rx * ux - sin_phi * ry * uy,
+            center[1] + sin_phi * rx * ux + cos_phi * ry * uy,
+        ]
+    };
+
+    let mut cubics = (0..segments)
+        .map(|n| {
+            let (t0, t1) = (start + delta * n as f32, start + delta * (n + 1) as f32);
+            let (a, b) = ([cos(t0), sin(t0)], [cos(t1), sin(t1)]);
+            let c1 = [a[0] - k * a[1], a[1] + k * a[0]];
+            let c2 = [b[0] + k * b[1], b[1] - k * b[0]];
+
+            [map(a), map(c1), map(c2), map(b)]
+        })
+        .collect::<Vec<_>>();
+
+    // Keep the ends exact so the contour stays connected
+    cubics[0][0] = p0;
+    cubics[segments - 1][3] = p1;
+
+    cubics
+}
+
+struct Cursor<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src: src.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<&u8> {
+        self.src.get(self.pos)
+    }
+
+    fn skip_separators(&mut self) {
+        while matches!(self.peek(), Some(b) if b.is_ascii_whitespace() || *b == b',') {
+            self.pos += 1;
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, SvgError> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |cursor: &mut Self| {
+            while matches!(cursor.peek(), Some(b) if b.is_ascii_digit()) {
+                cursor.pos += 1;
+            }
+        };
+
+        if matches!(self.peek(), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        digits(self);
+        if self.peek() == Some(&b'.') {
+            self.pos += 1;
+            digits(self);
+        }
+        if matches!(self.peek(), Some(b'e' | b'E'))
+            && matches!(self.src.get(self.pos + 1), Some(b) if b.is_ascii_digit() || *b == b'+' || *b == b'-')
+        {
+            self.pos += 2;
+            digits(self);
+        }
+
+        core::str::from_utf8(&self.src[start..self.pos])
+            .ok()
+            .and_then(|num| num.parse().ok())
+            .ok_or(SvgError::Path(start))
+    }
+
+    /// Arc flags may be written without separators, e.g. `a1 1 0 013 4`
+    fn flag(&mut self) -> Result<bool, SvgError> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(SvgError::Path(self.pos)),
+        };
+        self.pos += 1;
+
+        Ok(flag)
+    }
+}
+
+/// Numbers in an attribute like `points` or `viewBox`, stopping at the first
+/// that doesn't parse
+fn numbers(src: &str) -> Vec<f32> {
+    let mut cursor = Cursor::new(src);
+    let mut numbers = Vec::new();
+    loop {
+        cursor.skip_separators();
+        if cursor.peek().is_none() {
+            break;
+        }
+        match cursor.number() {
+            Ok(number) => numbers.push(number),
+            Err(_) => break,
+        }
+    }
+
+    numbers
+}
+
+/// Leading number of a length, ignoring units
+fn length(src: &str) -> Option<f32> {
+    Cursor::new(src.trim()).number().ok()
+}
+
+/// `[a, b, c, d, e, f]` mapping `[x, y]` to `[ax + cy + e, bx + dy + f]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Affine([f32; 6]);
+
+impl Affine {
+    const IDENTITY: Self = Affine([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn apply(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let [a, b, c, d, e, f] = self.0;
+        [a * x + c * y + e, b * x + d * y + f]
+    }
+
+    /// `self` applied after `other`
+    fn then(&self, other: &Self) -> Self {
+        let [a, b, c, d, e, f] = other.0;
+        let [x, y] = self.apply([e, f]);
+        let [a0, b0, c0, d0, ..] = self.0;
+
+        Affine([
+            a0 * a + c0 * b,
+            b0 * a + d0 * b,
+            a0 * c + c0 * d,
+            b0 * c + d0 * d,
+            x,
+            y,
+        ])
+    }
+
+    /// Scale applied to lengths like stroke widths
+    fn scale(&self) -> f32 {
+        let [a, b, c, d, ..] = self.0;
+        sqrt((a * d - b * c).abs())
+    }
+
+    /// Parse a `transform` attribute, applying the list left to right
+    fn parse(src: &str) -> Self {
+        let mut transform = Self::IDENTITY;
+        let mut rest = src;
+        while let Some(open) = rest.find('(') {
+            let Some(close) = rest[open..].find(')').map(|close| open + close) else {
+                break;
+            };
+
+            let name = rest[..open].trim_matches(|c: char| c.is_whitespace() || c == ',');
+            let args = numbers(&rest[open + 1..close]);
+            let arg = |n: usize, default: f32| args.get(n).copied().unwrap_or(default);
+
+            let next = match name {
+                "matrix" if args.len() == 6 => {
+                    Affine([args[0], args[1], args[2], args[3], args[4], args[5]])
+                }
+                "translate" => Affine([1.0, 0.0, 0.0, 1.0, arg(0, 0.0), arg(1, 0.0)]),
+                "scale" => Affine([arg(0, 1.0), 0.0, 0.0, arg(1, arg(0, 1.0)), 0.0, 0.0]),
+                "rotate" => {
+                    let angle = arg(0, 0.0) * PI / 180.0;
+                    let (s, c) = (sin(angle), cos(angle));
+                    let [cx, cy] = [arg(1, 0.0), arg(2, 0.0)];
+                    Affine([1.0, 0.0, 0.0, 1.0, cx, cy])
+                        .then(&Affine([c, s, -s, c, 0.0, 0.0]))
+                        .then(&Affine([1.0, 0.0, 0.0, 1.0, -cx, -cy]))
+                }
+                "skewX" => {
+                    let angle = arg(0, 0.0) * PI / 180.0;
+                    Affine([1.0, 0.0, sin(angle) / cos(angle), 1.0, 0.0, 0.0])
+                }
+                "skewY" => {
+                    let angle = arg(0, 0.0) * PI / 180.0;
+                    Affine([1.0, sin(angle) / cos(angle), 0.0, 1.0, 0.0, 0.0])
+                }
+                _ => Self::IDENTITY,
+            };
+
+            transform = transform.then(&next);
+            rest = &rest[close + 1..];
+        }
+
+        transform
+    }
+}
+
+/// A filled and/or stroked path in document coordinates
+#[derive(Debug)]
+pub struct Shape {
+    pub path: PathData,
+    pub fill: Option<[u8; 4]>,
+    pub fill_rule: FillRule,
+    pub stroke: Option<[u8; 4]>,
+    pub stroke_style: Stroke,
+}
+
+#[derive(Debug)]
+pub struct Document {
+    /// `[x, y, w, h]` region of document coordinates to show
+    pub view_box: [f32; 4],
+    /// Intended size, the view box size if not given
+    pub size: [f32; 2],
+    pub shapes: Vec<Shape>,
+}
+
+impl Document {
+    pub fn parse(src: &str) -> Result<Self, SvgError> {
+        let root = Element::parse(src)?;
+        if root.name != "svg" {
+            return Err(SvgError::NoRoot);
+        }
+
+        let attr = |name: &str| root.attr(name).and_then(length);
+        let view_box = match root.attr("viewBox").map(numbers).as_deref() {
+            Some(&[x, y, w, h]) => [x, y, w, h],
+            _ => [
+                0.0,
+                0.0,
+                attr("width").unwrap_or(100.0),
+                attr("height").unwrap_or(100.0),
+            ],
+        };
+        let size = [
+            attr("width").unwrap_or(view_box[2]),
+            attr("height").unwrap_or(view_box[3]),
+        ];
+
+        let mut shapes = Vec::new();
+        collect(&root, &Style::default(), &mut shapes);
+
+        Ok(Self {
+            view_box,
+            size,
+            shapes,
+        })
+    }
+
+    /// Projection showing the view box, flipping SVG's downward y axis
+    pub fn projection(&self) -> Matrix<4, 4> {
+        let [x, y, w, h] = self.view_box;
+        ortho([x, y + h], [x + w, y])
+    }
+
+    /// Draw every shape in document order. `paths.transform` should map
+    /// document coordinates to clip space, e.g. `projection`.
+    pub fn draw(&self, paths: &mut PathRenderer) {
+        for shape in &self.shapes {
+            if let Some(color) = shape.fill {
+                paths.fill(&shape.path.contours, shape.fill_rule, color);
+            }
+
+            if let Some(color) = shape.stroke {
+                // Stroke runs of closed and open contours separately
+                let mut start = 0;
+                for run in shape.path.closed.chunk_by(|a, b| a == b) {
+                    let end = start + run.len();
+                    let stroke = Stroke {
+                        closed: run[0],
+                        ..shape.stroke_style.clone()
+                    };
+                    paths.stroke(&shape.path.contours[start..end], &stroke, color);
+                    start = end;
+                }
+            }
+        }
+    }
+}
+
+/// Inherited presentation attributes
+#[derive(Debug, Clone)]
+struct Style {
+    transform: Affine,
+    fill: Option<[u8; 4]>,
+    fill_rule: FillRule,
+    stroke_color: Option<[u8; 4]>,
+    stroke: Stroke,
+    /// Fill and stroke opacity
+    opacity: [f32; 2],
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            transform: Affine::IDENTITY,
+            fill: Some([0, 0, 0, 0xFF]),
+            fill_rule: FillRule::NonZero,
+            stroke_color: None,
+            stroke: Stroke {
+                join: Join::Miter(4.0),
+                ..Stroke::default()
+            },
+            opacity: [1.0; 2],
+        }
+    }
+}
+
+impl Style {
+    fn inherit(&self, element: &Element) -> Self {
+        let mut style = self.clone();
+        if let Some(transform) = element.attr("transform") {
+            style.transform = self.transform.then(&Affine::parse(transform));
+        }
+
+        let declarations = element
+            .attr("style")
+            .unwrap_or("")
+            .split(';')
+            .filter_map(|decl| {
+                let (name, value) = decl.split_once(':')?;
+                Some((name.trim(), value.trim()))
+            });
+        let attributes = element.attrs.iter().copied();
+
+        // Style declarations take precedence over presentation attributes
+        for (name, value) in attributes.chain(declarations) {
+            match name {
+                "fill" => style.fill = color(value),
+                "stroke" => style.stroke_color = color(value),
+                "fill-rule" => {
+                    style.fill_rule = match value {
+                        "evenodd" => FillRule::EvenOdd,
+                        _ => FillRule::NonZero,
+                    }
+                }
+                "stroke-width" => style.stroke.width = length(value).unwrap_or(1.0),
+                "stroke-linejoin" => {
+                    style.stroke.join = match value {
+                        "round" => Join::Round,
+                        "bevel" => Join::Bevel,
+                        _ => Join::Miter(4.0),
+                    }
+                }
+                "stroke-miterlimit" => {
+                    if let (Join::Miter(_), Some(limit)) = (style.stroke.join, length(value)) {
+                        style.stroke.join = Join::Miter(limit);
+                    }
+                }
+                "stroke-linecap" => {
+                    style.stroke.cap = match value {
+                        "round" => Cap::Round,
+                        "square" => Cap::Square,
+                        _ => Cap::Butt,
+                    }
+                }
+                "stroke-dasharray" => style.stroke.dashes = numbers(value),
+                "stroke-dashoffset" => style.stroke.dash_offset = length(value).unwrap_or(0.0),
+                "opacity" => {
+                    let opacity = length(value).unwrap_or(1.0);
+                    style.opacity = style.opacity.map(|o| o * opacity);
+                }
+                "fill-opacity" => style.opacity[0] = self.opacity[0] * length(value).unwrap_or(1.0),
+                "stroke-opacity" => {
+                    style.opacity[1] = self.opacity[1] * length(value).unwrap_or(1.0)
+                }
+                _ => {}
+            }
+        }
+
+        style
+    }
+}
+
+fn collect(element: &Element, parent: &Style, shapes: &mut Vec<Shape>) {
+    let style = parent.inherit(element);
+    let attr = |name: &str| element.attr(name).and_then(length).unwrap_or(0.0);
+
+    let path = match element.name {
+        "svg" | "g" => {
+            for child in &element.children {
+                collect(child, &style, shapes);
+            }
+            return;
+        }
+        "path" => match parse_path(element.attr("d").unwrap_or("")) {
+            Ok(path) => path,
+            Err(err) => {
+                log::warn!("skipping path with malformed data: {:?}", err);
+                return;
+            }
+        },
+        "rect" => {
+            let [x, y, w, h] = [attr("x"), attr("y"), attr("width"), attr("height")];
+            let (rx, ry) = match (element.attr("rx"), element.attr("ry")) {
+                (Some(_), None) => (attr("rx"), attr("rx")),
+                (None, Some(_)) => (attr("ry"), attr("ry")),
+                _ => (attr("rx"), attr("ry")),
+            };
+            rect([x, y, w, h], [rx.min(w / 2.0), ry.min(h / 2.0)])
+        }
+        "circle" => ellipse([attr("cx"), attr("cy")], [attr("r"); 2]),
+        "ellipse" => ellipse([attr("cx"), attr("cy")], [attr("rx"), attr("ry")]),
+        "line" => polyline(&[attr("x1"), attr("y1"), attr("x2"), attr("y2")], false),
+        "polyline" => polyline(&numbers(element.attr("points").unwrap_or("")), false),
+        "polygon" => polyline(&numbers(element.attr("points").unwrap_or("")), true),
+        _ => return,
+    };
+
+    let mut path = path;
+    path.transform(&style.transform);
+
+    let alpha = |[r, g, b, a]: [u8; 4], opacity: f32| [r, g, b, (a as f32 * opacity) as u8];
+    shapes.push(Shape {
+        path,
+        fill: style.fill.map(|fill| alpha(fill, style.opacity[0])),
+        fill_rule: style.fill_rule,
+        stroke: style
+            .stroke_color
+            .map(|stroke| alpha(stroke, style.opacity[1])),
+        stroke_style: Stroke {
+            width: style.stroke.width * style.transform.scale(),
+            dashes: style
+                .stroke
+                .dashes
+                .iter()
+                .map(|dash| dash * style.transform.scale())
+                .collect(),
+            dash_offset: style.stroke.dash_offset * style.transform.scale(),
+            ..style.stroke.clone()
+        },
+    });
+}
+
+fn line(a: [f32; 2], b: [f32; 2]) -> Bezier {
+    [a, b].as_slice().into()
+}
+
+/// Cubic control point distance for a quarter of a unit circle
+const KAPPA: f32 = 0.552_284_8;
+
+/// Quarter ellipse from `from` to `to` around `center`
+fn quarter(center: [f32; 2], from: [f32; 2], to: [f32; 2]) -> Bezier {
+    let c1 = [
+        from[0] + KAPPA * (to[0] - center[0]),
+        from[1] + KAPPA * (to[1] - center[1]),
+    ];
+    let c2 = [
+        to[0] + KAPPA * (from[0] - center[0]),
+        to[1] + KAPPA * (from[1] - center[1]),
+    ];
+    [from, c1, c2, to].as_slice().into()
+}
+
+fn rect([x, y, w, h]: [f32; 4], [rx, ry]: [f32; 2]) -> PathData {
+    let mut path = PathData::default();
+    if w <= 0.0 || h <= 0.0 {
+        return path;
+    }
+
+    let [x1, y1] = [x + w, y + h];
+    let contour = if rx > 0.0 && ry > 0.0 {
+        [
+            line([x + rx, y], [x1 - rx, y]),
+            quarter([x1 - rx, y + ry], [x1 - rx, y], [x1, y + ry]),
+            line([x1, y + ry], [x1, y1 - ry]),
+            quarter([x1 - rx, y1 - ry], [x1, y1 - ry], [x1 - rx, y1]),
+            line([x1 - rx, y1], [x + rx, y1]),
+            quarter([x + rx, y1 - ry], [x + rx, y1], [x, y1 - ry]),
+            line([x, y1 - ry], [x, y + ry]),
+            quarter([x + rx, y + ry], [x, y + ry], [x + rx, y]),
+        ]
+        .into()
+    } else {
+        [
+            line([x, y], [x1, y]),
+            line([x1, y], [x1, y1]),
+            line([x1, y1], [x, y1]),
+            line([x, y1], [x, y]),
+        ]
+        .into()
+    };
+    path.push(contour, true);
+
+    path
+}
+
+fn ellipse([cx, cy]: [f32; 2], [rx, ry]: [f32; 2]) -> PathData {
+    let mut path = PathData::default();
+    if rx <= 0.0 || ry <= 0.0 {
+        return path;
+    }
+
+    let points = [[cx + rx, cy], [cx, cy + ry], [cx - rx, cy], [cx, cy - ry]];
+    let contour = (0..4)
+        .map(|n| quarter([cx, cy], points[n], points[(n + 1) % 4]))
+        .collect();
+    path.push(contour, true);
+
+    path
+}
+
+fn polyline(coords: &[f32], closed: bool) -> PathData {
+    let points = coords
+        .chunks_exact(2)
+        .map(|p| [p[0], p[1]])
+        .collect::<Vec<_>>();
+    let mut contour = points
+        .windows(2)
+        .map(|w| line(w[0], w[1]))
+        .collect::<Spline>();
+    if let (true, Some(&first), Some(&last)) = (closed, points.first(), points.last()) {
+        if first != last {
+            contour.push(line(last, first));
+        }
+    }
+
+    let mut path = PathData::default();
+    path.push(contour, closed);
+
+    path
+}
+
+/// `none`, `#rgb`, `#rrggbb`, `rgb(r, g, b)` or a basic colour keyword
+fn color(value: &str) -> Option<[u8; 4]> {
+    let value = value.trim();
+    let hex = |digits: &str| u32::from_str_radix(digits, 16).ok();
+
+    match value {
+        "none" | "transparent" => None,
+        "black" | "currentColor" => Some([0, 0, 0, 0xFF]),
+        "white" => Some([0xFF, 0xFF, 0xFF, 0xFF]),
+        "red" => Some([0xFF, 0, 0, 0xFF]),
+        "green" => Some([0, 0x80, 0, 0xFF]),
+        "lime" => Some([0, 0xFF, 0, 0xFF]),
+        "blue" => Some([0, 0, 0xFF, 0xFF]),
+        "yellow" => Some([0xFF, 0xFF, 0, 0xFF]),
+        "cyan" | "aqua" => Some([0, 0xFF, 0xFF, 0xFF]),
+        "magenta" | "fuchsia" => Some([0xFF, 0, 0xFF, 0xFF]),
+        "gray" | "grey" => Some([0x80, 0x80, 0x80, 0xFF]),
+        "orange" => Some([0xFF, 0xA5, 0, 0xFF]),
+        _ if value.starts_with('#') && value.len() == 4 => {
+            let rgb = hex(&value[1..])?;
+            let [r, g, b] = [rgb >> 8, rgb >> 4, rgb].map(|c| (c & 0xF) as u8 * 0x11);
+            Some([r, g, b, 0xFF])
+        }
+        _ if value.starts_with('#') && value.len() == 7 => {
+            let [_, r, g, b] = hex(&value[1..])?.to_be_bytes();
+            Some([r, g, b, 0xFF])
+        }
+        _ if value.starts_with("rgb(") => {
+            let [r, g, b] = match *numbers(value.get(4..value.len() - 1)?).as_slice() {
+                [r, g, b] => [r, g, b],
+                _ => return None,
+            };
+            Some([r as u8, g as u8, b as u8, 0xFF])
+        }
+        _ => {
+            log::warn!("unsupported colour {}", value);
+            Some([0, 0, 0, 0xFF])
+        }
+    }
+}
+
+/// Elements nested deeper than this are rejected instead of overflowing the
+/// stack
+const MAX_DEPTH: u32 = 128;
+
+/// Just enough of an XML parser for SVG: elements and attributes, skipping
+/// the prolog, comments, processing instructions and text
+#[derive(Debug)]
+struct Element<'a> {
+    name: &'a str,
+    attrs: Vec<(&'a str, &'a str)>,
+    children: Vec<Element<'a>>,
+}
+
+impl<'a> Element<'a> {
+    fn parse(src: &'a str) -> Result<Self, SvgError> {
+        let mut cursor = Cursor::new(src);
+        loop {
+            skip_misc(&mut cursor)?;
+            if cursor.peek().is_none() {
+                return Err(SvgError::NoRoot);
+            }
+            if cursor.src[cursor.pos..].starts_with(b"<!") {
+                // DOCTYPE
+                skip_past(&mut cursor, b">")?;
+                continue;
+            }
+
+            return Self::parse_element(src, &mut cursor, 0);
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&'a str> {
+        self.attrs
+            .iter()
+            .find(|(attr, _)| *attr == name)
+            .map(|&(_, value)| value)
+    }
+
+    /// Element at the cursor, inside `depth` others
+    fn parse_element(src: &'a str, cursor: &mut Cursor, depth: u32) -> Result<Self, SvgError> {
+        if cursor.peek() != Some(&b'<') || depth == MAX_DEPTH {
+            return Err(SvgError::Xml(cursor.pos));
+        }
+        cursor.pos += 1;
+
+        let name = xml_name(src, cursor)?;
+        let mut element = Element {
+            name,
+            attrs: Vec::new(),
+            children: Vec::new(),
+        };
+
+        loop {
+            cursor.skip_separators();
+            match cursor.peek() {
+                Some(b'/') => {
+                    cursor.pos += 1;
+                    expect(cursor, b">")?;
+                    return Ok(element);
+                }
+                Some(b'>') => {
+                    cursor.pos += 1;
+                    break;
+                }
+                Some(_) => {
+                    let attr = xml_name(src, cursor)?;
+                    cursor.skip_separators();
+                    expect(cursor, b"=")?;
+                    cursor.skip_separators();
+
+                    let quote = *cursor.peek().ok_or(SvgError::Xml(cursor.pos))?;
+                    if quote != b'"' && quote != b'\'' {
+                        return Err(SvgError::Xml(cursor.pos));
+                    }
+                    cursor.pos += 1;
+                    let start = cursor.pos;
+                    skip_past(cursor, &[quote])?;
+                    element.attrs.push((attr, &src[start..cursor.pos - 1]));
+                }
+                None => return Err(SvgError::Xml(cursor.pos)),
+            }
+        }
+
+        loop {
+            // Skip text content
+            while matches!(cursor.peek(), Some(b) if *b != b'<') {
+                cursor.pos += 1;
+            }
+
+            let rest = &cursor.src[cursor.pos..];
+            if rest.is_empty() {
+                return Err(SvgError::Xml(cursor.pos));
+            } else if rest.starts_with(b"</") {
+                cursor.pos += 2;
+                let close = xml_name(src, cursor)?;
+                if close != element.name {
+                    return Err(SvgError::Xml(cursor.pos));
+                }
+                cursor.skip_separators();
+                expect(cursor, b">")?;
+
+                return Ok(element);
+            } else if rest.starts_with(b"<![CDATA[") {
+                skip_past(cursor, b"]]>")?;
+            } else if rest.starts_with(b"<!--") || rest.starts_with(b"<?") {
+                skip_misc(cursor)?;
+            } else {
+                element
+                    .children
+                    .push(Self::parse_element(src, cursor, depth + 1)?);
+            }
+        }
+    }
+}
+
+/// Skip whitespace, comments and processing instructions
+fn skip_misc(cursor: &mut Cursor) -> Result<(), SvgError> {
+    loop {
+        cursor.skip_separators();
+        let rest = &cursor.src[cursor.pos..];
+        if rest.starts_with(b"<!--") {
+            skip_past(cursor, b"-->")?;
+        } else if rest.starts_with(b"<?") {
+            skip_past(cursor, b"?>")?;
+        } else {
+            return Ok(());
+        }
+    }
+}
+
+/// Move the cursor past the next occurrence of `pattern`
+fn skip_past(cursor: &mut Cursor, pattern: &[u8]) -> Result<(), SvgError> {
+    let found = cursor.src[cursor.pos..]
+        .windows(pattern.len())
+        .position(|window| window == pattern)
+        .ok_or(SvgError::Xml(cursor.pos))?;
+    cursor.pos += found + pattern.len();
+
+    Ok(())
+}
+
+fn expect(cursor: &mut Cursor, pattern: &[u8]) -> Result<(), SvgError> {
+    if cursor.src[cursor.pos..].starts_with(pattern) {
+        cursor.pos += pattern.len();
+        Ok(())
+    } else {
+        Err(SvgError::Xml(cursor.pos))
+    }
+}
+
+fn xml_name<'a>(src: &'a str, cursor: &mut Cursor) -> Result<&'a str, SvgError> {
+    let start = cursor.pos;
+    while matches!(cursor.peek(), Some(b) if b.is_ascii_alphanumeric() || b"-_:.".contains(b)) {
+        cursor.pos += 1;
+    }
+
+    if cursor.pos == start {
+        Err(SvgError::Xml(start))
+    } else {
+        Ok(&src[start..cursor.pos])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, core::f32::consts::FRAC_1_SQRT_2};
+
+    fn points(path: &PathData, contour: usize) -> Vec<&[[f32; 2]]> {
+        path.contours[contour].iter().map(Bezier::points).collect()
+    }
+
+    fn near([x0, y0]: [f32; 2], [x1, y1]: [f32; 2]) -> bool {
+        (x0 - x1).abs() < 1e-3 && (y0 - y1).abs() < 1e-3
+    }
+
+    #[test]
+    fn lines() {
+        let path = parse_path("M10 20 h5 v5 H10 z m1,1 l2-2").unwrap();
+        assert_eq!(path.closed, [true, false]);
+        assert_eq!(
+            points(&path, 0),
+            [
+                [[10.0, 20.0], [15.0, 20.0]],
+                [[15.0, 20.0], [15.0, 25.0]],
+                [[15.0, 25.0], [10.0, 25.0]],
+                [[10.0, 25.0], [10.0, 20.0]],
+            ]
+        );
+        assert_eq!(points(&path, 1), [[[11.0, 21.0], [13.0, 19.0]]]);
+    }
+
+    #[test]
+    fn implicit_commands() {
+        // Coordinates after a move continue as lines, numbers may run together
+        let path = parse_path("M.5.5 1 1-1e1,2").unwrap();
+        assert_eq!(
+            points(&path, 0),
+            [[[0.5, 0.5], [1.0, 1.0]], [[1.0, 1.0], [-10.0, 2.0]]]
+        );
+    }
+
+    #[test]
+    fn smooth_curves() {
+        let path = parse_path("M0 0 C1 1 2 1 3 0 S5 -1 6 0 Q7 1 8 0 T10 0").unwrap();
+        let curves = points(&path, 0);
+        assert_eq!(
+            curves[1],
+            [[3.0, 0.0], [4.0, -1.0], [5.0, -1.0], [6.0, 0.0]]
+        );
+        // The quadratic doesn't reflect the cubic before it
+        assert_eq!(curves[2], [[6.0, 0.0], [7.0, 1.0], [8.0, 0.0]]);
+        assert_eq!(curves[3], [[8.0, 0.0], [9.0, -1.0], [10.0, 0.0]]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_path("1 2").unwrap_err(), SvgError::Path(0));
+        assert_eq!(parse_path("M0 0 X").unwrap_err(), SvgError::Path(5));
+        assert_eq!(parse_path("M0 0 L1").unwrap_err(), SvgError::Path(7));
+        assert_eq!(
+            parse_path("M0 0 A1 1 0 2 0 1 1").unwrap_err(),
+            SvgError::Path(12)
+        );
+    }
+
+    #[test]
+    fn arcs() {
+        // Quarter of the unit circle around the origin
+        let cubics = arc([1.0, 0.0], [1.0, 1.0], 0.0, false, true, [0.0, 1.0]);
+        assert_eq!(cubics.len(), 1);
+        let [p0, c1, c2, p1] = cubics[0];
+        assert_eq!([p0, p1], [[1.0, 0.0], [0.0, 1.0]]);
+        let mid = [0, 1].map(|i| (p0[i] + 3.0 * (c1[i] + c2[i]) + p1[i]) / 8.0);
+        assert!(near(mid, [FRAC_1_SQRT_2; 2]));
+
+        // The large arc around the other centre takes three quarter turns
+        let cubics = arc([1.0, 0.0], [1.0, 1.0], 0.0, true, true, [0.0, 1.0]);
+        assert_eq!(cubics.len(), 3);
+        assert!(near(cubics[0][3], [2.0, 1.0]));
+        assert!(near(cubics[1][3], [1.0, 2.0]));
+
+        // Radii too small are scaled up to reach the end point
+        let cubics = arc([0.0, 0.0], [0.5, 0.5], 0.0, false, true, [2.0, 0.0]);
+        assert_eq!(cubics.len(), 2);
+        assert!(near(cubics[0][3], [1.0, -1.0]));
+
+        assert!(arc([1.0, 1.0], [1.0, 1.0], 0.0, false, true, [1.0, 1.0]).is_empty());
+        assert_eq!(
+            arc([0.0, 0.0], [0.0, 1.0], 0.0, false, true, [1.0, 1.0]),
+            [[[0.0, 0.0], [0.0, 0.0], [1.0, 1.0], [1.0, 1.0]]]
+        );
+    }
+
+    #[test]
+    fn transforms() {
+        let m = Affine::parse("translate(10 20) scale(2)");
+        assert_eq!(m.apply([1.0, 1.0]), [12.0, 22.0]);
+        assert_eq!(m.scale(), 2.0);
+
+        let m = Affine::parse("matrix(1 2 3 4 5 6)");
+        assert_eq!(m.apply([1.0, 1.0]), [9.0, 12.0]);
+
+        assert!(near(
+            Affine::parse("rotate(90, 1, 1)").apply([2.0, 1.0]),
+            [1.0, 2.0]
+        ));
+        assert!(near(
+            Affine::parse("skewX(45)").apply([0.0, 1.0]),
+            [1.0, 1.0]
+        ));
+        assert_eq!(Affine::parse("scale(2"), Affine::IDENTITY);
+    }
+
+    #[test]
+    fn depth() {
+        let nested = |depth: usize| {
+            let [open, close] = ["<g>".repeat(depth - 1), "</g>".repeat(depth - 1)];
+            ["<svg>", &open, &close, "</svg>"].concat()
+        };
+        assert!(Document::parse(&nested(MAX_DEPTH as usize)).is_ok());
+        assert_eq!(
+            Document::parse(&nested(MAX_DEPTH as usize + 1)).unwrap_err(),
+            SvgError::Xml(5 + 3 * (MAX_DEPTH as usize - 1))
+        );
+    }
+}
@@ -0,0 +1,480 @@
+use crate::{
+    gfx::{
+        framebuffer::Attachment,
+        graph::{FrameGraph, Handle, PassContext, Size, TargetDesc},
+        mesh::{Mesh, Topology},
+        program::Program,
+        shader::*,
+        state::RenderState,
+        texture::{Filter, Format, Rgba16F, Texture, TextureRgba, Wrap},
+        Resource, Uniform,
+    },
+    mem::vec::Vec,
+};
+
+/// Curve mapping HDR colours into the displayable range
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMap {
+    Reinhard = 0,
+    Aces = 1,
+    Uncharted2 = 2,
+}
+
+/// Full-screen effect of a `PostChain`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// Separable blur reaching `radius` pixels
+    GaussianBlur {
+        radius: f32,
+    },
+    /// Dual filter blur, each iteration doubling the reach at little cost.
+    /// No iterations leaves the image as it is.
+    KawaseBlur {
+        iterations: u32,
+    },
+    /// Glow around what is brighter than `threshold`, blurred over
+    /// `iterations` as with `KawaseBlur`
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        iterations: u32,
+    },
+    ToneMap {
+        curve: ToneMap,
+        exposure: f32,
+    },
+    Fxaa,
+    /// Darken towards the corners, `radius` and `softness` being fractions
+    /// of the screen height
+    Vignette {
+        radius: f32,
+        softness: f32,
+        strength: f32,
+    },
+    /// Split red and blue apart towards the corners by up to `strength`
+    /// pixels
+    ChromaticAberration {
+        strength: f32,
+    },
+    Crt {
+        curvature: f32,
+        scanlines: f32,
+        mask: f32,
+    },
+    /// Colour grading through the LUT of `PostChain::set_lut`
+    ColorGrade {
+        strength: f32,
+    },
+}
+
+/// Where a pass renders to
+#[derive(Clone, Copy)]
+enum Output {
+    Target(Handle<Rgba16F>),
+    Present,
+}
+
+/// Post-processing applied in `FrameGraph` passes, the last of which
+/// renders to the window. Intermediate results are kept in half float
+/// targets, so tone mapping can come after effects on HDR colours.
+///
+/// The effects can be changed between frames.
+pub struct PostChain {
+    pub effects: Vec<Effect>,
+    lut: Option<TextureRgba>,
+    quad: Mesh<[f32; 2]>,
+    copy: Program,
+    blur: Program,
+    kawase_down: Program,
+    kawase_up: Program,
+    bright: Program,
+    bloom: Program,
+    tone_map: Program,
+    fxaa: Program,
+    vignette: Program,
+    chromatic: Program,
+    crt: Program,
+    grade: Program,
+}
+
+impl PostChain {
+    pub fn new(effects: Vec<Effect>) -> Self {
+        Self {
+            effects,
+            lut: None,
+            // One triangle covering the screen
+            quad: Mesh::static_draw(&[[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]], Topology::Tris),
+            copy: Program::new(POST_VERT, TEX2D),
+            blur: Program::new(POST_VERT, POST_BLUR),
+            kawase_down: Program::new(POST_VERT, POST_KAWASE_DOWN),
+            kawase_up: Program::new(POST_VERT, POST_KAWASE_UP),
+            bright: Program::new(POST_VERT, POST_BRIGHT),
+            bloom: Program::new(POST_VERT, POST_BLOOM),
+            tone_map: Program::new(POST_VERT, POST_TONEMAP),
+            fxaa: Program::new(POST_VERT, POST_FXAA),
+            vignette: Program::new(POST_VERT, POST_VIGNETTE),
+            chromatic: Program::new(POST_VERT, POST_CHROMATIC),
+            crt: Program::new(POST_VERT, POST_CRT),
+            grade: Program::new(POST_VERT, POST_LUT),
+        }
+    }
+
+    /// Lookup table for `Effect::ColorGrade`, `n` slices of `n` x `n` laid
+    /// out left to right in an `n * n` x `n` texture, red increasing along x,
+    /// green along y and blue from slice to slice. Without one the effect
+    /// does nothing.
+    pub fn set_lut(&mut self, lut: Option<TextureRgba>) {
+        if let Some(lut) = &lut {
+            lut.set_filter(Filter::Linear, Filter::Linear);
+            lut.set_wrap(Wrap::ClampToEdge, Wrap::ClampToEdge);
+        }
+        self.lut = lut;
+    }
+
+    /// Add passes applying the effects to `input` in order, presenting the
+    /// result. `input` is expected to be the size of the window, and is
+    /// left with linear filtering clamped to its edges.
+    pub fn apply<'a, F: Format + 'static>(&'a self, graph: &mut FrameGraph<'a>, input: Handle<F>) {
+        let mut effects = self.effects.iter().peekable();
+        let Some(first) = effects.next() else {
+            self.fullscreen(
+                graph,
+                "post copy",
+                &self.copy,
+                input,
+                None,
+                Output::Present,
+                |_, _| {},
+            );
+            return;
+        };
+
+        let mut output = Self::output(graph, effects.peek().is_none());
+        self.effect(graph, *first, input, output);
+        while let (Output::Target(src), Some(effect)) = (output, effects.next()) {
+            output = Self::output(graph, effects.peek().is_none());
+            self.effect(graph, *effect, src, output);
+        }
+    }
+
+    fn output(graph: &mut FrameGraph, last: bool) -> Output {
+        if last {
+            Output::Present
+        } else {
+            Output::Target(Self::target(graph, 1.0))
+        }
+    }
+
+    fn target(graph: &mut FrameGraph, scale: f32) -> Handle<Rgba16F> {
+        let size = if scale == 1.0 {
+            Size::Window
+        } else {
+            Size::Scaled(scale)
+        };
+
+        graph.create("post", TargetDesc::new(size, None))
+    }
+
+    fn effect<'a, F: Format + 'static>(
+        &'a self,
+        graph: &mut FrameGraph<'a>,
+        effect: Effect,
+        src: Handle<F>,
+        output: Output,
+    ) {
+        match effect {
+            Effect::GaussianBlur { radius } => {
+                let horizontal = Self::target(graph, 1.0);
+                let blur = |dir: fn([f32; 2]) -> [f32; 2]| {
+                    move |_: &PassContext, texel: [f32; 2]| {
+                        dir(texel).bind(0);
+                        radius.bind(1);
+                    }
+                };
+
+                let (h, v) = (blur(|[x, _]| [x, 0.0]), blur(|[_, y]| [0.0, y]));
+                self.fullscreen(
+                    graph,
+                    "gaussian blur",
+                    &self.blur,
+                    src,
+                    None,
+                    Output::Target(horizontal),
+                    h,
+                );
+                self.fullscreen(
+                    graph,
+                    "gaussian blur",
+                    &self.blur,
+                    horizontal,
+                    None,
+                    output,
+                    v,
+                );
+            }
+
+            Effect::KawaseBlur { iterations } => {
+                self.kawase(graph, src, 1.0, iterations, output);
+            }
+
+            Effect::Bloom {
+                threshold,
+                intensity,
+                iterations,
+            } => {
+                let bright = Self::target(graph, 0.5);
+                self.fullscreen(
+                    graph,
+                    "bloom threshold",
+                    &self.bright,
+                    src,
+                    None,
+                    Output::Target(bright),
+                    move |_, _| threshold.bind(1),
+                );
+
+                let blurred = if iterations == 0 {
+                    bright
+                } else {
+                    let blurred = Self::target(graph, 0.5);
+                    self.kawase(graph, bright, 0.5, iterations, Output::Target(blurred));
+                    blurred
+                };
+
+                self.fullscreen(
+                    graph,
+                    "bloom",
+                    &self.bloom,
+                    src,
+                    Some(blurred),
+                    output,
+                    move |_, _| intensity.bind(1),
+                );
+            }
+
+            Effect::ToneMap { curve, exposure } => {
+                self.fullscreen(
+                    graph,
+                    "tone map",
+                    &self.tone_map,
+                    src,
+                    None,
+                    output,
+                    move |_, _| {
+                        exposure.bind(1);
+                        (curve as i32).bind(2);
+                    },
+                );
+            }
+
+            Effect::Fxaa => {
+                self.fullscreen(graph, "fxaa", &self.fxaa, src, None, output, |_, texel| {
+                    texel.bind(0)
+                });
+            }
+
+            Effect::Vignette {
+                radius,
+                softness,
+                strength,
+            } => {
+                self.fullscreen(
+                    graph,
+                    "vignette",
+                    &self.vignette,
+                    src,
+                    None,
+                    output,
+                    move |ctx, _| {
+                        let [w, h] = ctx.size();
+                        [radius, softness, strength, w as f32 / h.max(1) as f32].bind(1);
+                    },
+                );
+            }
+
+            Effect::ChromaticAberration { strength } => {
+                self.fullscreen(
+                    graph,
+                    "chromatic aberration",
+                    &self.chromatic,
+                    src,
+                    None,
+                    output,
+                    move |_, texel| {
+                        texel.bind(0);
+                        strength.bind(1);
+                    },
+                );
+            }
+
+            Effect::Crt {
+                curvature,
+                scanlines,
+                mask,
+            } => {
+                self.fullscreen(
+                    graph,
+                    "crt",
+                    &self.crt,
+                    src,
+                    None,
+                    output,
+                    move |_, texel| {
+                        texel.bind(0);
+                        [curvature, scanlines, mask, 0.0].bind(1);
+                    },
+                );
+            }
+
+            Effect::ColorGrade { strength } => match &self.lut {
+                Some(lut) => {
+                    self.fullscreen(
+                        graph,
+                        "color grade",
+                        &self.grade,
+                        src,
+                        None,
+                        output,
+                        move |_, _| {
+                            lut.bind_unit(1);
+                            strength.bind(1);
+                        },
+                    );
+                }
+                None => {
+                    self.fullscreen(graph, "post copy", &self.copy, src, None, output, |_, _| {});
+                }
+            },
+        }
+    }
+
+    /// Dual filter blur of `src`, which is `scale` times the window size,
+    /// halving the size `iterations` times and doubling it back. No
+    /// iterations copies `src` as it is.
+    fn kawase<'a, F: Format + 'static>(
+        &'a self,
+        graph: &mut FrameGraph<'a>,
+        src: Handle<F>,
+        scale: f32,
+        iterations: u32,
+        output: Output,
+    ) {
+        if iterations == 0 {
+            self.fullscreen(graph, "post copy", &self.copy, src, None, output, |_, _| {});
+            return;
+        }
+
+        let half_texel = |_: &PassContext, [x, y]: [f32; 2]| [x * 0.5, y * 0.5].bind(0);
+
+        let mut scale = scale * 0.5;
+        let mut down = Self::target(graph, scale);
+        self.fullscreen(
+            graph,
+            "kawase down",
+            &self.kawase_down,
+            src,
+            None,
+            Output::Target(down),
+            half_texel,
+        );
+        for _ in 1..iterations {
+            scale *= 0.5;
+            let next = Self::target(graph, scale);
+            self.fullscreen(
+                graph,
+                "kawase down",
+                &self.kawase_down,
+                down,
+                None,
+                Output::Target(next),
+                half_texel,
+            );
+            down = next;
+        }
+
+        // Back up through new targets, as targets can't be written after
+        // being read
+        let mut up = down;
+        for _ in 1..iterations {
+            scale *= 2.0;
+            let next = Self::target(graph, scale);
+            self.fullscreen(
+                graph,
+                "kawase up",
+                &self.kawase_up,
+                up,
+                None,
+                Output::Target(next),
+                half_texel,
+            );
+            up = next;
+        }
+        self.fullscreen(
+            graph,
+            "kawase up",
+            &self.kawase_up,
+            up,
+            None,
+            output,
+            half_texel,
+        );
+    }
+
+    /// Pass drawing `program` over the output with `src` bound to unit 0 and
+    /// `extra` to unit 1, `uniforms` being given the texel size of `src`
+    #[allow(clippy::too_many_arguments)]
+    fn fullscreen<'a, F: Format + 'static>(
+        &'a self,
+        graph: &mut FrameGraph<'a>,
+        name: &'static str,
+        program: &'a Program,
+        src: Handle<F>,
+        extra: Option<Handle<Rgba16F>>,
+        output: Output,
+        uniforms: impl Fn(&PassContext, [f32; 2]) + 'a,
+    ) {
+        let mut pass = graph.pass(name).read(src);
+        if let Some(extra) = extra {
+            pass = pass.read(extra);
+        }
+        pass = match output {
+            Output::Target(target) => pass.write(target, Attachment::Color(0)),
+            Output::Present => pass.present(None),
+        };
+
+        pass.execute(move |ctx| {
+            let tex = ctx.texture(src);
+            sample(tex, 0);
+            if let Some(extra) = extra {
+                sample(ctx.texture(extra), 1);
+            }
+
+            program.bind();
+            let [w, h] = tex.size();
+            uniforms(ctx, [1.0 / w as f32, 1.0 / h as f32]);
+
+            let prev = RenderState::current();
+            RenderState {
+                blend: None,
+                depth: None,
+                cull: None,
+                scissor: None,
+                stencil: None,
+                ..prev
+            }
+            .apply();
+            self.quad.draw();
+            prev.apply();
+        });
+    }
+}
+
+/// Bind `tex` to `unit` with bilinear filtering, clamped to its edges. This
+/// is set on the texture itself, there being no sampler objects to keep it
+/// apart, so it stays set afterwards.
+fn sample<F: Format>(tex: &Texture<F>, unit: u32) {
+    tex.set_filter(Filter::Linear, Filter::Linear);
+    tex.set_wrap(Wrap::ClampToEdge, Wrap::ClampToEdge);
+    tex.bind_unit(unit);
+}
@@ -1,15 +1,52 @@
-use crate::gfx::{gl::*, shader::Shader, Resource};
+use crate::gfx::{
+    buffer::{Buffer, DISPATCH_INDIRECT},
+    gl::*,
+    shader::{Shader, Stage},
+    Resource,
+};
 
 pub struct Program(GLuint);
 
 impl Program {
     pub fn new(vert_src: &str, frag_src: &str) -> Program {
+        Self::link(&[(vert_src, Stage::Vertex), (frag_src, Stage::Fragment)])
+    }
+
+    pub fn with_geometry(vert_src: &str, geom_src: &str, frag_src: &str) -> Program {
+        Self::link(&[
+            (vert_src, Stage::Vertex),
+            (geom_src, Stage::Geometry),
+            (frag_src, Stage::Fragment),
+        ])
+    }
+
+    /// Tessellated programs must be drawn with `Topology::Patches`.
+    pub fn with_tessellation(
+        vert_src: &str,
+        tesc_src: &str,
+        tese_src: &str,
+        frag_src: &str,
+    ) -> Program {
+        Self::link(&[
+            (vert_src, Stage::Vertex),
+            (tesc_src, Stage::TessControl),
+            (tese_src, Stage::TessEvaluation),
+            (frag_src, Stage::Fragment),
+        ])
+    }
+
+    /// Link any combination of graphics stages, e.g. tessellation followed by
+    /// a geometry shader.
+    pub fn link(stages: &[(&str, Stage)]) -> Program {
         let prog = unsafe { glCreateProgram() };
 
-        let vert = Shader::new(vert_src, GL_VERTEX_SHADER);
-        let frag = Shader::new(frag_src, GL_FRAGMENT_SHADER);
-        vert.attach(prog);
-        frag.attach(prog);
+        let shaders = stages
+            .iter()
+            .map(|&(src, stage)| Shader::new(src, stage))
+            .collect::<crate::mem::vec::Vec<_>>();
+        for shader in shaders.iter() {
+            shader.attach(prog);
+        }
 
         unsafe {
             glLinkProgram(prog);
@@ -34,3 +71,90 @@ impl Drop for Program {
         }
     }
 }
+
+pub struct ComputeProgram(GLuint);
+
+impl ComputeProgram {
+    pub fn new(comp_src: &str) -> ComputeProgram {
+        let prog = unsafe { glCreateProgram() };
+
+        let comp = Shader::new(comp_src, Stage::Compute);
+        comp.attach(prog);
+
+        unsafe {
+            glLinkProgram(prog);
+        }
+
+        ComputeProgram(prog)
+    }
+
+    pub fn dispatch(&self, [x, y, z]: [u32; 3]) {
+        self.bind();
+        unsafe {
+            glDispatchCompute(x, y, z);
+        }
+    }
+
+    /// Dispatch with the group counts read from `buf` at byte `offset`, laid
+    /// out as three consecutive `u32`s.
+    pub fn dispatch_indirect(&self, buf: &Buffer, offset: usize) {
+        self.bind();
+        buf.bind_to(DISPATCH_INDIRECT);
+        unsafe {
+            glDispatchComputeIndirect(offset as _);
+        }
+    }
+
+    pub fn work_group_size(&self) -> [u32; 3] {
+        let mut size = [0i32; 3];
+        unsafe {
+            glGetProgramiv(self.0, GL_COMPUTE_WORK_GROUP_SIZE, size.as_mut_ptr());
+        }
+
+        size.map(|n| n as _)
+    }
+}
+
+impl Resource for ComputeProgram {
+    fn bind(&self) {
+        unsafe {
+            glUseProgram(self.0);
+        }
+    }
+}
+
+impl Drop for ComputeProgram {
+    fn drop(&mut self) {
+        unsafe {
+            glDeleteProgram(self.0);
+        }
+    }
+}
+
+pub fn memory_barrier(barriers: &[Barrier]) {
+    let bits = barriers
+        .iter()
+        .fold(0, |bits, &barrier| bits | barrier as GLbitfield);
+
+    unsafe {
+        glMemoryBarrier(bits);
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy)]
+pub enum Barrier {
+    VertexAttribArray = GL_VERTEX_ATTRIB_ARRAY_BARRIER_BIT,
+    ElementArray = GL_ELEMENT_ARRAY_BARRIER_BIT,
+    Uniform = GL_UNIFORM_BARRIER_BIT,
+    TextureFetch = GL_TEXTURE_FETCH_BARRIER_BIT,
+    ShaderImageAccess = GL_SHADER_IMAGE_ACCESS_BARRIER_BIT,
+    Command = GL_COMMAND_BARRIER_BIT,
+    PixelBuffer = GL_PIXEL_BUFFER_BARRIER_BIT,
+    TextureUpdate = GL_TEXTURE_UPDATE_BARRIER_BIT,
+    BufferUpdate = GL_BUFFER_UPDATE_BARRIER_BIT,
+    Framebuffer = GL_FRAMEBUFFER_BARRIER_BIT,
+    AtomicCounter = GL_ATOMIC_COUNTER_BARRIER_BIT,
+    ShaderStorage = GL_SHADER_STORAGE_BARRIER_BIT,
+    All = GL_ALL_BARRIER_BITS,
+}
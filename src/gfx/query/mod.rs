@@ -0,0 +1,384 @@
+use {
+    crate::{
+        gfx::*,
+        mem::{string::String, vec::Vec},
+        win::time,
+    },
+    core::{
+        fmt::Write,
+        ptr::{addr_of, addr_of_mut},
+    },
+};
+
+/// GPU query object, read back without waiting once its result is available
+#[derive(Debug)]
+pub struct Query(GLuint);
+
+impl Query {
+    pub fn new() -> Self {
+        let mut id = 0;
+        unsafe {
+            glGenQueries(1, &mut id);
+        }
+
+        Self(id)
+    }
+
+    /// Record the GPU time in nanoseconds once the commands before it are done
+    pub fn timestamp(&self) {
+        unsafe {
+            glQueryCounter(self.0, GL_TIMESTAMP);
+        }
+    }
+
+    /// Measure the nanoseconds the GPU spends on commands until `end_elapsed`.
+    /// Elapsed time queries can't be nested.
+    pub fn begin_elapsed(&self) {
+        unsafe {
+            glBeginQuery(GL_TIME_ELAPSED, self.0);
+        }
+    }
+
+    pub fn end_elapsed() {
+        unsafe {
+            glEndQuery(GL_TIME_ELAPSED);
+        }
+    }
+
+    pub fn available(&self) -> bool {
+        let mut available = 0;
+        unsafe {
+            glGetQueryObjectiv(self.0, GL_QUERY_RESULT_AVAILABLE, &mut available);
+        }
+
+        available != 0
+    }
+
+    /// The result if the GPU got to it yet
+    pub fn result(&self) -> Option<u64> {
+        if !self.available() {
+            return None;
+        }
+
+        let mut result = 0;
+        unsafe {
+            glGetQueryObjectui64v(self.0, GL_QUERY_RESULT, &mut result);
+        }
+
+        Some(result)
+    }
+}
+
+impl Default for Query {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Query {
+    fn drop(&mut self) {
+        unsafe {
+            glDeleteQueries(1, &self.0);
+        }
+    }
+}
+
+/// Frames a GPU result may lag behind before it's given up on
+const LATENCY: usize = 4;
+/// Resolved frames kept for `Profiler::frames`
+const HISTORY: usize = 120;
+
+/// Timing of one scope, in nanoseconds on the `win::time` clock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeTiming {
+    pub name: &'static str,
+    /// Number of scopes this one is nested in
+    pub depth: u32,
+    pub cpu: [u64; 2],
+    /// Start and end of the GPU work issued in the scope, `None` if the
+    /// results didn't arrive in time
+    pub gpu: Option<[u64; 2]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub index: u64,
+    pub cpu: [u64; 2],
+    /// In the order the scopes were entered
+    pub scopes: Vec<ScopeTiming>,
+}
+
+struct Pending {
+    name: &'static str,
+    depth: u32,
+    cpu: [u64; 2],
+    queries: [Query; 2],
+}
+
+struct PendingFrame {
+    index: u64,
+    cpu: [u64; 2],
+    scopes: Vec<Pending>,
+}
+
+/// Named CPU and GPU timings per frame. GPU times come from timestamp
+/// queries read back frames later without stalling, so a frame's timings
+/// show up in `frames` after a few more frames have ended.
+pub struct Profiler {
+    index: u64,
+    start: u64,
+    scopes: Vec<Pending>,
+    open: Vec<usize>,
+    pending: Vec<PendingFrame>,
+    frames: Vec<Frame>,
+    free: Vec<Query>,
+    /// GPU timestamp minus CPU time, to put both on the same clock
+    gpu_offset: i64,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        let mut gpu = 0;
+        unsafe {
+            glGetInteger64v(GL_TIMESTAMP, &mut gpu);
+        }
+        let now = time::now();
+
+        Self {
+            index: 0,
+            start: now,
+            scopes: Vec::new(),
+            open: Vec::new(),
+            pending: Vec::new(),
+            frames: Vec::new(),
+            free: Vec::new(),
+            gpu_offset: gpu - now as i64,
+        }
+    }
+
+    /// Open a scope, closed by the next `pop`
+    pub fn push(&mut self, name: &'static str) {
+        let queries = [self.query(), self.query()];
+        queries[0].timestamp();
+
+        self.open.push(self.scopes.len());
+        self.scopes.push(Pending {
+            name,
+            depth: self.open.len() as u32 - 1,
+            cpu: [time::now(); 2],
+            queries,
+        });
+    }
+
+    pub fn pop(&mut self) {
+        if let Some(scope) = self.open.pop() {
+            let scope = &mut self.scopes[scope];
+            scope.queries[1].timestamp();
+            scope.cpu[1] = time::now();
+        }
+    }
+
+    fn query(&mut self) -> Query {
+        self.free.pop().unwrap_or_default()
+    }
+
+    /// Finish the frame, closing its open scopes, and collect the results of
+    /// earlier frames
+    pub fn end_frame(&mut self) {
+        while !self.open.is_empty() {
+            self.pop();
+        }
+
+        let end = time::now();
+        self.pending.push(PendingFrame {
+            index: self.index,
+            cpu: [self.start, end],
+            scopes: core::mem::take(&mut self.scopes),
+        });
+        self.index += 1;
+        self.start = end;
+
+        while let Some(frame) = self.pending.first() {
+            let ready = frame
+                .scopes
+                .iter()
+                .all(|scope| scope.queries[1].available());
+            if !ready && self.pending.len() <= LATENCY {
+                break;
+            }
+
+            let frame = self.pending.remove(0);
+            self.resolve(frame);
+        }
+    }
+
+    fn resolve(&mut self, frame: PendingFrame) {
+        let mut scopes = Vec::with_capacity(frame.scopes.len());
+        for Pending {
+            name,
+            depth,
+            cpu,
+            queries,
+        } in frame.scopes
+        {
+            let [start, end] = &queries;
+            let gpu = start
+                .result()
+                .zip(end.result())
+                .map(|(start, end)| [start, end].map(|t| (t as i64 - self.gpu_offset) as u64));
+
+            scopes.push(ScopeTiming {
+                name,
+                depth,
+                cpu,
+                gpu,
+            });
+            self.free.extend(queries);
+        }
+
+        if self.frames.len() == HISTORY {
+            self.frames.remove(0);
+        }
+        self.frames.push(Frame {
+            index: frame.index,
+            cpu: frame.cpu,
+            scopes,
+        });
+    }
+
+    /// Resolved frames, oldest first
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// Log the scopes of the latest resolved frame
+    pub fn log(&self) {
+        let Some(frame) = self.frames.last() else {
+            return;
+        };
+
+        let [start, end] = frame.cpu;
+        log::info!("frame {}: {:.3}ms", frame.index, ms(end - start));
+        for scope in &frame.scopes {
+            let indent = (scope.depth as usize + 1) * 2;
+            match scope.gpu {
+                Some([gpu_start, gpu_end]) => log::info!(
+                    "{:indent$}{}: cpu {:.3}ms, gpu {:.3}ms",
+                    "",
+                    scope.name,
+                    ms(scope.cpu[1] - scope.cpu[0]),
+                    ms(gpu_end.saturating_sub(gpu_start)),
+                    indent = indent
+                ),
+                None => log::info!(
+                    "{:indent$}{}: cpu {:.3}ms",
+                    "",
+                    scope.name,
+                    ms(scope.cpu[1] - scope.cpu[0]),
+                    indent = indent
+                ),
+            }
+        }
+    }
+
+    /// Resolved frames in the Chrome trace event format, for
+    /// `chrome://tracing` or Perfetto, with CPU and GPU scopes as two threads
+    pub fn chrome_trace(&self) -> String {
+        let origin = self.frames.first().map_or(0, |frame| frame.cpu[0]);
+        let us = |ns: u64| ns.saturating_sub(origin) as f64 / 1000.0;
+
+        let mut json = String::from(concat!(
+            "{\"traceEvents\":[",
+            "{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":0,\"tid\":0,\"args\":{\"name\":\"CPU\"}},",
+            "{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":0,\"tid\":1,\"args\":{\"name\":\"GPU\"}}",
+        ));
+        let mut event = |name: &str, tid: u32, [start, end]: [u64; 2]| {
+            json.push_str(",{\"name\":\"");
+            for ch in name.chars() {
+                if ch == '"' || ch == '\\' {
+                    json.push('\\');
+                }
+                json.push(ch);
+            }
+            let _ = write!(
+                json,
+                "\",\"ph\":\"X\",\"pid\":0,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3}}}",
+                tid,
+                us(start),
+                us(end) - us(start)
+            );
+        };
+
+        for frame in &self.frames {
+            event("frame", 0, frame.cpu);
+            for scope in &frame.scopes {
+                event(scope.name, 0, scope.cpu);
+                if let Some(gpu) = scope.gpu {
+                    event(scope.name, 1, gpu);
+                }
+            }
+        }
+
+        json.push_str("],\"displayTimeUnit\":\"ms\"}");
+        json
+    }
+}
+
+fn ms(ns: u64) -> f64 {
+    ns as f64 / 1_000_000.0
+}
+
+/// Profiler `scope` records into, GL contexts only being used from a single
+/// thread
+static mut PROFILER: Option<Profiler> = None;
+
+/// Set while `profiler` lends out the global profiler
+static mut BORROWED: bool = false;
+
+/// Start recording `scope`s into a global profiler, or stop and drop it.
+/// Needs a current GL context. Does nothing from within `profiler`.
+pub fn enable(enable: bool) {
+    unsafe {
+        if !*addr_of!(BORROWED) {
+            *addr_of_mut!(PROFILER) = enable.then(Profiler::new);
+        }
+    }
+}
+
+/// Access the global profiler, if enabled and not already being accessed
+/// further up the stack
+pub fn profiler<R>(f: impl FnOnce(&mut Profiler) -> R) -> Option<R> {
+    unsafe {
+        if *addr_of!(BORROWED) {
+            return None;
+        }
+        let profiler = (*addr_of_mut!(PROFILER)).as_mut()?;
+        *addr_of_mut!(BORROWED) = true;
+        let result = f(profiler);
+        *addr_of_mut!(BORROWED) = false;
+        Some(result)
+    }
+}
+
+/// Time until the returned guard is dropped under `name`, if the global
+/// profiler is enabled
+pub fn scope(name: &'static str) -> Scope {
+    Scope(profiler(|profiler| profiler.push(name)).is_some())
+}
+
+#[must_use = "the scope ends when dropped"]
+pub struct Scope(bool);
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if self.0 {
+            profiler(Profiler::pop);
+        }
+    }
+}
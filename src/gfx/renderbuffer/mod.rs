@@ -0,0 +1,71 @@
+use {
+    crate::gfx::{texture::Format, *},
+    core::marker::PhantomData,
+};
+
+/// Render target storage that can't be sampled, for depth and stencil
+/// buffers only ever used while rendering
+#[derive(Debug)]
+pub struct Renderbuffer<F: Format> {
+    id: GLuint,
+    size: [i32; 2],
+    samples: i32,
+    format: PhantomData<F>,
+}
+
+impl<F: Format> Renderbuffer<F> {
+    pub fn new(size: [i32; 2]) -> Self {
+        Self::multisample(size, 0)
+    }
+
+    /// Storage for `samples` samples per pixel, 0 for a single sample
+    pub fn multisample([w, h]: [i32; 2], samples: i32) -> Self {
+        let mut id = 0;
+        unsafe {
+            glGenRenderbuffers(1, &mut id);
+            glBindRenderbuffer(GL_RENDERBUFFER, id);
+            glRenderbufferStorageMultisample(GL_RENDERBUFFER, samples, F::GL_INTERNAL_FORMAT, w, h);
+        }
+
+        Self {
+            id,
+            size: [w, h],
+            samples,
+            format: PhantomData,
+        }
+    }
+
+    pub fn size(&self) -> [i32; 2] {
+        self.size
+    }
+
+    pub fn samples(&self) -> i32 {
+        self.samples
+    }
+}
+
+impl<F: Format> Resource for Renderbuffer<F> {
+    fn bind(&self) {
+        log::debug!("binding renderbuffer {}", self.id);
+        unsafe {
+            glBindRenderbuffer(GL_RENDERBUFFER, self.id);
+        }
+    }
+}
+
+impl<F: Format> core::ops::Deref for Renderbuffer<F> {
+    type Target = GLuint;
+
+    fn deref(&self) -> &Self::Target {
+        &self.id
+    }
+}
+
+impl<F: Format> Drop for Renderbuffer<F> {
+    fn drop(&mut self) {
+        log::debug!("dropping renderbuffer {}", self.id);
+        unsafe {
+            glDeleteRenderbuffers(1, &self.id);
+        }
+    }
+}
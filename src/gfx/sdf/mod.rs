@@ -0,0 +1,176 @@
+use crate::{
+    gfx::{
+        buffer::Usage,
+        mesh::{vertex::Norm, Mesh, Topology},
+        program::Program,
+        shader::{SDF_BOX_FRAG, SDF_BOX_VERT},
+        state::{Blend, RenderState},
+        *,
+    },
+    math::ortho,
+    mem::vec::Vec,
+};
+
+/// Look of a box drawn by `Boxes`. Everything is anti-aliased from the
+/// signed distance to the rounded outline, so it stays sharp at any size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxStyle {
+    /// Corner radii counter-clockwise from the bottom left
+    pub radii: [f32; 4],
+    pub fill: Fill,
+    /// Width of the border drawn inside the outline, 0 for none
+    pub border: f32,
+    pub border_color: [u8; 4],
+    pub shadow: Option<Shadow>,
+}
+
+impl Default for BoxStyle {
+    fn default() -> Self {
+        Self {
+            radii: [0.0; 4],
+            fill: Fill::Solid([0xFF; 4]),
+            border: 0.0,
+            border_color: [0; 4],
+            shadow: None,
+        }
+    }
+}
+
+/// Gradient positions are fractions of the box size, `[0, 0]` being the
+/// bottom left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    Solid([u8; 4]),
+    Linear {
+        start: [f32; 2],
+        end: [f32; 2],
+        colors: [[u8; 4]; 2],
+    },
+    Radial {
+        center: [f32; 2],
+        radius: f32,
+        colors: [[u8; 4]; 2],
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    pub offset: [f32; 2],
+    /// Width of the soft edge in pixels
+    pub blur: f32,
+    /// How much larger than the box the shadow is
+    pub spread: f32,
+    pub color: [u8; 4],
+}
+
+impl Shadow {
+    /// Distance the shadow reaches past the box on any side
+    pub fn extent(&self) -> f32 {
+        self.offset[0].abs().max(self.offset[1].abs()) + self.blur + self.spread.max(0.0)
+    }
+}
+
+crate::vertex! {
+    #[derive(Debug, Clone, Copy)]
+    struct Instance {
+        #[location = 1] rect: [f32; 4],
+        #[location = 2] radii: [f32; 4],
+        #[location = 3] gradient: [f32; 4],
+        #[location = 4] shadow: [f32; 4],
+        #[location = 5] params: [f32; 2],
+        #[location = 6] fill_start: Norm<[u8; 4]>,
+        #[location = 7] fill_end: Norm<[u8; 4]>,
+        #[location = 8] border_color: Norm<[u8; 4]>,
+        #[location = 9] shadow_color: Norm<[u8; 4]>,
+    }
+}
+
+/// Rounded boxes with borders, shadows and gradients, each drawn from a
+/// single instanced quad
+pub struct Boxes {
+    program: Program,
+    mesh: Mesh<[f32; 2], Instance>,
+    instances: Vec<Instance>,
+}
+
+impl Default for Boxes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Boxes {
+    pub fn new() -> Self {
+        Self {
+            program: Program::new(SDF_BOX_VERT, SDF_BOX_FRAG),
+            mesh: Mesh::instanced(
+                &[[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]],
+                &[],
+                Usage::StreamDraw,
+                Topology::TriStrip,
+            ),
+            instances: Vec::new(),
+        }
+    }
+
+    /// Queue a box spanning `min` to `max` in pixels, its shadow drawn
+    /// outside of that
+    pub fn push(&mut self, [x0, y0]: [f32; 2], [x1, y1]: [f32; 2], style: &BoxStyle) {
+        let (kind, gradient, [fill_start, fill_end]) = match style.fill {
+            Fill::Solid(color) => (0.0, [0.0; 4], [color; 2]),
+            Fill::Linear { start, end, colors } => {
+                (1.0, [start[0], start[1], end[0], end[1]], colors)
+            }
+            Fill::Radial {
+                center,
+                radius,
+                colors,
+            } => (2.0, [center[0], center[1], radius, 0.0], colors),
+        };
+        let shadow = style.shadow.unwrap_or(Shadow {
+            offset: [0.0; 2],
+            blur: 0.0,
+            spread: 0.0,
+            color: [0; 4],
+        });
+
+        self.instances.push(Instance {
+            rect: [x0, y0, x1, y1],
+            radii: style.radii,
+            gradient,
+            shadow: [
+                shadow.offset[0],
+                shadow.offset[1],
+                shadow.blur,
+                shadow.spread,
+            ],
+            params: [style.border, kind],
+            fill_start: Norm(fill_start),
+            fill_end: Norm(fill_end),
+            border_color: Norm(style.border_color),
+            shadow_color: Norm(shadow.color),
+        });
+    }
+
+    /// Draw the queued boxes onto the bound framebuffer of `size` pixels
+    pub fn flush(&mut self, [w, h]: [i32; 2]) {
+        if self.instances.is_empty() {
+            return;
+        }
+
+        self.mesh.update_instances(&self.instances);
+        self.program.bind();
+        ortho([0.0, 0.0], [w as f32, h as f32]).bind(0);
+
+        let prev = RenderState::current();
+        RenderState {
+            blend: Some(Blend::ALPHA),
+            ..prev
+        }
+        .apply();
+        self.mesh.draw_instanced(self.instances.len());
+        prev.apply();
+
+        self.instances.clear();
+    }
+}
@@ -0,0 +1,11 @@
+#version 460
+in vec2 tex_coords;
+in vec4 color;
+
+out vec4 frag_color;
+
+uniform sampler2D tex;
+
+void main() {
+	frag_color = texture(tex, tex_coords) * color;
+}
@@ -0,0 +1,15 @@
+#version 460
+layout(location=0) in vec2 pos;
+layout(location=1) in vec2 tex;
+layout(location=2) in vec4 rgba;
+
+layout(location=0) uniform mat4 projection;
+
+out vec2 tex_coords;
+out vec4 color;
+
+void main() {
+	tex_coords = tex;
+	color = rgba;
+	gl_Position = projection * vec4(pos, 0.0, 1.0);
+}
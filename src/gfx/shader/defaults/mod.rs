@@ -9,6 +9,27 @@ pub const POS2D_TEX2D: &str = shader_src!("pos2d_tex2d.vert");
 pub const POS3D: &str = shader_src!("pos3d.vert");
 pub const POS2D_RGB: &str = shader_src!("pos2d_rgb.vert");
 pub const POS3D_RGB: &str = shader_src!("pos3d_rgb.vert");
+pub const DRAW2D_VERT: &str = shader_src!("draw2d.vert");
+pub const SDF_BOX_VERT: &str = shader_src!("sdf_box.vert");
+pub const PATH_VERT: &str = shader_src!("path.vert");
+pub const POST_VERT: &str = shader_src!("post.vert");
 pub const WHITE: &str = shader_src!("white.frag");
 pub const RGB: &str = shader_src!("rgb.frag");
 pub const TEX2D: &str = shader_src!("tex2d.frag");
+pub const DRAW2D_FRAG: &str = shader_src!("draw2d.frag");
+pub const SDF_BOX_FRAG: &str = shader_src!("sdf_box.frag");
+pub const PATH_FRAG: &str = shader_src!("path.frag");
+pub const POST_BLUR: &str = shader_src!("post_blur.frag");
+pub const POST_KAWASE_DOWN: &str = shader_src!("post_kawase_down.frag");
+pub const POST_KAWASE_UP: &str = shader_src!("post_kawase_up.frag");
+pub const POST_BRIGHT: &str = shader_src!("post_bright.frag");
+pub const POST_BLOOM: &str = shader_src!("post_bloom.frag");
+pub const POST_TONEMAP: &str = shader_src!("post_tonemap.frag");
+pub const POST_FXAA: &str = shader_src!("post_fxaa.frag");
+pub const POST_VIGNETTE: &str = shader_src!("post_vignette.frag");
+pub const POST_CHROMATIC: &str = shader_src!("post_chromatic.frag");
+pub const POST_CRT: &str = shader_src!("post_crt.frag");
+pub const POST_LUT: &str = shader_src!("post_lut.frag");
+pub const BEZIER_TESC: &str = shader_src!("bezier.tesc");
+pub const BEZIER_TESE: &str = shader_src!("bezier.tese");
+pub const WIREFRAME_GEOM: &str = shader_src!("wireframe.geom");
@@ -0,0 +1,8 @@
+#version 460
+layout(location=1) uniform vec4 color;
+
+out vec4 frag_color;
+
+void main() {
+	frag_color = color;
+}
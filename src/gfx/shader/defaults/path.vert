@@ -0,0 +1,8 @@
+#version 460
+layout(location=0) in vec2 pos;
+
+layout(location=0) uniform mat4 transform;
+
+void main() {
+	gl_Position = transform * vec4(pos, 0.0, 1.0);
+}
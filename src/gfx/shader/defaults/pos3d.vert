@@ -1,6 +1,8 @@
 #version 460
 layout(location=0) in vec3 pos;
 
+layout(location=0) uniform mat4 transform;
+
 void main() {
-	gl_Position = vec4(pos, 1.0);
+	gl_Position = transform * vec4(pos, 1.0);
 }
@@ -2,9 +2,11 @@
 layout(location=0) in vec3 pos;
 layout(location=1) in vec3 rgb;
 
+layout(location=0) uniform mat4 transform;
+
 layout(location=0) out vec3 color;
 
 void main() {
-	gl_Position = vec4(pos, 1.0);
+	gl_Position = transform * vec4(pos, 1.0);
 	color = rgb;
 }
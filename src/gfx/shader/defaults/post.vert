@@ -0,0 +1,9 @@
+#version 460
+layout(location=0) in vec2 pos;
+
+out vec2 tex_coords;
+
+void main() {
+	tex_coords = pos * 0.5 + 0.5;
+	gl_Position = vec4(pos, 0.0, 1.0);
+}
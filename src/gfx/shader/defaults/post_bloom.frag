@@ -0,0 +1,13 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+layout(binding=1) uniform sampler2D bloom;
+layout(location=1) uniform float intensity;
+
+void main() {
+	vec4 color = texture(tex, tex_coords);
+	frag_color = vec4(color.rgb + texture(bloom, tex_coords).rgb * intensity, color.a);
+}
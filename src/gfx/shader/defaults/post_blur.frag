@@ -0,0 +1,25 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+// One texel along the blur direction
+layout(location=0) uniform vec2 dir;
+layout(location=1) uniform float radius;
+
+void main() {
+	// Radius covers three standard deviations
+	float sigma = max(radius / 3.0, 0.001);
+	int taps = min(int(radius + 0.5), 64);
+
+	vec4 sum = texture(tex, tex_coords);
+	float total = 1.0;
+	for (int i = 1; i <= taps; i++) {
+		float weight = exp(-float(i * i) / (2.0 * sigma * sigma));
+		sum += weight * (texture(tex, tex_coords + dir * float(i)) + texture(tex, tex_coords - dir * float(i)));
+		total += 2.0 * weight;
+	}
+
+	frag_color = sum / total;
+}
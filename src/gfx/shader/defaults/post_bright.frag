@@ -0,0 +1,20 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+layout(location=1) uniform float threshold;
+
+void main() {
+	vec3 color = texture(tex, tex_coords).rgb;
+	float brightness = max(color.r, max(color.g, color.b));
+
+	// Soft knee of half the threshold, so bloom fades in instead of popping
+	float knee = threshold * 0.5;
+	float soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+	soft = soft * soft / (4.0 * knee + 0.0001);
+	float weight = max(soft, brightness - threshold) / max(brightness, 0.0001);
+
+	frag_color = vec4(color * weight, 1.0);
+}
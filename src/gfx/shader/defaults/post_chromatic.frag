@@ -0,0 +1,21 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+layout(location=0) uniform vec2 texel;
+// Shift of red and blue at the corners, in pixels
+layout(location=1) uniform float strength;
+
+void main() {
+	vec2 offset = (tex_coords - 0.5) * 2.0 * strength * texel;
+	vec4 color = texture(tex, tex_coords);
+
+	frag_color = vec4(
+		texture(tex, tex_coords + offset).r,
+		color.g,
+		texture(tex, tex_coords - offset).b,
+		color.a
+	);
+}
@@ -0,0 +1,33 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+layout(location=0) uniform vec2 texel;
+// curvature, scanline strength, mask strength
+layout(location=1) uniform vec4 params;
+
+void main() {
+	// Bulge the screen outwards, blacking out what falls off it
+	vec2 uv = tex_coords * 2.0 - 1.0;
+	uv *= 1.0 + params.x * dot(uv.yx, uv.yx);
+	uv = uv * 0.5 + 0.5;
+	if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+		frag_color = vec4(0.0, 0.0, 0.0, 1.0);
+		return;
+	}
+
+	vec4 color = texture(tex, uv);
+	vec2 pixel = uv / texel;
+
+	float scanline = 0.5 + 0.5 * sin(pixel.y * 3.14159265);
+	color.rgb *= mix(1.0, scanline, params.y);
+
+	// Aperture grille, every third column favouring one primary
+	int column = int(mod(gl_FragCoord.x, 3.0));
+	vec3 mask = vec3(column == 0, column == 1, column == 2);
+	color.rgb *= mix(vec3(1.0), mask * 1.5 + 0.25, params.z);
+
+	frag_color = color;
+}
@@ -0,0 +1,43 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+layout(location=0) uniform vec2 texel;
+
+const float SPAN_MAX = 8.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float REDUCE_MIN = 1.0 / 128.0;
+
+float luma(vec3 color) {
+	return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+	float nw = luma(texture(tex, tex_coords + vec2(-1.0, 1.0) * texel).rgb);
+	float ne = luma(texture(tex, tex_coords + vec2(1.0, 1.0) * texel).rgb);
+	float sw = luma(texture(tex, tex_coords + vec2(-1.0, -1.0) * texel).rgb);
+	float se = luma(texture(tex, tex_coords + vec2(1.0, -1.0) * texel).rgb);
+	vec4 center = texture(tex, tex_coords);
+	float m = luma(center.rgb);
+
+	float lo = min(m, min(min(nw, ne), min(sw, se)));
+	float hi = max(m, max(max(nw, ne), max(sw, se)));
+
+	// Blur along the edge, perpendicular to the luma gradient. Texture
+	// coordinates are y-up, unlike the original FXAA's.
+	vec2 dir = vec2(-((nw + ne) - (sw + se)), (ne + se) - (nw + sw));
+	float reduce = max((nw + ne + sw + se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+	float scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+	dir = clamp(dir * scale, -SPAN_MAX, SPAN_MAX) * texel;
+
+	vec3 a = 0.5 * (texture(tex, tex_coords + dir * (1.0 / 3.0 - 0.5)).rgb
+		+ texture(tex, tex_coords + dir * (2.0 / 3.0 - 0.5)).rgb);
+	vec3 b = a * 0.5 + 0.25 * (texture(tex, tex_coords - dir * 0.5).rgb
+		+ texture(tex, tex_coords + dir * 0.5).rgb);
+
+	// The wider blur went past the edge if it left the local luma range
+	float lb = luma(b);
+	frag_color = vec4(lb < lo || lb > hi ? a : b, center.a);
+}
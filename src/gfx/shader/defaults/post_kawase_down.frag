@@ -0,0 +1,18 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+// Half a texel of the source
+layout(location=0) uniform vec2 half_texel;
+
+void main() {
+	vec4 sum = texture(tex, tex_coords) * 4.0;
+	sum += texture(tex, tex_coords - half_texel);
+	sum += texture(tex, tex_coords + half_texel);
+	sum += texture(tex, tex_coords + vec2(half_texel.x, -half_texel.y));
+	sum += texture(tex, tex_coords - vec2(half_texel.x, -half_texel.y));
+
+	frag_color = sum / 8.0;
+}
@@ -0,0 +1,22 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+// Half a texel of the source
+layout(location=0) uniform vec2 half_texel;
+
+void main() {
+	vec2 o = half_texel;
+	vec4 sum = texture(tex, tex_coords + vec2(-o.x * 2.0, 0.0));
+	sum += texture(tex, tex_coords + vec2(-o.x, o.y)) * 2.0;
+	sum += texture(tex, tex_coords + vec2(0.0, o.y * 2.0));
+	sum += texture(tex, tex_coords + vec2(o.x, o.y)) * 2.0;
+	sum += texture(tex, tex_coords + vec2(o.x * 2.0, 0.0));
+	sum += texture(tex, tex_coords + vec2(o.x, -o.y)) * 2.0;
+	sum += texture(tex, tex_coords + vec2(0.0, -o.y * 2.0));
+	sum += texture(tex, tex_coords + vec2(-o.x, -o.y)) * 2.0;
+
+	frag_color = sum / 12.0;
+}
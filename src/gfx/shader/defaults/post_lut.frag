@@ -0,0 +1,29 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+// n slices of n x n laid out left to right, red along x, green along y and
+// blue across slices
+layout(binding=1) uniform sampler2D lut;
+layout(location=1) uniform float strength;
+
+vec3 grade(vec3 color) {
+	float n = float(textureSize(lut, 0).y);
+	color = clamp(color, 0.0, 1.0);
+
+	// Sample texel centres, blending the two nearest blue slices
+	float slice = color.b * (n - 1.0);
+	float lower = floor(slice);
+	vec2 uv = (color.rg * (n - 1.0) + 0.5) / vec2(n * n, n);
+	vec3 a = texture(lut, uv + vec2(lower / n, 0.0)).rgb;
+	vec3 b = texture(lut, uv + vec2(min(lower + 1.0, n - 1.0) / n, 0.0)).rgb;
+
+	return mix(a, b, slice - lower);
+}
+
+void main() {
+	vec4 color = texture(tex, tex_coords);
+	frag_color = vec4(mix(color.rgb, grade(color.rgb), strength), color.a);
+}
@@ -0,0 +1,31 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+layout(location=1) uniform float exposure;
+// 0 Reinhard, 1 ACES, 2 Uncharted 2
+layout(location=2) uniform int curve;
+
+vec3 uncharted2(vec3 x) {
+	const float a = 0.15, b = 0.50, c = 0.10, d = 0.20, e = 0.02, f = 0.30;
+	return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
+
+void main() {
+	vec4 color = texture(tex, tex_coords);
+	vec3 x = color.rgb * exposure;
+
+	vec3 mapped;
+	if (curve == 0) {
+		mapped = x / (1.0 + x);
+	} else if (curve == 1) {
+		// Narkowicz's fit of the ACES filmic curve
+		mapped = clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+	} else {
+		mapped = uncharted2(x * 2.0) / uncharted2(vec3(11.2));
+	}
+
+	frag_color = vec4(mapped, color.a);
+}
@@ -0,0 +1,16 @@
+#version 460
+in vec2 tex_coords;
+
+out vec4 frag_color;
+
+layout(binding=0) uniform sampler2D tex;
+// radius, softness, strength, aspect ratio
+layout(location=1) uniform vec4 params;
+
+void main() {
+	vec4 color = texture(tex, tex_coords);
+	vec2 d = (tex_coords - 0.5) * vec2(params.w, 1.0);
+	float shade = 1.0 - smoothstep(params.x - params.y, params.x, length(d));
+
+	frag_color = vec4(color.rgb * mix(1.0, shade, params.z), color.a);
+}
@@ -0,0 +1,66 @@
+#version 460
+in vec2 pos;
+flat in vec4 box_rect;
+flat in vec4 box_radii;
+flat in vec4 box_gradient;
+flat in vec4 box_shadow;
+flat in vec2 box_params;
+flat in vec4 box_fill_start;
+flat in vec4 box_fill_end;
+flat in vec4 box_border_color;
+flat in vec4 box_shadow_color;
+
+out vec4 frag_color;
+
+const uint SOLID = 0u;
+const uint LINEAR = 1u;
+const uint RADIAL = 2u;
+
+// Distance to a box of half extents `b` centred on the origin, with corner
+// radii `r` counter-clockwise from the bottom left
+float rounded_box(vec2 p, vec2 b, vec4 r) {
+	float radius = p.x > 0.0 ? (p.y > 0.0 ? r.z : r.y) : (p.y > 0.0 ? r.w : r.x);
+	radius = min(radius, min(b.x, b.y));
+	vec2 q = abs(p) - b + radius;
+
+	return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - radius;
+}
+
+void main() {
+	vec2 center = (box_rect.xy + box_rect.zw) * 0.5;
+	vec2 half_size = (box_rect.zw - box_rect.xy) * 0.5;
+	float dist = rounded_box(pos - center, half_size, box_radii);
+	float aa = max(fwidth(dist), 1e-4);
+
+	// Gradient coordinates are fractions of the box size
+	vec2 uv = (pos - box_rect.xy) / max(box_rect.zw - box_rect.xy, vec2(1e-4));
+	float t = 0.0;
+	switch (uint(box_params.y)) {
+	case LINEAR: {
+		vec2 dir = box_gradient.zw - box_gradient.xy;
+		t = dot(uv - box_gradient.xy, dir) / max(dot(dir, dir), 1e-8);
+		break;
+	}
+	case RADIAL:
+		t = length(uv - box_gradient.xy) / max(box_gradient.z, 1e-4);
+		break;
+	}
+	vec4 color = mix(box_fill_start, box_fill_end, clamp(t, 0.0, 1.0));
+
+	float border = box_params.x;
+	if (border > 0.0) {
+		color = mix(color, box_border_color, smoothstep(-border - aa * 0.5, -border + aa * 0.5, dist));
+	}
+	color.a *= 1.0 - smoothstep(-aa * 0.5, aa * 0.5, dist);
+
+	float spread = box_shadow.w;
+	float blur = max(box_shadow.z, aa);
+	float shadow_dist = rounded_box(pos - center - box_shadow.xy, half_size + spread, box_radii + spread);
+	vec4 shadow = box_shadow_color;
+	shadow.a *= 1.0 - smoothstep(-blur * 0.5, blur * 0.5, shadow_dist);
+
+	// Box over its shadow, with straight alpha
+	float alpha = color.a + shadow.a * (1.0 - color.a);
+	vec3 rgb = (color.rgb * color.a + shadow.rgb * shadow.a * (1.0 - color.a)) / max(alpha, 1e-4);
+	frag_color = vec4(rgb, alpha);
+}
@@ -0,0 +1,42 @@
+#version 460
+layout(location=0) in vec2 corner;
+layout(location=1) in vec4 rect;
+layout(location=2) in vec4 radii;
+layout(location=3) in vec4 gradient;
+layout(location=4) in vec4 shadow;
+layout(location=5) in vec2 params;
+layout(location=6) in vec4 fill_start;
+layout(location=7) in vec4 fill_end;
+layout(location=8) in vec4 border_color;
+layout(location=9) in vec4 shadow_color;
+
+layout(location=0) uniform mat4 projection;
+
+out vec2 pos;
+flat out vec4 box_rect;
+flat out vec4 box_radii;
+flat out vec4 box_gradient;
+flat out vec4 box_shadow;
+flat out vec2 box_params;
+flat out vec4 box_fill_start;
+flat out vec4 box_fill_end;
+flat out vec4 box_border_color;
+flat out vec4 box_shadow_color;
+
+void main() {
+	// Grow the quad to cover the shadow and a pixel of anti-aliasing
+	float margin = max(abs(shadow.x), abs(shadow.y)) + shadow.z + max(shadow.w, 0.0) + 1.0;
+	pos = mix(rect.xy - margin, rect.zw + margin, corner);
+
+	box_rect = rect;
+	box_radii = radii;
+	box_gradient = gradient;
+	box_shadow = shadow;
+	box_params = params;
+	box_fill_start = fill_start;
+	box_fill_end = fill_end;
+	box_border_color = border_color;
+	box_shadow_color = shadow_color;
+
+	gl_Position = projection * vec4(pos, 0.0, 1.0);
+}
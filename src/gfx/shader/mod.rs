@@ -6,9 +6,9 @@ pub use defaults::*;
 pub struct Shader(GLuint);
 
 impl Shader {
-    pub fn new(source: &str, stage: GLenum) -> Shader {
+    pub fn new(source: &str, stage: Stage) -> Shader {
         unsafe {
-            let shader = glCreateShader(stage);
+            let shader = glCreateShader(stage as _);
             glShaderSource(shader, 1, &(source.as_ptr() as _), core::ptr::null());
             glCompileShader(shader);
 
@@ -30,3 +30,14 @@ impl Drop for Shader {
         }
     }
 }
+
+#[repr(u32)]
+#[derive(Clone, Copy)]
+pub enum Stage {
+    Vertex = GL_VERTEX_SHADER,
+    TessControl = GL_TESS_CONTROL_SHADER,
+    TessEvaluation = GL_TESS_EVALUATION_SHADER,
+    Geometry = GL_GEOMETRY_SHADER,
+    Fragment = GL_FRAGMENT_SHADER,
+    Compute = GL_COMPUTE_SHADER,
+}
@@ -0,0 +1,129 @@
+pub mod sheet;
+
+use crate::{
+    gfx::{
+        draw2d::Draw2d,
+        texture::{Format, Texture},
+    },
+    math::{cos, sin},
+    mem::vec::Vec,
+};
+
+/// Textured quad placed in pixel coordinates, drawn through `Draw2d`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite {
+    /// Bottom left and top right texture coordinates shown
+    pub uv: [[f32; 2]; 2],
+    /// Size in pixels before scaling
+    pub size: [f32; 2],
+    /// Where `origin` ends up
+    pub position: [f32; 2],
+    /// Point rotated and scaled about, as a fraction of the size from the
+    /// bottom left
+    pub origin: [f32; 2],
+    /// Counter-clockwise, in radians
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    pub tint: [u8; 4],
+    /// Mirror horizontally and vertically
+    pub flip: [bool; 2],
+    /// Sprites with greater depth are further away, drawn under those in
+    /// front by `SpriteBatch`
+    pub depth: f32,
+}
+
+impl Sprite {
+    pub fn new(uv: [[f32; 2]; 2], size: [f32; 2]) -> Self {
+        Self {
+            uv,
+            size,
+            position: [0.0; 2],
+            origin: [0.5; 2],
+            rotation: 0.0,
+            scale: [1.0; 2],
+            tint: [0xFF; 4],
+            flip: [false; 2],
+            depth: 0.0,
+        }
+    }
+
+    /// Sprite showing all of `tex` at its size
+    pub fn whole<F: Format>(tex: &Texture<F>) -> Self {
+        let [w, h] = tex.size();
+        Self::new([[0.0; 2], [1.0; 2]], [w as f32, h as f32])
+    }
+
+    /// Corners in pixels, counter-clockwise from the bottom left
+    pub fn corners(&self) -> [[f32; 2]; 4] {
+        let [w, h] = [self.size[0] * self.scale[0], self.size[1] * self.scale[1]];
+        let [ox, oy] = [self.origin[0] * w, self.origin[1] * h];
+        let [s, c] = [sin(self.rotation), cos(self.rotation)];
+        let [px, py] = self.position;
+
+        [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]].map(|[x, y]| {
+            let [x, y] = [x - ox, y - oy];
+            [px + x * c - y * s, py + x * s + y * c]
+        })
+    }
+
+    /// Texture coordinates of each corner, flipped as needed
+    pub fn uvs(&self) -> [[f32; 2]; 4] {
+        let [[mut u0, mut v0], [mut u1, mut v1]] = self.uv;
+        if self.flip[0] {
+            core::mem::swap(&mut u0, &mut u1);
+        }
+        if self.flip[1] {
+            core::mem::swap(&mut v0, &mut v1);
+        }
+
+        [[u0, v0], [u1, v0], [u1, v1], [u0, v1]]
+    }
+
+    /// Queue the sprite on `draw` showing part of `tex`
+    pub fn draw<F: Format>(&self, tex: &Texture<F>, draw: &mut Draw2d) {
+        draw.quad(tex, self.corners(), self.uvs(), self.tint);
+    }
+}
+
+/// Sprites collected over a frame and drawn back to front by depth, those
+/// at equal depth in the order they were pushed
+pub struct SpriteBatch<'a, F: Format> {
+    sprites: Vec<(&'a Texture<F>, Sprite)>,
+}
+
+impl<F: Format> Default for SpriteBatch<'_, F> {
+    fn default() -> Self {
+        Self {
+            sprites: Vec::new(),
+        }
+    }
+}
+
+impl<'a, F: Format> SpriteBatch<'a, F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, tex: &'a Texture<F>, sprite: Sprite) {
+        self.sprites.push((tex, sprite));
+    }
+
+    /// Queue every sprite on `draw`, emptying the batch. Neighbours sharing
+    /// a texture end up in the same draw call, so atlases keep the count low.
+    pub fn draw(&mut self, draw: &mut Draw2d) {
+        self.sprites
+            .sort_by(|(_, a), (_, b)| b.depth.total_cmp(&a.depth));
+
+        for (tex, sprite) in self.sprites.drain(..) {
+            sprite.draw(tex, draw);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+}
@@ -0,0 +1,431 @@
+use {
+    super::Sprite,
+    crate::{
+        gfx::{
+            atlas::Region,
+            model::json::{ParseError, Value},
+        },
+        mem::{collections::BTreeMap, string::String, vec::Vec},
+    },
+    core::fmt,
+};
+
+/// Duration of frames that don't specify one, in seconds
+const FRAME_TIME: f32 = 0.1;
+
+/// Image of a sheet, in pixels from the bottom left of the sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub name: String,
+    /// `[x, y, width, height]`
+    pub rect: [i32; 4],
+    /// Seconds shown for when animated
+    pub duration: f32,
+}
+
+/// Image split into frames, with named animations over them
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteSheet {
+    pub size: [i32; 2],
+    pub frames: Vec<Frame>,
+    pub animations: BTreeMap<String, Animation>,
+    area: Region,
+}
+
+impl SpriteSheet {
+    /// Cells of `cell` pixels, left to right and top to bottom, after a
+    /// `margin` around the image and with `spacing` between cells
+    pub fn grid(size: [i32; 2], cell: [i32; 2], margin: i32, spacing: i32) -> Self {
+        assert!(
+            cell[0] > 0 && cell[1] > 0 && spacing >= 0,
+            "grid cells must have a positive size and non-negative spacing"
+        );
+        let count = |axis: usize| (size[axis] - 2 * margin + spacing) / (cell[axis] + spacing);
+        let [columns, rows] = [count(0), count(1)];
+
+        let frames = (0..rows)
+            .flat_map(|row| (0..columns).map(move |col| (row, col)))
+            .map(|(row, col)| Frame {
+                name: String::new(),
+                rect: [
+                    margin + col * (cell[0] + spacing),
+                    size[1] - margin - row * (cell[1] + spacing) - cell[1],
+                    cell[0],
+                    cell[1],
+                ],
+                duration: FRAME_TIME,
+            })
+            .collect();
+
+        Self::new(size, frames)
+    }
+
+    /// Sheet from the JSON exported by Aseprite or TexturePacker, with
+    /// frames as a hash or an array. Aseprite frame tags become animations.
+    pub fn from_json(json: &[u8]) -> Result<Self, SheetError> {
+        let root = Value::parse(json)?;
+        let meta = root.get("meta");
+        let size = [meta.get("size").get("w"), meta.get("size").get("h")]
+            .map(|n| n.f32().map(|n| n as i32));
+        let size = match size {
+            [Some(w), Some(h)] => [w, h],
+            _ => return Err(SheetError::Missing("meta.size")),
+        };
+
+        let entries: Vec<(&str, &Value)> = match root.get("frames") {
+            Value::Object(frames) => frames
+                .iter()
+                .map(|(name, frame)| (name.as_str(), frame))
+                .collect(),
+            frames => frames
+                .items()
+                .iter()
+                .map(|frame| (frame.get("filename").str().unwrap_or(""), frame))
+                .collect(),
+        };
+
+        let frames = entries
+            .into_iter()
+            .map(|(name, frame)| {
+                if frame.get("rotated").bool() == Some(true) {
+                    log::warn!("rotated frame '{}' will be drawn sideways", name);
+                }
+
+                let rect = frame.get("frame");
+                let [x, y, w, h] = ["x", "y", "w", "h"].map(|key| rect.get(key).f32());
+                match (x, y, w, h) {
+                    (Some(x), Some(y), Some(w), Some(h)) => Ok(Frame {
+                        name: name.into(),
+                        // Flip from y down to up
+                        rect: [x as i32, size[1] - (y + h) as i32, w as i32, h as i32],
+                        duration: frame
+                            .get("duration")
+                            .f32()
+                            .map_or(FRAME_TIME, |ms| ms / 1000.0),
+                    }),
+                    _ => Err(SheetError::Missing("frame")),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut sheet = Self::new(size, frames);
+        for tag in meta.get("frameTags").items() {
+            let (name, from, to) = match (
+                tag.get("name").str(),
+                tag.get("from").usize(),
+                tag.get("to").usize(),
+            ) {
+                (Some(name), Some(from), Some(to)) if from <= to && to < sheet.frames.len() => {
+                    (name, from, to)
+                }
+                _ => return Err(SheetError::Missing("frameTags")),
+            };
+
+            let mut frames: Vec<_> = (from..=to).collect();
+            let playback = match tag.get("direction").str() {
+                Some("pingpong") => Playback::PingPong,
+                Some("reverse") => {
+                    frames.reverse();
+                    Playback::Loop
+                }
+                _ => Playback::Loop,
+            };
+
+            sheet
+                .animations
+                .insert(name.into(), Animation { frames, playback });
+        }
+
+        Ok(sheet)
+    }
+
+    fn new(size: [i32; 2], frames: Vec<Frame>) -> Self {
+        Self {
+            size,
+            frames,
+            animations: BTreeMap::new(),
+            area: Region {
+                rect: [0, 0, size[0], size[1]],
+                uv: [[0.0; 2], [1.0; 2]],
+            },
+        }
+    }
+
+    /// Where the sheet's image is in the texture drawn from, e.g. an
+    /// `Atlas` region. By default the image is the whole texture.
+    pub fn place(&mut self, area: Region) {
+        self.area = area;
+    }
+
+    pub fn frame_named(&self, name: &str) -> Option<usize> {
+        self.frames.iter().position(|frame| frame.name == name)
+    }
+
+    /// Texture coordinates of frame `idx`
+    pub fn uv(&self, idx: usize) -> [[f32; 2]; 2] {
+        let [x, y, w, h] = self.frames[idx].rect;
+        let [[u0, v0], [u1, v1]] = self.area.uv;
+        let map = |px: i32, axis: usize, start: f32, end: f32| {
+            start + px as f32 / self.size[axis] as f32 * (end - start)
+        };
+
+        [
+            [map(x, 0, u0, u1), map(y, 1, v0, v1)],
+            [map(x + w, 0, u0, u1), map(y + h, 1, v0, v1)],
+        ]
+    }
+
+    /// Sprite showing frame `idx` at its size
+    pub fn sprite(&self, idx: usize) -> Sprite {
+        let [.., w, h] = self.frames[idx].rect;
+        Sprite::new(self.uv(idx), [w as f32, h as f32])
+    }
+
+    /// Show frame `idx` on `sprite`, keeping everything else
+    pub fn show(&self, sprite: &mut Sprite, idx: usize) {
+        let [.., w, h] = self.frames[idx].rect;
+        sprite.uv = self.uv(idx);
+        sprite.size = [w as f32, h as f32];
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Playback {
+    /// Stop on the last frame
+    Once,
+    Loop,
+    /// Play forwards then backwards, repeating
+    PingPong,
+}
+
+/// Non-empty sequence of sheet frames, each shown for its `Frame::duration`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    frames: Vec<usize>,
+    pub playback: Playback,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<usize>, playback: Playback) -> Self {
+        assert!(!frames.is_empty(), "animation without frames");
+        Self { frames, playback }
+    }
+
+    pub fn frames(&self) -> &[usize] {
+        &self.frames
+    }
+
+    /// Seconds until the animation ends or repeats
+    pub fn duration(&self, sheet: &SpriteSheet) -> f32 {
+        (0..self.cycle_len())
+            .map(|step| sheet.frames[self.step(step)].duration)
+            .sum()
+    }
+
+    /// Sheet frame shown `time` seconds in
+    pub fn frame_at(&self, sheet: &SpriteSheet, time: f32) -> usize {
+        let total = self.duration(sheet);
+        if total <= 0.0 || self.is_finished(sheet, time) {
+            return self.step(self.cycle_len() - 1);
+        }
+
+        let mut time = time.max(0.0) % total;
+        for step in 0..self.cycle_len() {
+            let frame = self.step(step);
+            time -= sheet.frames[frame].duration;
+            if time < 0.0 {
+                return frame;
+            }
+        }
+
+        self.step(self.cycle_len() - 1)
+    }
+
+    /// Whether a `Playback::Once` animation has reached its end
+    pub fn is_finished(&self, sheet: &SpriteSheet, time: f32) -> bool {
+        self.playback == Playback::Once && time >= self.duration(sheet)
+    }
+
+    /// Frames in one cycle, ping-pong not repeating the ends
+    fn cycle_len(&self) -> usize {
+        let n = self.frames.len();
+        match self.playback {
+            Playback::PingPong if n > 2 => 2 * n - 2,
+            _ => n,
+        }
+    }
+
+    fn step(&self, step: usize) -> usize {
+        let n = self.frames.len();
+        self.frames[if step < n { step } else { 2 * n - 2 - step }]
+    }
+}
+
+/// Playback position of an `Animation`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Animator {
+    /// Seconds since the start
+    pub time: f32,
+    /// Multiplier on the time passed to `update`
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl Default for Animator {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl Animator {
+    pub fn update(&mut self, dt: f32) {
+        if !self.paused {
+            self.time += dt * self.speed;
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.time = 0.0;
+    }
+
+    /// Advance by `dt` seconds and show the current frame of `animation`
+    /// on `sprite`
+    pub fn animate(
+        &mut self,
+        dt: f32,
+        animation: &Animation,
+        sheet: &SpriteSheet,
+        sprite: &mut Sprite,
+    ) {
+        self.update(dt);
+        sheet.show(sprite, animation.frame_at(sheet, self.time));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SheetError {
+    /// Malformed JSON at the byte offset
+    Json(usize),
+    /// Missing or invalid field
+    Missing(&'static str),
+}
+
+impl From<ParseError> for SheetError {
+    fn from(ParseError(pos): ParseError) -> Self {
+        SheetError::Json(pos)
+    }
+}
+
+impl fmt::Display for SheetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SheetError::Json(pos) => write!(f, "malformed JSON at byte {}", pos),
+            SheetError::Missing(field) => write!(f, "missing or invalid '{}'", field),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::mem::vec};
+
+    #[test]
+    fn grid() {
+        let sheet = SpriteSheet::grid([38, 22], [8, 8], 2, 2);
+        let rects = sheet
+            .frames
+            .iter()
+            .map(|frame| frame.rect)
+            .collect::<Vec<_>>();
+        // Rows top to bottom, in pixels from the bottom left
+        assert_eq!(
+            rects,
+            [
+                [2, 12, 8, 8],
+                [12, 12, 8, 8],
+                [22, 12, 8, 8],
+                [2, 2, 8, 8],
+                [12, 2, 8, 8],
+                [22, 2, 8, 8],
+            ]
+        );
+        assert_eq!(
+            sheet.uv(0),
+            [[2.0 / 38.0, 12.0 / 22.0], [10.0 / 38.0, 20.0 / 22.0]]
+        );
+    }
+
+    const JSON: &[u8] = br#"{
+        "frames": {
+            "a": {"frame": {"x": 0, "y": 0, "w": 4, "h": 2}, "duration": 100},
+            "b": {"frame": {"x": 4, "y": 0, "w": 4, "h": 2}, "duration": 200},
+            "c": {"frame": {"x": 0, "y": 2, "w": 4, "h": 2}}
+        },
+        "meta": {
+            "size": {"w": 8, "h": 4},
+            "frameTags": [
+                {"name": "bounce", "from": 0, "to": 2, "direction": "pingpong"},
+                {"name": "back", "from": 1, "to": 2, "direction": "reverse"}
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn from_json() {
+        let sheet = SpriteSheet::from_json(JSON).unwrap();
+        assert_eq!(sheet.frame_named("b"), Some(1));
+        assert_eq!(sheet.frames[0].rect, [0, 2, 4, 2]);
+        assert_eq!(sheet.frames[2].rect, [0, 0, 4, 2]);
+        assert_eq!(sheet.frames[1].duration, 0.2);
+        assert_eq!(sheet.frames[2].duration, FRAME_TIME);
+
+        let bounce = &sheet.animations["bounce"];
+        assert_eq!(
+            (bounce.frames(), bounce.playback),
+            (&[0, 1, 2][..], Playback::PingPong)
+        );
+        let back = &sheet.animations["back"];
+        assert_eq!(
+            (back.frames(), back.playback),
+            (&[2, 1][..], Playback::Loop)
+        );
+
+        let json = core::str::from_utf8(JSON)
+            .unwrap()
+            .replace(r#""to": 2"#, r#""to": 3"#);
+        assert_eq!(
+            SpriteSheet::from_json(json.as_bytes()),
+            Err(SheetError::Missing("frameTags"))
+        );
+    }
+
+    #[test]
+    fn playback() {
+        let sheet = SpriteSheet::from_json(JSON).unwrap();
+        let frames_at = |animation: &Animation, times: &[f32]| {
+            times
+                .iter()
+                .map(|&time| animation.frame_at(&sheet, time))
+                .collect::<Vec<_>>()
+        };
+        let times = [0.0, 0.05, 0.15, 0.35, 0.45, 0.65, 0.75, 0.95];
+
+        // Frames last 0.1, 0.2 and 0.1 seconds
+        let looped = Animation::new(vec![0, 1, 2], Playback::Loop);
+        assert!((looped.duration(&sheet) - 0.4).abs() < 1e-6);
+        assert_eq!(frames_at(&looped, &times), [0, 0, 1, 2, 0, 1, 2, 1]);
+
+        let once = Animation::new(vec![0, 1, 2], Playback::Once);
+        assert_eq!(frames_at(&once, &times), [0, 0, 1, 2, 2, 2, 2, 2]);
+        assert!(!once.is_finished(&sheet, 0.35) && once.is_finished(&sheet, 0.45));
+
+        // Back through the middle frame without repeating the ends
+        let ping_pong = Animation::new(vec![0, 1, 2], Playback::PingPong);
+        assert!((ping_pong.duration(&sheet) - 0.6).abs() < 1e-6);
+        assert_eq!(frames_at(&ping_pong, &times), [0, 0, 1, 2, 1, 0, 1, 2]);
+    }
+}
@@ -0,0 +1,311 @@
+use {crate::gfx::*, core::ptr::addr_of_mut};
+
+/// Fixed-function state used for drawing. Applying it only issues the GL
+/// calls for what differs from the last applied state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderState {
+    pub blend: Option<Blend>,
+    /// Depth test comparison, `None` to disable the test
+    pub depth: Option<Compare>,
+    pub depth_write: bool,
+    /// Faces to discard, `None` to draw both
+    pub cull: Option<Face>,
+    pub front_face: Winding,
+    /// `[x, y, w, h]` rectangle outside of which nothing is drawn
+    pub scissor: Option<[i32; 4]>,
+    pub stencil: Option<Stencil>,
+    pub color_mask: [bool; 4],
+    pub polygon_mode: PolygonMode,
+    pub line_width: f32,
+}
+
+impl Default for RenderState {
+    /// The initial state of a GL context
+    fn default() -> Self {
+        Self {
+            blend: None,
+            depth: None,
+            depth_write: true,
+            cull: None,
+            front_face: Winding::Ccw,
+            scissor: None,
+            stencil: None,
+            color_mask: [true; 4],
+            polygon_mode: PolygonMode::Fill,
+            line_width: 1.0,
+        }
+    }
+}
+
+/// Last applied state, `None` when the context state is unknown. GL contexts
+/// are only ever used from a single thread.
+static mut CACHE: Option<RenderState> = None;
+
+impl RenderState {
+    /// The last applied state
+    pub fn current() -> Self {
+        unsafe { (*addr_of_mut!(CACHE)).unwrap_or_default() }
+    }
+
+    /// Forget the cached state so the next `apply` sets everything, needed
+    /// after a new context is made current or GL is called directly
+    pub fn invalidate() {
+        unsafe {
+            *addr_of_mut!(CACHE) = None;
+        }
+    }
+
+    pub fn apply(&self) {
+        let prev = unsafe { (*addr_of_mut!(CACHE)).replace(*self) };
+        macro_rules! changed {
+            ($field:ident) => {
+                !matches!(prev, Some(prev) if prev.$field == self.$field)
+            };
+        }
+
+        unsafe {
+            if changed!(blend) {
+                toggle(GL_BLEND, self.blend.is_some());
+                if let Some(blend) = self.blend {
+                    glBlendFuncSeparate(
+                        blend.src as _,
+                        blend.dst as _,
+                        blend.src_alpha as _,
+                        blend.dst_alpha as _,
+                    );
+                    glBlendEquationSeparate(blend.equation as _, blend.equation_alpha as _);
+                }
+            }
+
+            if changed!(depth) {
+                toggle(GL_DEPTH_TEST, self.depth.is_some());
+                if let Some(func) = self.depth {
+                    glDepthFunc(func as _);
+                }
+            }
+
+            if changed!(depth_write) {
+                glDepthMask(self.depth_write as _);
+            }
+
+            if changed!(cull) {
+                toggle(GL_CULL_FACE, self.cull.is_some());
+                if let Some(face) = self.cull {
+                    glCullFace(face as _);
+                }
+            }
+
+            if changed!(front_face) {
+                glFrontFace(self.front_face as _);
+            }
+
+            if changed!(scissor) {
+                toggle(GL_SCISSOR_TEST, self.scissor.is_some());
+                if let Some([x, y, w, h]) = self.scissor {
+                    glScissor(x, y, w, h);
+                }
+            }
+
+            if changed!(stencil) {
+                toggle(GL_STENCIL_TEST, self.stencil.is_some());
+                // The write mask also applies to clears with the test disabled
+                let stencil = self.stencil.unwrap_or_default();
+                glStencilFunc(stencil.func as _, stencil.reference, stencil.read_mask);
+                glStencilMask(stencil.write_mask);
+                for (face, [fail, depth_fail, pass]) in
+                    [(GL_FRONT, stencil.front), (GL_BACK, stencil.back)]
+                {
+                    glStencilOpSeparate(face, fail as _, depth_fail as _, pass as _);
+                }
+            }
+
+            if changed!(color_mask) {
+                let [r, g, b, a] = self.color_mask.map(|mask| mask as GLboolean);
+                glColorMask(r, g, b, a);
+            }
+
+            if changed!(polygon_mode) {
+                glPolygonMode(GL_FRONT_AND_BACK, self.polygon_mode as _);
+            }
+
+            if changed!(line_width) {
+                glLineWidth(self.line_width);
+            }
+        }
+    }
+
+    /// Run `clear` with the write masks that limit clears reset, restoring
+    /// the current state afterwards. The scissor rectangle still applies, so
+    /// clears can be limited to part of the target.
+    pub(crate) fn clearing(clear: impl FnOnce()) {
+        let prev = Self::current();
+        Self {
+            color_mask: [true; 4],
+            depth_write: true,
+            stencil: prev.stencil.map(|stencil| Stencil {
+                write_mask: !0,
+                ..stencil
+            }),
+            ..prev
+        }
+        .apply();
+        clear();
+        prev.apply();
+    }
+}
+
+/// Clip depth to `0..1` instead of `-1..1`, as `perspective_reverse_z`
+/// expects, keeping full depth buffer precision
+pub fn clip_zero_to_one(enable: bool) {
+    unsafe {
+        glClipControl(
+            GL_LOWER_LEFT,
+            if enable {
+                GL_ZERO_TO_ONE
+            } else {
+                GL_NEGATIVE_ONE_TO_ONE
+            },
+        );
+    }
+}
+
+unsafe fn toggle(cap: GLenum, enable: bool) {
+    if enable {
+        glEnable(cap);
+    } else {
+        glDisable(cap);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Blend {
+    pub src: Factor,
+    pub dst: Factor,
+    pub src_alpha: Factor,
+    pub dst_alpha: Factor,
+    pub equation: Equation,
+    pub equation_alpha: Equation,
+}
+
+impl Blend {
+    /// Straight alpha blending, accumulating coverage in the destination alpha
+    pub const ALPHA: Self = Self {
+        src_alpha: Factor::One,
+        ..Self::new(Factor::SrcAlpha, Factor::OneMinusSrcAlpha)
+    };
+    pub const PREMULTIPLIED: Self = Self::new(Factor::One, Factor::OneMinusSrcAlpha);
+    pub const ADDITIVE: Self = Self::new(Factor::One, Factor::One);
+
+    /// Blend colour and alpha alike, adding source and destination
+    pub const fn new(src: Factor, dst: Factor) -> Self {
+        Self {
+            src,
+            dst,
+            src_alpha: src,
+            dst_alpha: dst,
+            equation: Equation::Add,
+            equation_alpha: Equation::Add,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stencil {
+    pub func: Compare,
+    pub reference: i32,
+    pub read_mask: u32,
+    pub write_mask: u32,
+    /// Operations on stencil fail, depth fail and pass for front faces
+    pub front: [StencilOp; 3],
+    pub back: [StencilOp; 3],
+}
+
+impl Default for Stencil {
+    fn default() -> Self {
+        Self {
+            func: Compare::Always,
+            reference: 0,
+            read_mask: !0,
+            write_mask: !0,
+            front: [StencilOp::Keep; 3],
+            back: [StencilOp::Keep; 3],
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Factor {
+    Zero = GL_ZERO,
+    One = GL_ONE,
+    SrcColor = GL_SRC_COLOR,
+    OneMinusSrcColor = GL_ONE_MINUS_SRC_COLOR,
+    DstColor = GL_DST_COLOR,
+    OneMinusDstColor = GL_ONE_MINUS_DST_COLOR,
+    SrcAlpha = GL_SRC_ALPHA,
+    OneMinusSrcAlpha = GL_ONE_MINUS_SRC_ALPHA,
+    DstAlpha = GL_DST_ALPHA,
+    OneMinusDstAlpha = GL_ONE_MINUS_DST_ALPHA,
+    ConstantColor = GL_CONSTANT_COLOR,
+    OneMinusConstantColor = GL_ONE_MINUS_CONSTANT_COLOR,
+    SrcAlphaSaturate = GL_SRC_ALPHA_SATURATE,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Equation {
+    Add = GL_FUNC_ADD,
+    Subtract = GL_FUNC_SUBTRACT,
+    ReverseSubtract = GL_FUNC_REVERSE_SUBTRACT,
+    Min = GL_MIN,
+    Max = GL_MAX,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compare {
+    Never = GL_NEVER,
+    Less = GL_LESS,
+    Equal = GL_EQUAL,
+    LessEqual = GL_LEQUAL,
+    Greater = GL_GREATER,
+    NotEqual = GL_NOTEQUAL,
+    GreaterEqual = GL_GEQUAL,
+    Always = GL_ALWAYS,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilOp {
+    Keep = GL_KEEP,
+    Zero = GL_ZERO,
+    Replace = GL_REPLACE,
+    Incr = GL_INCR,
+    IncrWrap = GL_INCR_WRAP,
+    Decr = GL_DECR,
+    DecrWrap = GL_DECR_WRAP,
+    Invert = GL_INVERT,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    Front = GL_FRONT,
+    Back = GL_BACK,
+    FrontAndBack = GL_FRONT_AND_BACK,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    Ccw = GL_CCW,
+    Cw = GL_CW,
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonMode {
+    Point = GL_POINT,
+    Line = GL_LINE,
+    Fill = GL_FILL,
+}
@@ -1,31 +1,71 @@
-use {crate::gfx::*, core::marker::PhantomData};
+use {
+    crate::{
+        gfx::*,
+        mem::{vec, vec::Vec},
+    },
+    core::marker::PhantomData,
+};
 
-pub type TextureRgb = Texture<[f32; 3]>;
-pub type TextureRgba = Texture<[f32; 4]>;
-pub type TextureStencil = Texture<i32>;
+pub type TextureRgb = Texture<Rgb8>;
+pub type TextureRgba = Texture<Rgba8>;
+pub type TextureStencil = Texture<Stencil8>;
 
 pub trait Format {
+    /// Sized internal format the texture is stored as
+    const GL_INTERNAL_FORMAT: u32;
+    /// Format and type of `Pixel` when uploading and reading back
     const GL_FORMAT: u32;
-}
+    const GL_TYPE: u32;
 
-impl Format for [f32; 3] {
-    const GL_FORMAT: u32 = GL_RGB;
+    type Pixel: Copy + Default;
 }
 
-impl Format for [f32; 4] {
-    const GL_FORMAT: u32 = GL_RGBA;
+macro_rules! format {
+    ($($format:ident => $internal:ident, $gl_format:ident, $gl_type:ident, $pixel:ty;)*) => {$(
+        #[derive(Debug, Clone, Copy)]
+        pub struct $format;
+
+        impl Format for $format {
+            const GL_INTERNAL_FORMAT: u32 = $internal;
+            const GL_FORMAT: u32 = $gl_format;
+            const GL_TYPE: u32 = $gl_type;
+
+            type Pixel = $pixel;
+        }
+    )*};
 }
 
-impl Format for i32 {
-    const GL_FORMAT: u32 = GL_STENCIL_INDEX;
+format! {
+    R8 => GL_R8, GL_RED, GL_UNSIGNED_BYTE, u8;
+    Rg8 => GL_RG8, GL_RG, GL_UNSIGNED_BYTE, [u8; 2];
+    Rgb8 => GL_RGB8, GL_RGB, GL_UNSIGNED_BYTE, [u8; 3];
+    Rgba8 => GL_RGBA8, GL_RGBA, GL_UNSIGNED_BYTE, [u8; 4];
+    Srgb8Alpha8 => GL_SRGB8_ALPHA8, GL_RGBA, GL_UNSIGNED_BYTE, [u8; 4];
+    R16F => GL_R16F, GL_RED, GL_FLOAT, f32;
+    Rg16F => GL_RG16F, GL_RG, GL_FLOAT, [f32; 2];
+    Rgba16F => GL_RGBA16F, GL_RGBA, GL_FLOAT, [f32; 4];
+    R32F => GL_R32F, GL_RED, GL_FLOAT, f32;
+    Rgba32F => GL_RGBA32F, GL_RGBA, GL_FLOAT, [f32; 4];
+    Depth16 => GL_DEPTH_COMPONENT16, GL_DEPTH_COMPONENT, GL_UNSIGNED_SHORT, u16;
+    Depth24 => GL_DEPTH_COMPONENT24, GL_DEPTH_COMPONENT, GL_UNSIGNED_INT, u32;
+    Depth32F => GL_DEPTH_COMPONENT32F, GL_DEPTH_COMPONENT, GL_FLOAT, f32;
+    Depth24Stencil8 => GL_DEPTH24_STENCIL8, GL_DEPTH_STENCIL, GL_UNSIGNED_INT_24_8, u32;
+    Stencil8 => GL_STENCIL_INDEX8, GL_STENCIL_INDEX, GL_UNSIGNED_BYTE, u8;
 }
 
 pub const TEX_2D: u32 = GL_TEXTURE_2D;
+pub const TEX_2D_ARRAY: u32 = GL_TEXTURE_2D_ARRAY;
+pub const TEX_3D: u32 = GL_TEXTURE_3D;
+pub const TEX_CUBE_MAP: u32 = GL_TEXTURE_CUBE_MAP;
+pub const TEX_2D_MULTISAMPLE: u32 = GL_TEXTURE_2D_MULTISAMPLE;
 
 #[derive(Debug)]
 pub struct Texture<F: Format> {
     id: GLuint,
     target: GLuint,
+    size: [i32; 2],
+    /// Layers of an array texture or depth of a 3D one, 1 otherwise
+    depth: i32,
     format: PhantomData<F>,
 }
 
@@ -38,24 +78,264 @@ impl<F: Format> Texture<F> {
             glTexImage2D(
                 target as _,
                 0,
-                F::GL_FORMAT as _,
+                F::GL_INTERNAL_FORMAT as _,
                 w,
                 h,
                 0,
                 F::GL_FORMAT as _,
-                GL_UNSIGNED_BYTE,
+                F::GL_TYPE,
                 core::ptr::null(),
             );
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_NEAREST as _);
-            glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_NEAREST as _);
         }
 
-        Self {
+        Self::wrap(id, target, [w, h], 1)
+    }
+
+    /// Immutable storage for `levels` mip levels, see `mip_levels`
+    pub fn storage(target: u32, [w, h]: [i32; 2], levels: i32) -> Self {
+        let mut id = 0;
+        unsafe {
+            glGenTextures(1, &mut id);
+            glBindTexture(target as _, id);
+            glTexStorage2D(target as _, levels, F::GL_INTERNAL_FORMAT, w, h);
+        }
+
+        Self::wrap(id, target, [w, h], 1)
+    }
+
+    /// Immutable storage for `layers` layers of an array or 3D texture
+    pub fn layers(target: u32, [w, h]: [i32; 2], layers: i32, levels: i32) -> Self {
+        let mut id = 0;
+        unsafe {
+            glGenTextures(1, &mut id);
+            glBindTexture(target as _, id);
+            glTexStorage3D(target as _, levels, F::GL_INTERNAL_FORMAT, w, h, layers);
+        }
+
+        Self::wrap(id, target, [w, h], layers)
+    }
+
+    /// Multisampled render target, resolved with `Framebuffer::resolve` before
+    /// it can be sampled normally
+    pub fn multisample([w, h]: [i32; 2], samples: i32) -> Self {
+        let mut id = 0;
+        unsafe {
+            glGenTextures(1, &mut id);
+            glBindTexture(GL_TEXTURE_2D_MULTISAMPLE, id);
+            glTexStorage2DMultisample(
+                GL_TEXTURE_2D_MULTISAMPLE,
+                samples,
+                F::GL_INTERNAL_FORMAT,
+                w,
+                h,
+                GL_TRUE as _,
+            );
+        }
+
+        Self::wrap(id, GL_TEXTURE_2D_MULTISAMPLE, [w, h], 1)
+    }
+
+    /// Texture filled from `pixels`, row by row starting at the bottom
+    pub fn from_pixels(target: u32, size: [i32; 2], pixels: &[F::Pixel]) -> Self {
+        let tex = Self::new(target, size);
+        tex.update([0, 0], size, pixels);
+
+        tex
+    }
+
+    fn wrap(id: GLuint, target: u32, size: [i32; 2], depth: i32) -> Self {
+        let tex = Self {
             id,
             target,
+            size,
+            depth,
             format: PhantomData,
+        };
+        // Multisample textures have no sampler state
+        if target != GL_TEXTURE_2D_MULTISAMPLE {
+            tex.set_filter(Filter::Nearest, Filter::Nearest);
         }
+
+        tex
+    }
+
+    pub fn update(&self, offset: [i32; 2], size: [i32; 2], pixels: &[F::Pixel]) {
+        self.update_level(0, offset, size, pixels);
     }
+
+    pub fn update_level(
+        &self,
+        level: i32,
+        [x, y]: [i32; 2],
+        [w, h]: [i32; 2],
+        pixels: &[F::Pixel],
+    ) {
+        assert!(
+            pixels.len() >= (w * h) as usize,
+            "not enough pixels for {}x{}",
+            w,
+            h
+        );
+        self.bind();
+        unsafe {
+            glPixelStorei(GL_UNPACK_ALIGNMENT, 1);
+            glTexSubImage2D(
+                self.target,
+                level,
+                x,
+                y,
+                w,
+                h,
+                F::GL_FORMAT,
+                F::GL_TYPE,
+                pixels.as_ptr() as _,
+            );
+        }
+    }
+
+    /// Pixels of mip `level`, bottom row first and, for array and 3D
+    /// textures, layer by layer
+    pub fn read_pixels(&self, level: i32) -> Vec<F::Pixel> {
+        assert!(
+            !matches!(self.target, TEX_CUBE_MAP | TEX_2D_MULTISAMPLE),
+            "can't read back cube map or multisample textures"
+        );
+        let [w, h] = self.size.map(|n| (n >> level).max(1));
+        // Array layers keep their count down the mip chain, 3D slices don't
+        let depth = match self.target {
+            TEX_3D => (self.depth >> level).max(1),
+            _ => self.depth,
+        };
+        let mut pixels = vec![F::Pixel::default(); (w * h * depth) as usize];
+
+        self.bind();
+        unsafe {
+            glPixelStorei(GL_PACK_ALIGNMENT, 1);
+            glGetTexImage(
+                self.target,
+                level,
+                F::GL_FORMAT,
+                F::GL_TYPE,
+                pixels.as_mut_ptr() as _,
+            );
+        }
+
+        pixels
+    }
+
+    /// Fill mip `level` with zeros
+    pub fn clear(&self, level: i32) {
+        unsafe {
+            glClearTexImage(self.id, level, F::GL_FORMAT, F::GL_TYPE, core::ptr::null());
+        }
+    }
+
+    /// Copy the `size` pixels at `src` to `dst` in `target`, without going
+    /// through a framebuffer
+    pub fn copy_to(
+        &self,
+        target: &Texture<F>,
+        [sx, sy]: [i32; 2],
+        [dx, dy]: [i32; 2],
+        [w, h]: [i32; 2],
+    ) {
+        unsafe {
+            glCopyImageSubData(
+                self.id,
+                self.target,
+                0,
+                sx,
+                sy,
+                0,
+                target.id,
+                target.target,
+                0,
+                dx,
+                dy,
+                0,
+                w,
+                h,
+                1,
+            );
+        }
+    }
+
+    pub fn generate_mipmaps(&self) {
+        self.bind();
+        unsafe {
+            glGenerateMipmap(self.target);
+        }
+    }
+
+    pub fn set_filter(&self, min: Filter, mag: Filter) {
+        self.bind();
+        unsafe {
+            glTexParameteri(self.target, GL_TEXTURE_MIN_FILTER, min as _);
+            glTexParameteri(self.target, GL_TEXTURE_MAG_FILTER, mag as _);
+        }
+    }
+
+    pub fn set_wrap(&self, s: Wrap, t: Wrap) {
+        self.bind();
+        unsafe {
+            glTexParameteri(self.target, GL_TEXTURE_WRAP_S, s as _);
+            glTexParameteri(self.target, GL_TEXTURE_WRAP_T, t as _);
+        }
+    }
+
+    pub fn set_anisotropy(&self, samples: f32) {
+        self.bind();
+        unsafe {
+            let mut max = 1.0;
+            glGetFloatv(GL_MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+            glTexParameterf(
+                self.target,
+                GL_TEXTURE_MAX_ANISOTROPY,
+                samples.clamp(1.0, max),
+            );
+        }
+    }
+
+    /// Colour sampled outside the texture with `Wrap::ClampToBorder`
+    pub fn set_border(&self, rgba: [f32; 4]) {
+        self.bind();
+        unsafe {
+            glTexParameterfv(self.target, GL_TEXTURE_BORDER_COLOR, rgba.as_ptr());
+        }
+    }
+
+    /// Bind to texture unit `unit`, for programs sampling several textures
+    pub fn bind_unit(&self, unit: u32) {
+        unsafe {
+            glActiveTexture(GL_TEXTURE0 + unit);
+            self.bind();
+            glActiveTexture(GL_TEXTURE0);
+        }
+    }
+
+    pub fn size(&self) -> [i32; 2] {
+        self.size
+    }
+
+    /// Bind mip `level` to image unit `unit` for `imageLoad`/`imageStore`.
+    pub fn bind_image(&self, unit: u32, level: i32, access: Access) {
+        unsafe {
+            glBindImageTexture(
+                unit,
+                self.id,
+                level,
+                GL_FALSE as _,
+                0,
+                access as _,
+                F::GL_INTERNAL_FORMAT,
+            );
+        }
+    }
+}
+
+/// Number of levels in a full mip chain for `size`
+pub fn mip_levels([w, h]: [i32; 2]) -> i32 {
+    32 - (w.max(h).max(1) as u32).leading_zeros() as i32
 }
 
 impl<F: Format> Resource for Texture<F> {
@@ -83,3 +363,32 @@ impl<F: Format> Drop for Texture<F> {
         }
     }
 }
+
+#[repr(u32)]
+#[derive(Clone, Copy)]
+pub enum Filter {
+    Nearest = GL_NEAREST,
+    Linear = GL_LINEAR,
+    NearestMipmapNearest = GL_NEAREST_MIPMAP_NEAREST,
+    LinearMipmapNearest = GL_LINEAR_MIPMAP_NEAREST,
+    NearestMipmapLinear = GL_NEAREST_MIPMAP_LINEAR,
+    LinearMipmapLinear = GL_LINEAR_MIPMAP_LINEAR,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy)]
+pub enum Wrap {
+    Repeat = GL_REPEAT,
+    MirroredRepeat = GL_MIRRORED_REPEAT,
+    ClampToEdge = GL_CLAMP_TO_EDGE,
+    ClampToBorder = GL_CLAMP_TO_BORDER,
+    MirrorClampToEdge = GL_MIRROR_CLAMP_TO_EDGE,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy)]
+pub enum Access {
+    ReadOnly = GL_READ_ONLY,
+    WriteOnly = GL_WRITE_ONLY,
+    ReadWrite = GL_READ_WRITE,
+}
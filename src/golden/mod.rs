@@ -0,0 +1,263 @@
+//! Golden-image testing: render a scene offscreen and compare it against a
+//! stored reference PNG. Run with `HEX_LN_BLESS=1` to write the rendered
+//! images as the new references.
+
+extern crate std;
+
+use {
+    crate::{
+        gfx::{
+            framebuffer::{Attachment, Framebuffer},
+            image::{DecodeError, Image},
+            texture::{Texture, TextureRgba, TEX_2D},
+            Resource, Target,
+        },
+        win::window::Window,
+    },
+    core::{ffi::CStr, fmt},
+    std::{
+        env, format, fs, io,
+        path::{Path, PathBuf},
+        sync::{Mutex, MutexGuard},
+    },
+};
+
+pub const BLESS_VAR: &str = "HEX_LN_BLESS";
+
+/// GL contexts are per thread and SDL isn't thread safe, so tests running in
+/// parallel take turns
+static CONTEXT: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerance {
+    /// Largest difference in any channel for a pixel to still match
+    pub channel: u8,
+    /// Fraction of pixels allowed to exceed `channel`
+    pub pixels: f32,
+    /// Lowest mean structural similarity (SSIM) of the luma, 1.0 for identical
+    pub ssim: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            channel: 2,
+            pixels: 0.001,
+            ssim: 0.98,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Mismatch {
+    Missing(PathBuf),
+    Decode(DecodeError),
+    Io(io::Error),
+    Size {
+        expected: [i32; 2],
+        actual: [i32; 2],
+    },
+    Pixels {
+        differing: f32,
+        ssim: f32,
+        diff: PathBuf,
+    },
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Missing(path) => write!(
+                f,
+                "no reference at {}, rerun with {}=1 to create it",
+                path.display(),
+                BLESS_VAR
+            ),
+            Mismatch::Decode(err) => write!(f, "failed to decode reference: {:?}", err),
+            Mismatch::Io(err) => write!(f, "{}", err),
+            Mismatch::Size { expected, actual } => {
+                write!(f, "rendered {:?} but reference is {:?}", actual, expected)
+            }
+            Mismatch::Pixels {
+                differing,
+                ssim,
+                diff,
+            } => write!(
+                f,
+                "{:.3}% of pixels differ with SSIM {:.4}, see {}",
+                differing * 100.0,
+                ssim,
+                diff.display()
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for Mismatch {
+    fn from(err: io::Error) -> Self {
+        Mismatch::Io(err)
+    }
+}
+
+pub struct Golden {
+    dir: PathBuf,
+    size: [i32; 2],
+    pub tolerance: Tolerance,
+    tex: TextureRgba,
+    fb: Framebuffer,
+    _window: Window,
+    _context: MutexGuard<'static, ()>,
+}
+
+impl Golden {
+    /// Offscreen context rendering `size` images, compared against references
+    /// in `dir`
+    pub fn new(dir: impl AsRef<Path>, size: [i32; 2]) -> Self {
+        let context = CONTEXT.lock().unwrap_or_else(|err| err.into_inner());
+        let name = CStr::from_bytes_with_nul(b"golden\0").expect("invalid window name");
+        let window = Window::hidden(name, size[0], size[1]).expect("offscreen context failed");
+
+        let tex = Texture::new(TEX_2D, size);
+        let mut fb = Framebuffer::new();
+        fb.attach(Attachment::Color(0), &tex);
+
+        Self {
+            dir: dir.as_ref().into(),
+            size,
+            tolerance: Tolerance::default(),
+            tex,
+            fb,
+            _window: window,
+            _context: context,
+        }
+    }
+
+    /// Clear the offscreen target to transparent black, let `draw` render into
+    /// it and compare the result against reference `name`
+    pub fn render(&self, name: &str, draw: impl FnOnce([i32; 2])) -> Result<(), Mismatch> {
+        self.fb.bind();
+        self.fb.viewport([0, 0], self.size);
+        self.fb.clear_color([0.0; 4]);
+        draw(self.size);
+
+        self.check(name, &self.fb.read_pixels([0, 0], self.size))
+    }
+
+    /// Compare `actual` against reference `name`, writing the image and a
+    /// diff next to the reference if they don't match
+    pub fn check(&self, name: &str, actual: &Image) -> Result<(), Mismatch> {
+        let reference = self.dir.join(format!("{}.png", name));
+        if env::var_os(BLESS_VAR).is_some() {
+            fs::create_dir_all(&self.dir)?;
+            fs::write(&reference, actual.encode_png())?;
+            log::info!("blessed {}", reference.display());
+
+            return Ok(());
+        }
+
+        let expected = fs::read(&reference).map_err(|_| Mismatch::Missing(reference))?;
+        let expected = Image::decode(&expected).map_err(Mismatch::Decode)?;
+        if expected.size != actual.size {
+            return Err(Mismatch::Size {
+                expected: expected.size,
+                actual: actual.size,
+            });
+        }
+
+        let (differing, diff) = compare(&expected, actual, self.tolerance.channel);
+        let ssim = ssim(&expected, actual);
+        if differing <= self.tolerance.pixels && ssim >= self.tolerance.ssim {
+            return Ok(());
+        }
+
+        let diff_path = self.dir.join(format!("{}.diff.png", name));
+        fs::write(
+            self.dir.join(format!("{}.actual.png", name)),
+            actual.encode_png(),
+        )?;
+        fs::write(&diff_path, diff.encode_png())?;
+
+        Err(Mismatch::Pixels {
+            differing,
+            ssim,
+            diff: diff_path,
+        })
+    }
+
+    /// The offscreen colour target `render` draws into
+    pub fn target(&self) -> &TextureRgba {
+        &self.tex
+    }
+}
+
+/// Fraction of pixels differing by more than `channel`, and an image showing
+/// them in red over a faded copy of the reference
+fn compare(expected: &Image, actual: &Image, channel: u8) -> (f32, Image) {
+    let mut diff = Image::new(expected.size);
+    let mut differing = 0;
+    for ((out, a), b) in diff
+        .pixels
+        .iter_mut()
+        .zip(expected.pixels.iter())
+        .zip(actual.pixels.iter())
+    {
+        let delta = (0..4).map(|c| a[c].abs_diff(b[c])).max().unwrap_or(0);
+        *out = if delta > channel {
+            differing += 1;
+            [0x80 + delta / 2, 0, 0, 0xFF]
+        } else {
+            let grey = (luma(*a) / 4.0) as u8;
+            [grey, grey, grey, 0xFF]
+        };
+    }
+
+    (differing as f32 / expected.pixels.len().max(1) as f32, diff)
+}
+
+fn luma([r, g, b, a]: [u8; 4]) -> f32 {
+    (0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32) * a as f32 / 255.0
+}
+
+/// Mean SSIM of the luma over 8x8 windows
+fn ssim(expected: &Image, actual: &Image) -> f32 {
+    const WINDOW: usize = 8;
+    const C1: f32 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f32 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let [w, h] = expected.size.map(|n| n as usize);
+    let mut total = 0.0;
+    let mut windows = 0;
+    for y0 in (0..h).step_by(WINDOW) {
+        for x0 in (0..w).step_by(WINDOW) {
+            let idx = (y0..(y0 + WINDOW).min(h))
+                .flat_map(|y| (x0..(x0 + WINDOW).min(w)).map(move |x| y * w + x));
+            let samples = idx
+                .map(|i| (luma(expected.pixels[i]), luma(actual.pixels[i])))
+                .collect::<std::vec::Vec<_>>();
+
+            let n = samples.len() as f32;
+            let (mx, my) = samples
+                .iter()
+                .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+            let (vx, vy, cov) = samples
+                .iter()
+                .fold((0.0, 0.0, 0.0), |(vx, vy, cov), (x, y)| {
+                    (
+                        vx + (x - mx) * (x - mx) / n,
+                        vy + (y - my) * (y - my) / n,
+                        cov + (x - mx) * (y - my) / n,
+                    )
+                });
+
+            total += (2.0 * mx * my + C1) * (2.0 * cov + C2)
+                / ((mx * mx + my * my + C1) * (vx + vy + C2));
+            windows += 1;
+        }
+    }
+
+    if windows == 0 {
+        1.0
+    } else {
+        total / windows as f32
+    }
+}
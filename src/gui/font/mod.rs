@@ -1,42 +1,60 @@
 use {
     crate::{
         gfx::{
-            buffer::Usage,
-            framebuffer::{Attachment, Framebuffer},
-            mesh::{Mesh, Topology},
-            program::Program,
-            shader::{POS2D, WHITE},
-            texture::{Texture, TEX_2D},
+            atlas::{Atlas, Region},
+            framebuffer::{Attachment, Framebuffer, Mask},
+            path::{FillRule, PathRenderer},
+            query,
+            renderbuffer::Renderbuffer,
+            texture::{Depth24Stencil8, Filter, Rgba8, TextureRgba},
             Resource, Target,
         },
-        math::{Spline, Subdivide},
+        math::{ortho, Spline},
         mem::vec::Vec,
     },
     ttf_parser::{Face, FaceParsingError, OutlineBuilder, Rect},
 };
 
 const PIXELS_PER_EM: f32 = 16.0;
+/// Resolution glyphs are rasterized into the atlas at
+const RASTER_PIXELS_PER_EM: i32 = 128;
+/// Samples per pixel glyph outlines are anti-aliased with
+const GLYPH_SAMPLES: i32 = 4;
 
 #[derive(Debug)]
 pub struct Font {
     glyphs: Vec<Option<Glyph>>,
+    atlas: Atlas<u8, Rgba8>,
     pub pixels_per_unit: f32,
     pub line_height: i16,
 }
 
 impl Font {
     pub fn load(file: &[u8]) -> Result<Self, FaceParsingError> {
+        let _scope = query::scope("Font::load");
         let mut glyphs = Vec::with_capacity(128);
 
         let face = Face::parse(file, 0)?;
-        let builder = GlyphBuilder::new(&face);
+        let mut atlas = Atlas::new([512, 512], [4096, 4096], 1);
+        let mut builder = GlyphBuilder::new(&face);
+        let bake = query::scope("bake glyphs");
         for ch in 0..128u8 {
-            let glyph = builder.glyph(ch as char);
+            let glyph = builder.glyph(ch as char, &mut atlas);
             glyphs.push(glyph);
         }
+        drop(bake);
+
+        // Growing the atlas moves glyphs, so look them up once all are in
+        for (ch, glyph) in glyphs.iter_mut().enumerate() {
+            if let Some(glyph) = glyph {
+                glyph.region = atlas.get(&(ch as u8));
+            }
+        }
+        atlas.texture().set_filter(Filter::Linear, Filter::Linear);
 
         Ok(Self {
             glyphs,
+            atlas,
             pixels_per_unit: PIXELS_PER_EM / face.units_per_em() as f32,
             line_height: face.height(),
         })
@@ -45,6 +63,11 @@ impl Font {
     pub fn get(&self, idx: u8) -> Option<&Glyph> {
         self.glyphs[idx as usize].as_ref()
     }
+
+    /// Atlas texture holding every glyph, see `Glyph::region`
+    pub fn texture(&self) -> &TextureRgba {
+        self.atlas.texture()
+    }
 }
 
 impl Default for Font {
@@ -59,7 +82,9 @@ impl Default for Font {
 
 #[derive(Debug)]
 pub struct Glyph {
-    pub tex: Option<Texture<[f32; 4]>>,
+    /// Area of `Font::texture` holding the glyph, `None` without an outline
+    pub region: Option<Region>,
+    /// Outline bounds in font units
     pub size: [i32; 2],
     pub bearing: [i32; 2],
     pub h_advance: u16,
@@ -67,18 +92,20 @@ pub struct Glyph {
 
 struct GlyphBuilder<'a> {
     face: &'a Face<'a>,
-    stencil: Program,
+    paths: PathRenderer,
 }
 
 impl<'a> GlyphBuilder<'a> {
     fn new(face: &'a Face<'a>) -> Self {
         Self {
             face,
-            stencil: Program::new(POS2D, WHITE),
+            paths: PathRenderer::new(),
         }
     }
 
-    fn glyph(&self, ch: char) -> Option<Glyph> {
+    fn glyph(&mut self, ch: char, atlas: &mut Atlas<u8, Rgba8>) -> Option<Glyph> {
+        let upem = self.face.units_per_em() as i32;
+
         self.face.glyph_index(ch).map_or_else(
             || {
                 log::debug!("skipping unprintable character {}", ch.escape_default());
@@ -88,73 +115,70 @@ impl<'a> GlyphBuilder<'a> {
                 log::debug!("constructing glyph for '{}'", ch.escape_default());
 
                 let mut outline = SplineBuilder::new();
-                let (tex, size) = self.face.outline_glyph(idx, &mut outline).map_or_else(
-                    || {
-                        log::debug!("no outline found for '{}'", ch.escape_default());
+                let (region, size) = self
+                    .face
+                    .outline_glyph(idx, &mut outline)
+                    .map_or_else(
+                        || {
+                            log::debug!("no outline found for '{}'", ch.escape_default());
+                            Some((None, [0, 0]))
+                        },
+                        |Rect {
+                             x_min,
+                             x_max,
+                             y_min,
+                             y_max,
+                         }| {
+                            let size = [(x_max - x_min) as i32, (y_max - y_min) as i32];
+
+                            log::debug!(
+                                "'{}' has outline with size {:?} and {} splines",
+                                ch.escape_default(),
+                                size,
+                                outline.splines.len(),
+                            );
+
+                            // Fill the outline multisampled, then resolve it into the atlas
+                            let raster =
+                                size.map(|n| ((n * RASTER_PIXELS_PER_EM + upem - 1) / upem).max(1));
+                            let region = atlas.allocate(ch as u8, raster)?;
+
+                            let color = Renderbuffer::<Rgba8>::multisample(raster, GLYPH_SAMPLES);
+                            let depth_stencil =
+                                Renderbuffer::<Depth24Stencil8>::multisample(raster, GLYPH_SAMPLES);
+                            let mut msaa = Framebuffer::new();
+                            msaa.attach_renderbuffer(Attachment::Color(0), &color);
+                            msaa.attach_renderbuffer(Attachment::DepthStencil, &depth_stencil);
+
+                            let mut fb = Framebuffer::new();
+                            fb.attach(Attachment::Color(0), atlas.texture());
+
+                            msaa.bind();
+                            msaa.viewport([0, 0], raster);
+                            msaa.clear_color([0.0, 0.0, 0.0, 0.0]);
+                            msaa.clear_stencil(0);
+
+                            self.paths.transform =
+                                ortho([x_min as f32, y_min as f32], [x_max as f32, y_max as f32]);
+                            self.paths
+                                .fill(&outline.splines, FillRule::NonZero, [0xFF; 4]);
+
+                            let [x, y, w, h] = region.rect;
+                            msaa.blit_to(
+                                &fb,
+                                [0, 0, w, h],
+                                [x, y, x + w, y + h],
+                                &[Mask::Color],
+                                Filter::Nearest,
+                            );
+
+                            Some((Some(region), size))
+                        },
+                    )
+                    .unwrap_or_else(|| {
+                        log::warn!("no room in the atlas for '{}'", ch.escape_default());
                         (None, [0, 0])
-                    },
-                    |Rect {
-                         x_min,
-                         x_max,
-                         y_min,
-                         y_max,
-                     }| {
-                        let size = [(x_max - x_min) as i32, (y_max - y_min) as i32];
-
-                        log::debug!(
-                            "'{}' has outline with size {:?} and {} splines",
-                            ch.escape_default(),
-                            size,
-                            outline.splines.len(),
-                        );
-
-                        let verts = outline.splines.iter().fold(
-                            Vec::with_capacity(outline.splines.len() * 100 + 1),
-                            |mut points, spline| {
-                                points.push([0.0, 0.0]);
-                                spline
-                                    .subdivide(10)
-                                    .iter()
-                                    .fold(points, |mut points, point| {
-                                        let point = [
-                                            (2.0 * (point[0] - x_min as f32) / size[0] as f32)
-                                                - 1.0,
-                                            (2.0 * (point[1] - y_min as f32) / size[1] as f32)
-                                                - 1.0,
-                                        ];
-                                        points.push(point);
-
-                                        points
-                                    })
-                            },
-                        );
-
-                        let glyph = Mesh::new(&verts, Usage::StaticDraw, Topology::TriFan);
-                        let quad = Mesh::new(
-                            &[[-1.0, 1.0], [1.0, 1.0], [-1.0, -1.0], [1.0, -1.0]],
-                            Usage::StaticDraw,
-                            Topology::TriStrip,
-                        );
-
-                        let tex: Texture<[f32; 4]> = Texture::new(TEX_2D, size);
-                        let stencil: Texture<i32> = Texture::new(TEX_2D, size);
-
-                        let fb = Framebuffer::new();
-                        fb.attach(Attachment::Color0, &tex);
-                        fb.attach(Attachment::Stencil, &stencil);
-
-                        fb.bind();
-                        fb.viewport([0, 0], size);
-                        fb.clear_color([0.0, 0.0, 0.0, 0.0]);
-
-                        self.stencil.bind();
-                        stencil.bind();
-                        glyph.stencil();
-                        quad.draw();
-
-                        (Some(tex), size)
-                    },
-                );
+                    });
 
                 let h_advance = self.face.glyph_hor_advance(idx).unwrap_or(0);
                 let bearing = [
@@ -163,7 +187,7 @@ impl<'a> GlyphBuilder<'a> {
                 ];
 
                 Some(Glyph {
-                    tex,
+                    region,
                     size,
                     bearing,
                     h_advance,
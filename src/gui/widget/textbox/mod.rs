@@ -1,11 +1,12 @@
 use crate::{
     gfx::{
         buffer::Usage,
-        framebuffer::{Attachment, Framebuffer},
         mesh::{Mesh, Topology},
         program::Program,
-        texture::{Texture, TextureRgba, TEX_2D},
-        Resource, Target, Uniform,
+        query,
+        sdf::{BoxStyle, Boxes},
+        state::{Blend, RenderState},
+        Resource, Uniform,
     },
     gui::font::{Font, Glyph},
     math::ortho,
@@ -27,48 +28,81 @@ static TEXT_FRAG: &str = concat!(
     "\0"
 );
 
-#[derive(Debug)]
+/// Per-glyph quad, `[left, bottom, right, top]` and its atlas UVs
+type Quad = ([f32; 4], [f32; 4]);
+
 pub struct TextBox {
     text: Vec<u8>,
-    tex: TextureRgba,
-    buf: Framebuffer,
+    style: Option<BoxStyle>,
+    program: Program,
+    boxes: Boxes,
+    mesh: Mesh<[f32; 2], Quad>,
+    quads: Vec<Quad>,
 }
 
-impl TextBox {
-    pub fn new(size: [i32; 2]) -> Self {
-        let tex = Texture::new(TEX_2D, size);
-        let buf = Framebuffer::new();
-        buf.attach(Attachment::Color0, &tex);
+impl Default for TextBox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
+impl TextBox {
+    pub fn new() -> Self {
         Self {
             text: Vec::with_capacity(1),
-            tex,
-            buf,
+            style: None,
+            program: Program::new(TEXT_VERT, TEXT_FRAG),
+            boxes: Boxes::new(),
+            mesh: Mesh::instanced(
+                &[[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]],
+                &[],
+                Usage::StreamDraw,
+                Topology::TriStrip,
+            ),
+            quads: Vec::new(),
         }
     }
 
-    pub fn draw(&self, [w, h]: [i32; 2], font: &Font, em: f32) {
-        log::debug!("rendering {:?}", self.text);
-        let program = Program::new(TEXT_VERT, TEXT_FRAG);
-        program.bind();
+    /// Draw the text on a box of `style` instead of straight onto the target
+    pub fn set_style(&mut self, style: Option<BoxStyle>) {
+        self.style = style;
+    }
+
+    /// Render over the bound target of `size`, e.g. from a `FrameGraph`
+    /// pass, without clearing it first
+    pub fn render(&mut self, [w, h]: [i32; 2], font: &Font, em: f32) {
+        let _scope = query::scope("TextBox::render");
+
+        // Inset the box to leave room for its shadow, and the text past its border
+        let mut inset = 0.0;
+        if let Some(style) = &self.style {
+            let margin = style.shadow.map_or(0.0, |shadow| shadow.extent());
+            self.boxes.push(
+                [margin, margin],
+                [w as f32 - margin, h as f32 - margin],
+                style,
+            );
+            self.boxes.flush([w, h]);
+
+            inset = margin + style.border;
+        }
 
-        self.buf.bind();
-        self.buf.viewport([0, 0], [w, h]);
-        self.buf.clear_color([0.0, 0.0, 0.0, 1.0]);
+        self.program.bind();
 
         // Build an orthographic projection matrix
         ortho([0.0, 0.0], [w as f32, h as f32]).bind(0);
 
         // Position the cursor for the first character
         let scale = em * font.pixels_per_unit;
-        let mut y = h as f32 - font.line_height as f32 * scale;
-        let mut x = 0.0;
+        let mut y = h as f32 - inset - font.line_height as f32 * scale;
+        let mut x = inset;
+        self.quads.clear();
         for byte in self.text.iter() {
             match byte {
                 b'\n' => {
                     // Move cursor to beginning of next line
                     y -= font.line_height as f32 * scale;
-                    x = 0.0;
+                    x = inset;
                 }
 
                 b'\t' => {
@@ -79,9 +113,9 @@ impl TextBox {
                 }
 
                 &ch => {
-                    // Draw the character if it has an outline
+                    // Queue the character if it has an outline
                     let glyph = font.get(ch).expect("character not found");
-                    if let Some(tex) = &glyph.tex {
+                    if let Some(region) = glyph.region {
                         let [w, h] = [glyph.size[0] as f32 * scale, glyph.size[1] as f32 * scale];
                         let [dx, dy] = [
                             glyph.bearing[0] as f32 * scale,
@@ -93,40 +127,36 @@ impl TextBox {
                         let bottom = y + dy;
                         let top = bottom + h;
 
-                        log::debug!(
-                            "rendering {} with bottom left ({}, {}) and top right ({}, {})",
-                            (ch as char).escape_default(),
-                            left,
-                            bottom,
-                            right,
-                            top
-                        );
-
-                        tex.bind();
-                        Mesh::new(
-                            &[
-                                ([left, top], [0.0, 1.0]),
-                                ([right, top], [1.0, 1.0]),
-                                ([left, bottom], [0.0, 0.0]),
-                                ([right, bottom], [1.0, 0.0]),
-                            ],
-                            Usage::StaticDraw,
-                            Topology::TriStrip,
-                        )
-                        .draw();
+                        let [min, max] = region.uv;
+                        self.quads
+                            .push(([left, bottom, right, top], [min[0], min[1], max[0], max[1]]));
                     }
 
                     x += glyph.h_advance as f32 * scale;
                 }
             }
         }
+
+        if self.quads.is_empty() {
+            return;
+        }
+
+        // Every glyph is in the font's atlas, so the whole text is one draw
+        self.mesh.update_instances(&self.quads);
+
+        let prev = RenderState::current();
+        RenderState {
+            blend: Some(Blend::ALPHA),
+            ..prev
+        }
+        .apply();
+
+        font.texture().bind();
+        self.mesh.draw_instanced(self.quads.len());
+        prev.apply();
     }
 
     pub fn update(&mut self, text: &str) {
         self.text = text.as_bytes().into();
     }
-
-    pub fn view(&self) -> &TextureRgba {
-        &self.tex
-    }
 }
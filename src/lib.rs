@@ -1,6 +1,8 @@
 #![no_std]
 #![feature(core_intrinsics)]
 pub mod gfx;
+#[cfg(feature = "golden")]
+pub mod golden;
 pub mod gui;
 pub mod math;
 pub mod mem;
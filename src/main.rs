@@ -10,13 +10,16 @@ use {
     core::ffi::CStr,
     hex_ln::{
         gfx::{
-            buffer::Usage,
-            mesh::{Mesh, Topology},
-            program::Program,
-            shader::{POS2D_TEX2D, TEX2D},
-            Resource, Target, SWAP_CHAIN,
+            draw2d::Draw2d,
+            framebuffer::Attachment,
+            graph::{Clear, FrameGraph, Size, TargetDesc, TargetPool},
+            post::{Effect, PostChain},
+            query::{self, Profiler},
+            sprite::Sprite,
+            texture::{Rgba16F, Rgba8},
         },
         gui::{font::Font, widget::TextBox},
+        mem::vec,
         win::{
             event::{Event, EventChannel, KeyCode},
             window::Window,
@@ -24,9 +27,6 @@ use {
     },
 };
 
-const WIDTH: i32 = 1920;
-const HEIGHT: i32 = 1080;
-
 #[cfg(feature = "no_std")]
 mod no_std {
     #[lang = "eh_personality"]
@@ -87,24 +87,21 @@ pub fn main() {
     )
     .expect("window creation failed");
 
+    query::enable(true);
     let font = Font::default();
-    let mut greets = TextBox::new([1920, 1080]);
+    let mut greets = TextBox::new();
     greets.update("Greetz!\n\tit builds:D");
-    greets.draw([1920, 1080], &font, 10.0);
-
-    let tex_quad = Mesh::new(
-        &[
-            ([-1.0, 1.0], [0.0, 1.0]),
-            ([1.0, 1.0], [1.0, 1.0]),
-            ([-1.0, -1.0], [0.0, 0.0]),
-            ([1.0, -1.0], [1.0, 0.0]),
-        ],
-        Usage::StaticDraw,
-        Topology::TriStrip,
-    );
-
-    let glyph_prog = Program::new(POS2D_TEX2D, TEX2D);
-    glyph_prog.bind();
+
+    let mut draw = Draw2d::new();
+    let mut pool = TargetPool::new(window.size());
+    let post = PostChain::new(vec![
+        Effect::Fxaa,
+        Effect::Vignette {
+            radius: 0.9,
+            softness: 0.6,
+            strength: 0.5,
+        },
+    ]);
 
     let mut events = EventChannel;
     events.text_input(true);
@@ -121,20 +118,56 @@ pub fn main() {
                     edit = !edit;
                 }
 
+                Event::Keyboard { down, sym, .. } if sym == KeyCode::Tab && down => {
+                    query::profiler(|profiler| profiler.log());
+                }
+
+                Event::Resized { size } => {
+                    pool.resize(size);
+                }
+
                 _ => {}
             },
 
             None => {}
         };
 
-        SWAP_CHAIN.bind();
-        SWAP_CHAIN.clear_color([0.0, 0.0, 0.0, 1.0]);
-        SWAP_CHAIN.viewport([0, 0], [WIDTH, HEIGHT]);
+        let mut graph = FrameGraph::new(&mut pool);
+        let text = graph.create::<Rgba8>(
+            "text",
+            TargetDesc::new(Size::Window, Some(Clear::Color([0.0, 0.0, 0.0, 1.0]))),
+        );
+
+        graph
+            .pass("text")
+            .write(text, Attachment::Color(0))
+            .execute(|ctx| greets.render(ctx.size(), &font, 10.0));
+
+        let scene = graph.create::<Rgba16F>(
+            "scene",
+            TargetDesc::new(Size::Window, Some(Clear::Color([0.0, 0.0, 0.0, 1.0]))),
+        );
+
+        let draw = &mut draw;
+        graph
+            .pass("scene")
+            .read(text)
+            .write(scene, Attachment::Color(0))
+            .execute(move |ctx| {
+                if edit {
+                    let tex = ctx.texture(text);
+                    let greeting = Sprite {
+                        origin: [0.0, 0.0],
+                        ..Sprite::whole(tex)
+                    };
+                    greeting.draw(tex, draw);
+                }
+                draw.flush(ctx.size());
+            });
+        post.apply(&mut graph, scene);
 
-        if edit {
-            greets.view().bind();
-            tex_quad.draw();
-        }
+        graph.execute().expect("frame graph failed");
         window.swap();
+        query::profiler(Profiler::end_frame);
     }
 }
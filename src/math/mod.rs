@@ -1,5 +1,8 @@
 extern crate alloc;
-use {alloc::vec::Vec, core::intrinsics::powf32};
+use {
+    alloc::vec::Vec,
+    core::intrinsics::{cosf32, powf32, sinf32, sqrtf32},
+};
 
 pub type Matrix<const M: usize, const N: usize> = [[f32; N]; M];
 
@@ -17,6 +20,185 @@ pub fn ortho([left, bottom]: [f32; 2], [right, top]: [f32; 2]) -> Matrix<4, 4> {
     ]
 }
 
+/// Right-handed perspective projection mapping depth `near..far` to GL's
+/// `-1..1`, `fov_y` in radians
+pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Matrix<4, 4> {
+    let f = 1.0 / tan(fov_y / 2.0);
+
+    [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, (far + near) / (near - far), -1.0],
+        [0.0, 0.0, 2.0 * far * near / (near - far), 0.0],
+    ]
+}
+
+/// Infinite perspective projection mapping depth `near..` to `1..0`, for
+/// use with `gfx::state::clip_zero_to_one`, a `Compare::Greater` depth test
+/// and depth cleared to 0
+pub fn perspective_reverse_z(fov_y: f32, aspect: f32, near: f32) -> Matrix<4, 4> {
+    let f = 1.0 / tan(fov_y / 2.0);
+
+    [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, 0.0, -1.0],
+        [0.0, 0.0, near, 0.0],
+    ]
+}
+
+/// View matrix of a camera at `eye` facing `target`, looking down -z
+pub fn look_at(eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> Matrix<4, 4> {
+    let forward = normalize(sub(target, eye));
+    let right = normalize(cross(forward, up));
+    let up = cross(right, forward);
+
+    [
+        [right[0], up[0], -forward[0], 0.0],
+        [right[1], up[1], -forward[1], 0.0],
+        [right[2], up[2], -forward[2], 0.0],
+        [-dot(right, eye), -dot(up, eye), dot(forward, eye), 1.0],
+    ]
+}
+
+/// `a * b`, applying `b` first
+pub fn multiply(a: &Matrix<4, 4>, b: &Matrix<4, 4>) -> Matrix<4, 4> {
+    let mut out = [[0.0; 4]; 4];
+    for (col, out) in out.iter_mut().enumerate() {
+        for (row, out) in out.iter_mut().enumerate() {
+            *out = (0..4).map(|k| a[k][row] * b[col][k]).sum();
+        }
+    }
+
+    out
+}
+
+pub fn transform(m: &Matrix<4, 4>, [x, y, z, w]: [f32; 4]) -> [f32; 4] {
+    let mut out = [0.0; 4];
+    for (row, out) in out.iter_mut().enumerate() {
+        *out = m[0][row] * x + m[1][row] * y + m[2][row] * z + m[3][row] * w;
+    }
+
+    out
+}
+
+/// Inverse by cofactor expansion, `None` if `m` is singular
+pub fn invert(m: &Matrix<4, 4>) -> Option<Matrix<4, 4>> {
+    let a = |c: usize, r: usize| m[c][r];
+
+    let s0 = a(0, 0) * a(1, 1) - a(1, 0) * a(0, 1);
+    let s1 = a(0, 0) * a(1, 2) - a(1, 0) * a(0, 2);
+    let s2 = a(0, 0) * a(1, 3) - a(1, 0) * a(0, 3);
+    let s3 = a(0, 1) * a(1, 2) - a(1, 1) * a(0, 2);
+    let s4 = a(0, 1) * a(1, 3) - a(1, 1) * a(0, 3);
+    let s5 = a(0, 2) * a(1, 3) - a(1, 2) * a(0, 3);
+    let c5 = a(2, 2) * a(3, 3) - a(3, 2) * a(2, 3);
+    let c4 = a(2, 1) * a(3, 3) - a(3, 1) * a(2, 3);
+    let c3 = a(2, 1) * a(3, 2) - a(3, 1) * a(2, 2);
+    let c2 = a(2, 0) * a(3, 3) - a(3, 0) * a(2, 3);
+    let c1 = a(2, 0) * a(3, 2) - a(3, 0) * a(2, 2);
+    let c0 = a(2, 0) * a(3, 1) - a(3, 0) * a(2, 1);
+
+    let det = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+    if det.abs() < f32::EPSILON * f32::EPSILON {
+        return None;
+    }
+    let inv = 1.0 / det;
+
+    Some([
+        [
+            (a(1, 1) * c5 - a(1, 2) * c4 + a(1, 3) * c3) * inv,
+            (-a(0, 1) * c5 + a(0, 2) * c4 - a(0, 3) * c3) * inv,
+            (a(3, 1) * s5 - a(3, 2) * s4 + a(3, 3) * s3) * inv,
+            (-a(2, 1) * s5 + a(2, 2) * s4 - a(2, 3) * s3) * inv,
+        ],
+        [
+            (-a(1, 0) * c5 + a(1, 2) * c2 - a(1, 3) * c1) * inv,
+            (a(0, 0) * c5 - a(0, 2) * c2 + a(0, 3) * c1) * inv,
+            (-a(3, 0) * s5 + a(3, 2) * s2 - a(3, 3) * s1) * inv,
+            (a(2, 0) * s5 - a(2, 2) * s2 + a(2, 3) * s1) * inv,
+        ],
+        [
+            (a(1, 0) * c4 - a(1, 1) * c2 + a(1, 3) * c0) * inv,
+            (-a(0, 0) * c4 + a(0, 1) * c2 - a(0, 3) * c0) * inv,
+            (a(3, 0) * s4 - a(3, 1) * s2 + a(3, 3) * s0) * inv,
+            (-a(2, 0) * s4 + a(2, 1) * s2 - a(2, 3) * s0) * inv,
+        ],
+        [
+            (-a(1, 0) * c3 + a(1, 1) * c1 - a(1, 2) * c0) * inv,
+            (a(0, 0) * c3 - a(0, 1) * c1 + a(0, 2) * c0) * inv,
+            (-a(3, 0) * s3 + a(3, 1) * s1 - a(3, 2) * s0) * inv,
+            (a(2, 0) * s3 - a(2, 1) * s1 + a(2, 2) * s0) * inv,
+        ],
+    ])
+}
+
+/// Window coordinates of `point`, with y up from the bottom of `viewport`
+/// (`[x, y, width, height]`) and depth in `0..1` for GL's default `-1..1`
+/// clip depth. `None` behind the camera
+pub fn project(point: [f32; 3], view_proj: &Matrix<4, 4>, viewport: [i32; 4]) -> Option<[f32; 3]> {
+    let [x, y, z] = point;
+    let [x, y, z, w] = transform(view_proj, [x, y, z, 1.0]);
+    if w <= 0.0 {
+        return None;
+    }
+
+    let [vx, vy, vw, vh] = viewport.map(|v| v as f32);
+    Some([
+        vx + (x / w + 1.0) / 2.0 * vw,
+        vy + (y / w + 1.0) / 2.0 * vh,
+        (z / w + 1.0) / 2.0,
+    ])
+}
+
+/// World position under window coordinates, the inverse of `project`.
+/// Unprojecting depth 0 and 1 gives a pick ray through the pixel
+pub fn unproject(
+    [x, y, depth]: [f32; 3],
+    view_proj: &Matrix<4, 4>,
+    viewport: [i32; 4],
+) -> Option<[f32; 3]> {
+    let [vx, vy, vw, vh] = viewport.map(|v| v as f32);
+    let ndc = [
+        (x - vx) / vw * 2.0 - 1.0,
+        (y - vy) / vh * 2.0 - 1.0,
+        depth * 2.0 - 1.0,
+        1.0,
+    ];
+
+    let [x, y, z, w] = transform(&invert(view_proj)?, ndc);
+    (w != 0.0).then(|| [x / w, y / w, z / w])
+}
+
+pub fn add([ax, ay, az]: [f32; 3], [bx, by, bz]: [f32; 3]) -> [f32; 3] {
+    [ax + bx, ay + by, az + bz]
+}
+
+pub fn sub([ax, ay, az]: [f32; 3], [bx, by, bz]: [f32; 3]) -> [f32; 3] {
+    [ax - bx, ay - by, az - bz]
+}
+
+pub fn scale([x, y, z]: [f32; 3], s: f32) -> [f32; 3] {
+    [x * s, y * s, z * s]
+}
+
+pub fn dot([ax, ay, az]: [f32; 3], [bx, by, bz]: [f32; 3]) -> f32 {
+    ax * bx + ay * by + az * bz
+}
+
+pub fn cross([ax, ay, az]: [f32; 3], [bx, by, bz]: [f32; 3]) -> [f32; 3] {
+    [ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx]
+}
+
+pub fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = sqrt(dot(v, v));
+    if len == 0.0 {
+        v
+    } else {
+        scale(v, 1.0 / len)
+    }
+}
+
 pub type Spline = Vec<Bezier>;
 
 pub trait Subdivide {
@@ -38,6 +220,27 @@ impl From<&[[f32; 2]]> for Bezier {
     }
 }
 
+impl Bezier {
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.0
+    }
+
+    /// Control points of the equivalent cubic, elevating lines and quadratics
+    /// so a spline can be drawn as `Topology::Patches(4)`.
+    pub fn cubic(&self) -> Option<[[f32; 2]; 4]> {
+        let lerp = |[ax, ay]: [f32; 2], [bx, by]: [f32; 2], t: f32| {
+            [ax + (bx - ax) * t, ay + (by - ay) * t]
+        };
+
+        match *self.0.as_slice() {
+            [p0, p1] => Some([p0, lerp(p0, p1, 1.0 / 3.0), lerp(p0, p1, 2.0 / 3.0), p1]),
+            [p0, p1, p2] => Some([p0, lerp(p0, p1, 2.0 / 3.0), lerp(p2, p1, 2.0 / 3.0), p2]),
+            [p0, p1, p2, p3] => Some([p0, p1, p2, p3]),
+            _ => None,
+        }
+    }
+}
+
 impl Subdivide for Bezier {
     fn subdivide(&self, n: usize) -> Vec<[f32; 2]> {
         (0..=n)
@@ -61,13 +264,131 @@ impl Subdivide for Bezier {
     }
 }
 
-fn powf(a: f32, b: f32) -> f32 {
+pub fn powf(a: f32, b: f32) -> f32 {
     unsafe { powf32(a, b) }
 }
 
+pub fn sin(x: f32) -> f32 {
+    unsafe { sinf32(x) }
+}
+
+pub fn cos(x: f32) -> f32 {
+    unsafe { cosf32(x) }
+}
+
+pub fn sqrt(x: f32) -> f32 {
+    unsafe { sqrtf32(x) }
+}
+
+pub fn tan(x: f32) -> f32 {
+    sin(x) / cos(x)
+}
+
+/// Angle of `[x, y]` from the positive x axis in `-PI..=PI`, accurate to
+/// about 1e-5 radians
+pub fn atan2(y: f32, x: f32) -> f32 {
+    use core::f32::consts::{FRAC_PI_2, PI};
+
+    if x == 0.0 && y == 0.0 {
+        return 0.0;
+    }
+
+    // Minimax polynomial for atan on 0..=1
+    let t = x.abs().min(y.abs()) / x.abs().max(y.abs());
+    let s = t * t;
+    let mut angle = t
+        * (0.99997726
+            + s * (-0.33262347
+                + s * (0.19354346 + s * (-0.11643287 + s * (0.05265332 - s * 0.0117212)))));
+
+    if y.abs() > x.abs() {
+        angle = FRAC_PI_2 - angle;
+    }
+    if x < 0.0 {
+        angle = PI - angle;
+    }
+    if y < 0.0 {
+        -angle
+    } else {
+        angle
+    }
+}
+
 fn factorial(n: usize) -> usize {
     match n {
         0 | 1 => 1,
         _ => factorial(n - 1) * n,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near<const N: usize>(a: [f32; N], b: [f32; N]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4)
+    }
+
+    fn camera() -> Matrix<4, 4> {
+        let view = look_at([3.0, 2.0, 5.0], [0.0, 0.5, 0.0], [0.0, 1.0, 0.0]);
+        multiply(&perspective(1.0, 1.5, 0.1, 100.0), &view)
+    }
+
+    #[test]
+    fn inverse() {
+        const IDENTITY: Matrix<4, 4> = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+
+        for m in [camera(), ortho([-2.0, -1.0], [6.0, 3.0])] {
+            let product = multiply(&invert(&m).unwrap(), &m);
+            assert!(product.iter().zip(IDENTITY).all(|(a, b)| near(*a, b)));
+        }
+
+        let mut singular = IDENTITY;
+        singular[2] = singular[1];
+        assert_eq!(invert(&singular), None);
+    }
+
+    #[test]
+    fn look_at_eye() {
+        let view = look_at([1.0, 2.0, 3.0], [1.0, 2.0, -7.0], [0.0, 1.0, 0.0]);
+        assert!(near(
+            transform(&view, [1.0, 2.0, 3.0, 1.0]),
+            [0.0, 0.0, 0.0, 1.0]
+        ));
+        // The target ends up straight ahead, down -z
+        assert!(near(
+            transform(&view, [1.0, 2.0, -7.0, 1.0]),
+            [0.0, 0.0, -10.0, 1.0]
+        ));
+    }
+
+    #[test]
+    fn perspective_depth() {
+        let proj = perspective(1.0, 1.0, 0.5, 20.0);
+        let depth = |z: f32| {
+            let [_, _, z, w] = transform(&proj, [0.0, 0.0, z, 1.0]);
+            z / w
+        };
+        assert!((depth(-0.5) + 1.0).abs() < 1e-4);
+        assert!((depth(-20.0) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn project_round_trip() {
+        let viewport = [10, 20, 640, 480];
+        let point = [0.25, 1.0, -0.5];
+
+        let window = project(point, &camera(), viewport).unwrap();
+        assert!(window[2] > 0.0 && window[2] < 1.0);
+        let back = unproject(window, &camera(), viewport).unwrap();
+        assert!(back.iter().zip(point).all(|(a, b)| (a - b).abs() < 1e-3));
+
+        // Behind the camera
+        assert_eq!(project([6.0, 4.0, 10.0], &camera(), viewport), None);
+    }
+}
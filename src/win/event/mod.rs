@@ -12,13 +12,104 @@ pub enum Event {
     TextInput {
         text: [i8; 32],
     },
+    MouseMotion {
+        pos: [i32; 2],
+        /// Movement since the last motion event, the only meaningful part
+        /// in relative mouse mode
+        rel: [i32; 2],
+        /// Bit `n - 1` set while button `n` is held, see `MouseButton`
+        buttons: u32,
+    },
+    MouseButton {
+        down: bool,
+        button: MouseButton,
+        pos: [i32; 2],
+        clicks: u8,
+    },
+    MouseWheel {
+        /// Positive y scrolls away from the user
+        scroll: [f32; 2],
+    },
+    /// The window's drawable area changed size
+    Resized {
+        size: [i32; 2],
+    },
 }
 
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum KeyCode {
     CapsLock = SDLK_CAPSLOCK,
-    None,
+    W = SDLK_w,
+    A = SDLK_a,
+    S = SDLK_s,
+    D = SDLK_d,
+    Q = SDLK_q,
+    E = SDLK_e,
+    Space = SDLK_SPACE,
+    LShift = SDLK_LSHIFT,
+    LCtrl = SDLK_LCTRL,
+    Escape = SDLK_ESCAPE,
+    Return = SDLK_RETURN,
+    Backspace = SDLK_BACKSPACE,
+    Tab = SDLK_TAB,
+    Up = SDLK_UP,
+    Down = SDLK_DOWN,
+    Left = SDLK_LEFT,
+    Right = SDLK_RIGHT,
+    None = SDLK_UNKNOWN,
+}
+
+impl KeyCode {
+    #[allow(non_upper_case_globals)]
+    fn from_sym(sym: u32) -> Self {
+        match sym {
+            SDLK_CAPSLOCK => KeyCode::CapsLock,
+            SDLK_w => KeyCode::W,
+            SDLK_a => KeyCode::A,
+            SDLK_s => KeyCode::S,
+            SDLK_d => KeyCode::D,
+            SDLK_q => KeyCode::Q,
+            SDLK_e => KeyCode::E,
+            SDLK_SPACE => KeyCode::Space,
+            SDLK_LSHIFT => KeyCode::LShift,
+            SDLK_LCTRL => KeyCode::LCtrl,
+            SDLK_ESCAPE => KeyCode::Escape,
+            SDLK_RETURN => KeyCode::Return,
+            SDLK_BACKSPACE => KeyCode::Backspace,
+            SDLK_TAB => KeyCode::Tab,
+            SDLK_UP => KeyCode::Up,
+            SDLK_DOWN => KeyCode::Down,
+            SDLK_LEFT => KeyCode::Left,
+            SDLK_RIGHT => KeyCode::Right,
+            _ => KeyCode::None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    Other(u8),
+}
+
+impl MouseButton {
+    /// Bit of this button in `Event::MouseMotion::buttons`, 0 for a button
+    /// number the mask can't hold
+    pub fn mask(self) -> u32 {
+        let n = match self {
+            MouseButton::Left => SDL_BUTTON_LEFT,
+            MouseButton::Middle => SDL_BUTTON_MIDDLE,
+            MouseButton::Right => SDL_BUTTON_RIGHT,
+            MouseButton::Other(n) => n as u32,
+        };
+
+        n.checked_sub(1)
+            .and_then(|bit| 1u32.checked_shl(bit))
+            .unwrap_or(0)
+    }
 }
 
 pub struct EventChannel;
@@ -33,18 +124,27 @@ impl EventChannel {
             }
         }
     }
+
+    /// Hide and capture the cursor, reporting only relative motion, for
+    /// mouse look
+    pub fn relative_mouse(&self, enable: bool) {
+        unsafe {
+            SDL_SetRelativeMouseMode(if enable { SDL_TRUE } else { SDL_FALSE });
+        }
+    }
 }
 
 impl Iterator for EventChannel {
     type Item = Event;
 
+    /// The next pending event, skipping ones without an `Event` equivalent
     fn next(&mut self) -> Option<Self::Item> {
         unsafe {
             let mut event = core::mem::zeroed();
 
-            match SDL_PollEvent(&mut event) {
-                1 => match event.type_ {
-                    SDL_QUIT => Some(Event::Quit),
+            while SDL_PollEvent(&mut event) == 1 {
+                match event.type_ {
+                    SDL_QUIT => return Some(Event::Quit),
                     SDL_KEYDOWN | SDL_KEYUP => {
                         let SDL_KeyboardEvent {
                             type_,
@@ -53,32 +153,87 @@ impl Iterator for EventChannel {
                             ..
                         } = event.key;
 
-                        let down = match type_ {
-                            SDL_KEYDOWN => true,
-                            SDL_KEYUP => false,
-                            _ => panic!(),
-                        };
-
-                        Some(Event::Keyboard {
-                            down,
+                        return Some(Event::Keyboard {
+                            down: type_ == SDL_KEYDOWN,
                             timestamp,
-                            sym: match sym as u32 {
-                                SDLK_CAPSLOCK => KeyCode::CapsLock,
-                                _ => KeyCode::None,
-                            },
+                            sym: KeyCode::from_sym(sym as u32),
                             mod_: mod_ as _,
-                        })
+                        });
                     }
 
                     SDL_TEXTINPUT => {
                         let SDL_TextInputEvent { text, .. } = event.text;
 
-                        Some(Event::TextInput { text: text })
+                        return Some(Event::TextInput { text });
+                    }
+
+                    SDL_MOUSEMOTION => {
+                        let SDL_MouseMotionEvent {
+                            state,
+                            x,
+                            y,
+                            xrel,
+                            yrel,
+                            ..
+                        } = event.motion;
+
+                        return Some(Event::MouseMotion {
+                            pos: [x, y],
+                            rel: [xrel, yrel],
+                            buttons: state,
+                        });
+                    }
+
+                    SDL_MOUSEBUTTONDOWN | SDL_MOUSEBUTTONUP => {
+                        let SDL_MouseButtonEvent {
+                            type_,
+                            button,
+                            clicks,
+                            x,
+                            y,
+                            ..
+                        } = event.button;
+
+                        return Some(Event::MouseButton {
+                            down: type_ == SDL_MOUSEBUTTONDOWN,
+                            button: match button as u32 {
+                                SDL_BUTTON_LEFT => MouseButton::Left,
+                                SDL_BUTTON_MIDDLE => MouseButton::Middle,
+                                SDL_BUTTON_RIGHT => MouseButton::Right,
+                                _ => MouseButton::Other(button),
+                            },
+                            pos: [x, y],
+                            clicks,
+                        });
+                    }
+
+                    SDL_MOUSEWHEEL => {
+                        let SDL_MouseWheelEvent {
+                            preciseX, preciseY, ..
+                        } = event.wheel;
+
+                        return Some(Event::MouseWheel {
+                            scroll: [preciseX, preciseY],
+                        });
                     }
-                    _ => None,
-                },
-                _ => None,
+
+                    SDL_WINDOWEVENT
+                        if event.window.event as u32 == SDL_WINDOWEVENT_SIZE_CHANGED =>
+                    {
+                        // The event's size is in window coordinates, which
+                        // differ from pixels on high DPI displays
+                        let window = SDL_GetWindowFromID(event.window.windowID);
+                        let [mut w, mut h] = [0, 0];
+                        SDL_GL_GetDrawableSize(window, &mut w, &mut h);
+
+                        return Some(Event::Resized { size: [w, h] });
+                    }
+
+                    _ => {}
+                }
             }
+
+            None
         }
     }
 }
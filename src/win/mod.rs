@@ -1,4 +1,5 @@
 pub mod event;
+pub mod time;
 pub mod window;
 
 #[allow(
@@ -0,0 +1,9 @@
+use super::sdl::*;
+
+/// Monotonic nanoseconds since an arbitrary point
+pub fn now() -> u64 {
+    unsafe {
+        let ticks = SDL_GetPerformanceCounter() as u128;
+        (ticks * 1_000_000_000 / SDL_GetPerformanceFrequency() as u128) as u64
+    }
+}
@@ -1,4 +1,4 @@
-use super::sdl::*;
+use {super::sdl::*, crate::gfx::state::RenderState};
 
 pub struct Window {
     window: *mut SDL_Window,
@@ -7,6 +7,15 @@ pub struct Window {
 
 impl Window {
     pub fn new(name: &core::ffi::CStr, w: i32, h: i32) -> Result<Self, ()> {
+        Self::with_flags(name, w, h, SDL_WINDOW_SHOWN | SDL_WINDOW_RESIZABLE)
+    }
+
+    /// Window that is never shown, for an offscreen GL context
+    pub fn hidden(name: &core::ffi::CStr, w: i32, h: i32) -> Result<Self, ()> {
+        Self::with_flags(name, w, h, SDL_WINDOW_HIDDEN)
+    }
+
+    fn with_flags(name: &core::ffi::CStr, w: i32, h: i32, flags: u32) -> Result<Self, ()> {
         unsafe {
             SDL_InitSubSystem(SDL_INIT_VIDEO);
 
@@ -16,17 +25,17 @@ impl Window {
                 SDL_WINDOWPOS_UNDEFINED_MASK as _,
                 w,
                 h,
-                SDL_WINDOW_OPENGL | SDL_WINDOW_SHOWN,
+                SDL_WINDOW_OPENGL | flags,
             );
 
             if !window.is_null() {
                 SDL_GL_SetAttribute(SDL_GL_CONTEXT_MAJOR_VERSION, 4);
                 SDL_GL_SetAttribute(SDL_GL_CONTEXT_MINOR_VERSION, 6);
 
-                Ok(Self {
-                    window,
-                    ctx: SDL_GL_CreateContext(window),
-                })
+                let ctx = SDL_GL_CreateContext(window);
+                RenderState::invalidate();
+
+                Ok(Self { window, ctx })
             } else {
                 SDL_QuitSubSystem(SDL_INIT_VIDEO);
                 Err(())
@@ -34,6 +43,16 @@ impl Window {
         }
     }
 
+    /// Size of the drawable area in pixels
+    pub fn size(&self) -> [i32; 2] {
+        let [mut w, mut h] = [0, 0];
+        unsafe {
+            SDL_GL_GetDrawableSize(self.window, &mut w, &mut h);
+        }
+
+        [w, h]
+    }
+
     pub fn swap(&self) {
         unsafe {
             SDL_GL_SwapWindow(self.window);
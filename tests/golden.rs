@@ -0,0 +1,92 @@
+#![cfg(feature = "golden")]
+use hex_ln::{
+    gfx::{
+        buffer::Usage,
+        mesh::{Mesh, Topology},
+        program::Program,
+        sdf::{BoxStyle, Fill, Shadow},
+        shader::{POS2D_TEX2D, TEX2D},
+        Resource,
+    },
+    golden::Golden,
+    gui::{font::Font, widget::TextBox},
+};
+
+const REFERENCES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+#[test]
+fn glyph_rasterization() {
+    let golden = Golden::new(REFERENCES, [64, 64]);
+    let font = Font::default();
+
+    let program = Program::new(POS2D_TEX2D, TEX2D);
+
+    for ch in [b'A', b'g', b'Q', b'8'] {
+        let [min, max] = font
+            .get(ch)
+            .and_then(|glyph| glyph.region)
+            .expect("glyph has no outline")
+            .uv;
+
+        let quad = Mesh::new(
+            &[
+                ([-1.0, 1.0], [min[0], max[1]]),
+                ([1.0, 1.0], max),
+                ([-1.0, -1.0], min),
+                ([1.0, -1.0], [max[0], min[1]]),
+            ],
+            Usage::StaticDraw,
+            Topology::TriStrip,
+        );
+
+        golden
+            .render(&format!("glyph_{}", ch as char), |_| {
+                program.bind();
+                font.texture().bind();
+                quad.draw();
+            })
+            .unwrap_or_else(|err| panic!("'{}': {}", ch as char, err));
+    }
+}
+
+#[test]
+fn text_layout() {
+    let golden = Golden::new(REFERENCES, [256, 64]);
+    let font = Font::default();
+
+    let mut text = TextBox::new();
+    text.update("Greetz!\n\tit builds:D");
+
+    golden
+        .render("textbox", |size| text.render(size, &font, 1.5))
+        .unwrap_or_else(|err| panic!("{}", err));
+}
+
+#[test]
+fn decorated_text_box() {
+    let golden = Golden::new(REFERENCES, [256, 96]);
+    let font = Font::default();
+
+    let mut text = TextBox::new();
+    text.set_style(Some(BoxStyle {
+        radii: [4.0, 12.0, 4.0, 12.0],
+        fill: Fill::Linear {
+            start: [0.0, 0.0],
+            end: [0.0, 1.0],
+            colors: [[0x20, 0x20, 0x30, 0xFF], [0x40, 0x40, 0x60, 0xFF]],
+        },
+        border: 2.0,
+        border_color: [0xC0, 0xC0, 0xFF, 0xFF],
+        shadow: Some(Shadow {
+            offset: [2.0, -2.0],
+            blur: 6.0,
+            spread: 0.0,
+            color: [0, 0, 0, 0x80],
+        }),
+    }));
+    text.update("Greetz!");
+
+    golden
+        .render("textbox_decorated", |size| text.render(size, &font, 1.5))
+        .unwrap_or_else(|err| panic!("{}", err));
+}